@@ -0,0 +1,387 @@
+//! Minimal runtime wiring the DID pallet with balances, timestamp and its `DidApi` runtime API,
+//! the executable reference of the `HOWTO.md` integration for parachain and solo chain teams.
+//!
+//! The `std` feature builds the native runtime, its Wasm blob with `substrate-wasm-builder` and
+//! `rpc`, serving the `DidApi` to resolvers over JSON-RPC. Without it the crate is the `no_std`
+//! runtime the Wasm blob is built from.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![recursion_limit = "256"]
+
+#[cfg(feature = "std")]
+include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
+
+#[cfg(feature = "std")]
+pub mod rpc;
+
+use frame_support::{
+    construct_runtime, parameter_types,
+    traits::{ConstBool, ConstU128, ConstU16, ConstU32, ConstU64, Everything, KeyOwnerProofSystem},
+    weights::{constants::RocksDbWeight, Weight},
+    PalletId,
+};
+use frame_system::{EnsureRoot, EnsureRootWithSuccess};
+use pallet_did::{
+    document::DidDocument,
+    types::{
+        ActivityStatus, AttestationStatus, AttributeMetadata, AttributeTransaction, Delegate, Page,
+        ReadQuery,
+    },
+};
+use sp_api::impl_runtime_apis;
+use sp_consensus_babe::{AllowedSlots, BabeEpochConfiguration};
+use sp_core::{crypto::KeyTypeId, H256};
+use sp_runtime::{
+    create_runtime_str, generic,
+    traits::{
+        AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, Verify,
+    },
+    DispatchError, MultiSignature,
+};
+use sp_std::prelude::*;
+use sp_version::RuntimeVersion;
+
+pub type Signature = MultiSignature;
+pub type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
+pub type Balance = u128;
+pub type BlockNumber = u32;
+pub type Index = u32;
+pub type Moment = u64;
+
+pub type Address = sp_runtime::MultiAddress<AccountId, ()>;
+pub type Header = generic::Header<BlockNumber, BlakeTwo256>;
+pub type Block = generic::Block<Header, UncheckedExtrinsic>;
+pub type SignedExtra = (frame_system::CheckNonce<Runtime>, frame_system::CheckWeight<Runtime>);
+pub type UncheckedExtrinsic =
+    generic::UncheckedExtrinsic<Address, RuntimeCall, Signature, SignedExtra>;
+pub type Executive = frame_executive::Executive<
+    Runtime,
+    Block,
+    frame_system::ChainContext<Runtime>,
+    Runtime,
+    AllPalletsWithSystem,
+>;
+
+pub const MILLISECS_PER_BLOCK: u64 = 6_000;
+pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
+pub const HOURS: BlockNumber = 60 * MINUTES;
+pub const DAYS: BlockNumber = 24 * 60 * MINUTES;
+pub const DOLLARS: Balance = 1_000_000_000_000;
+pub const EPOCH_DURATION_IN_SLOTS: u64 = 4 * HOURS as u64;
+
+/// Epoch configuration of the BABE genesis, with a primary slot claimed 1 in 4 slots.
+pub const BABE_GENESIS_EPOCH_CONFIG: BabeEpochConfiguration = BabeEpochConfiguration {
+    c: (1, 4),
+    allowed_slots: AllowedSlots::PrimaryAndSecondaryPlainSlots,
+};
+
+#[sp_version::runtime_version]
+pub const VERSION: RuntimeVersion = RuntimeVersion {
+    spec_name: create_runtime_str!("did-example"),
+    impl_name: create_runtime_str!("did-example"),
+    authoring_version: 1,
+    spec_version: 1,
+    impl_version: 1,
+    apis: RUNTIME_API_VERSIONS,
+    transaction_version: 1,
+    state_version: 1,
+};
+
+parameter_types! {
+    pub const Version: RuntimeVersion = VERSION;
+    /// Keyless identity controlled by the root origin.
+    pub TreasuryAccount: AccountId = PalletId(*b"py/trsry").into_account_truncating();
+    pub DelegateValidityDefaults: Vec<(Vec<u8>, BlockNumber)> = vec![
+        (b"x25519VerificationKey2022".to_vec(), 180 * DAYS),
+        (b"SessionKey".to_vec(), DAYS),
+    ];
+    pub ReservedNamespaces: Vec<Vec<u8>> = vec![b"did/".to_vec(), b"svc/".to_vec(), b"sys/".to_vec()];
+    pub DormancyPeriod: Option<BlockNumber> = Some(365 * DAYS);
+    pub ForeignAttributePrefix: [u8; 32] = {
+        let mut prefix = [0u8; 32];
+        prefix[..16].copy_from_slice(&sp_io::hashing::twox_128(b"DID"));
+        prefix[16..].copy_from_slice(&sp_io::hashing::twox_128(b"AttributeOf"));
+        prefix
+    };
+}
+
+impl frame_system::Config for Runtime {
+    type BaseCallFilter = Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = RocksDbWeight;
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Index = Index;
+    type BlockNumber = BlockNumber;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = AccountIdLookup<AccountId, ()>;
+    type Header = Header;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU32<2_400>;
+    type Version = Version;
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ConstU16<42>;
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_timestamp::Config for Runtime {
+    type Moment = Moment;
+    type OnTimestampSet = Babe;
+    type MinimumPeriod = ConstU64<{ MILLISECS_PER_BLOCK / 2 }>;
+    type WeightInfo = ();
+}
+
+impl pallet_balances::Config for Runtime {
+    type Balance = Balance;
+    type DustRemoval = ();
+    type RuntimeEvent = RuntimeEvent;
+    type ExistentialDeposit = ConstU128<500>;
+    type AccountStore = System;
+    type WeightInfo = ();
+    type MaxLocks = ConstU32<50>;
+    type MaxReserves = ConstU32<50>;
+    type ReserveIdentifier = [u8; 8];
+}
+
+impl pallet_babe::Config for Runtime {
+    type EpochDuration = ConstU64<EPOCH_DURATION_IN_SLOTS>;
+    type ExpectedBlockTime = ConstU64<MILLISECS_PER_BLOCK>;
+    type EpochChangeTrigger = pallet_babe::SameAuthoritiesForever;
+    type DisabledValidators = ();
+    // Without a session pallet there are no equivocation reports.
+    type KeyOwnerProofSystem = ();
+    type KeyOwnerProof = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+        KeyTypeId,
+        pallet_babe::AuthorityId,
+    )>>::Proof;
+    type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+        KeyTypeId,
+        pallet_babe::AuthorityId,
+    )>>::IdentificationTuple;
+    type HandleEquivocation = ();
+    type WeightInfo = ();
+    type MaxAuthorities = ConstU32<32>;
+}
+
+impl pallet_did::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Public = sp_runtime::MultiSigner;
+    type Signature = Signature;
+    type KeyHasher = BlakeTwo256;
+    type Moment = Moment;
+    type Timestamp = Timestamp;
+    type ControllerOrigin = EnsureRootWithSuccess<AccountId, TreasuryAccount>;
+    type DefaultAttributeValidity = ConstU32<{ 30 * DAYS }>;
+    type MaxAttributeValidity = ConstU32<{ 365 * DAYS }>;
+    type DelegateValidityDefaults = DelegateValidityDefaults;
+    type DefaultDelegateValidity = ConstU32<{ 180 * DAYS }>;
+    type MaxDelegateRenewals = ConstU32<64>;
+    type ExplicitOwnershipOnly = ConstBool<false>;
+    type ReservedNamespaces = ReservedNamespaces;
+    type Currency = Balances;
+    type ProfileDeposit = ConstU128<{ 10 * DOLLARS }>;
+    type NamespaceDeposit = ConstU128<{ 100 * DOLLARS }>;
+    type MaxDisplayNameLength = ConstU32<64>;
+    type HandleDeposit = ConstU128<{ 10 * DOLLARS }>;
+    type MaxHandleLength = ConstU32<32>;
+    type AccessEscrowPeriod = ConstU32<{ 7 * DAYS }>;
+    type ForeignStateRoots = ();
+    type ForeignAttributePrefix = ForeignAttributePrefix;
+    type ContractAuthorizer = ();
+    type WeightInfo = pallet_did::weights::SubstrateWeight<Runtime>;
+    type EmitMetaTransactionRejections = ConstBool<true>;
+    type RequireAttestationConsent = ConstBool<false>;
+    type FeelessFirstWrites = ConstU32<10>;
+    type MaxFeelessWriteBytes = ConstU32<128>;
+    // The chain starts at the current storage version: on its later upgrades, these migrations
+    // are skipped if they can not run twice, and otherwise find nothing to migrate.
+    type Migration = (
+        (
+            pallet_did::migrations::v1::TranslateOriginalLayout<Runtime>,
+            pallet_did::migrations::v4::RekeyByIdentity<Runtime>,
+        ),
+        (
+            (
+                pallet_did::migrations::BackfillAttributeMetadata<Runtime>,
+                pallet_did::migrations::IndexDelegatesByType<Runtime>,
+            ),
+            (
+                pallet_did::migrations::BackfillAttributeDigests<Runtime>,
+                (
+                    pallet_did::migrations::TrimBoundedStorage<Runtime>,
+                    (
+                        pallet_did::migrations::PrunePresentationNonces<Runtime>,
+                        pallet_did::migrations::CountRescuers<Runtime>,
+                    ),
+                ),
+            ),
+        ),
+    );
+    type MigrationStepSize = ConstU32<1_000>;
+    type DormancyPeriod = DormancyPeriod;
+    type ReapGracePeriod = ConstU32<{ 30 * DAYS }>;
+    type ReapBounty = ConstU128<{ DOLLARS }>;
+    type MaxWatchers = ConstU32<16>;
+    type ExpiryNotice = ConstU32<{ 7 * DAYS }>;
+    type MaxExpiryAlerts = ConstU32<256>;
+    type MaxDependents = ConstU32<32>;
+    type MaxBytesPerDid = ConstU32<{ 64 * 1024 }>;
+    // The VRF output of an epoch ago, that no block author can bias. A parachain uses the
+    // randomness of the relay chain instead.
+    type Randomness = pallet_babe::RandomnessFromOneEpochAgo<Runtime>;
+    type ChallengeTtl = ConstU32<{ 10 * MINUTES }>;
+    type MaxRecoveryVouches = ConstU32<16>;
+    type AttestationDeposit = ConstU128<{ DOLLARS }>;
+    type MaxPendingAttestations = ConstU32<32>;
+    type CredentialDeposit = ConstU128<{ 10 * DOLLARS }>;
+    type MaxCredentialsPerSubject = ConstU32<32>;
+    type MaxForeignStateRootAge = ConstU32<{ 10 * MINUTES }>;
+    type WatcherDeposit = ConstU128<{ DOLLARS / 10 }>;
+    type MaxNonceExpiries = ConstU32<1_024>;
+    type MaxRescuers = ConstU32<16>;
+    type AdminOrigin = EnsureRoot<AccountId>;
+}
+
+construct_runtime!(
+    pub enum Runtime where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system,
+        Babe: pallet_babe,
+        Timestamp: pallet_timestamp,
+        Balances: pallet_balances,
+        DID: pallet_did,
+    }
+);
+
+impl_runtime_apis! {
+    impl sp_api::Core<Block> for Runtime {
+        fn version() -> RuntimeVersion {
+            VERSION
+        }
+
+        fn execute_block(block: Block) {
+            Executive::execute_block(block);
+        }
+
+        fn initialize_block(header: &<Block as BlockT>::Header) {
+            Executive::initialize_block(header)
+        }
+    }
+
+    impl pallet_did::runtime_api::DidApi<
+        Block,
+        AccountId,
+        BlockNumber,
+        pallet_did::Call<Runtime>,
+        Balance,
+        Signature,
+        Moment,
+    > for Runtime {
+        fn nonces_of(identity: AccountId) -> Vec<(Vec<u8>, u64)> {
+            DID::nonces_of(&identity)
+        }
+
+        fn attributes_of(
+            identity: AccountId,
+            cursor: Option<Vec<u8>>,
+            max: u32,
+        ) -> Page<(Vec<u8>, Vec<u8>, BlockNumber)> {
+            DID::attributes_of(&identity, cursor, max)
+        }
+
+        fn delegates_of(
+            identity: AccountId,
+            cursor: Option<Vec<u8>>,
+            max: u32,
+        ) -> Page<(Vec<u8>, AccountId, Delegate<BlockNumber>)> {
+            DID::delegates_of(&identity, cursor, max)
+        }
+
+        fn identities_of_owner(
+            owner: AccountId,
+            cursor: Option<Vec<u8>>,
+            max: u32,
+        ) -> Page<AccountId> {
+            DID::identities_of_owner(&owner, cursor, max)
+        }
+
+        fn next_meta_nonce(identity: AccountId) -> u64 {
+            DID::next_meta_nonce(&identity)
+        }
+
+        fn dry_run_execute(
+            transaction: AttributeTransaction<Signature, AccountId>,
+        ) -> Result<frame_support::dispatch::PostDispatchInfo, DispatchError> {
+            DID::dry_run_execute(transaction)
+        }
+
+        fn inactive_delegates(
+            identity: AccountId,
+            inactive_for: BlockNumber,
+        ) -> Vec<(Vec<u8>, AccountId, BlockNumber)> {
+            DID::inactive_delegates(&identity, inactive_for)
+        }
+
+        fn did_document(identity: AccountId) -> DidDocument {
+            DID::did_document(&identity)
+        }
+
+        fn attribute_metadata(
+            identity: AccountId,
+            name: Vec<u8>,
+        ) -> Option<AttributeMetadata<BlockNumber, Moment>> {
+            DID::attribute_metadata(&identity, &name)
+        }
+
+        fn attestation_status(identity: AccountId, name: Vec<u8>) -> Option<AttestationStatus> {
+            DID::attestation_status(&identity, &name)
+        }
+
+        fn verify_claims_batch(claims: Vec<(AccountId, Vec<u8>, Vec<u8>)>) -> Vec<bool> {
+            DID::verify_claims_batch(claims)
+        }
+
+        fn document_hash(identity: AccountId) -> [u8; 32] {
+            DID::document_hash(identity)
+        }
+
+        fn identity_by_handle(handle: Vec<u8>) -> Option<AccountId> {
+            DID::identity_by_handle(&handle)
+        }
+
+        fn credentials_of(subject: AccountId) -> Vec<([u8; 32], AttestationStatus)> {
+            DID::credentials_of(&subject)
+                .into_iter()
+                .filter_map(|(id, _)| Some((id, DID::credential_status(&subject, &id)?)))
+                .collect()
+        }
+
+        fn activity_status(identity: AccountId) -> ActivityStatus<BlockNumber> {
+            DID::activity_status(&identity)
+        }
+
+        fn error_code(error: DispatchError) -> Option<(u16, Vec<u8>)> {
+            pallet_did::error_codes::error_code_of::<Runtime>(&error)
+                .map(|(code, name)| (code, name.as_bytes().to_vec()))
+        }
+
+        fn operation_cost(who: AccountId, call: pallet_did::Call<Runtime>) -> (Weight, Balance) {
+            DID::operation_cost(&who, &call)
+        }
+
+        fn query_cost(query: ReadQuery) -> Weight {
+            DID::query_cost(query)
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! JSON-RPC methods of a node resolving DIDs over the `DidApi` runtime API of the example runtime.
+//!
+//! Merged in the node RPC module with `Did::new(client.clone()).into_rpc()`.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Encode;
+use jsonrpsee::{
+    core::RpcResult,
+    proc_macros::rpc,
+    types::error::{CallError, ErrorObject},
+};
+use pallet_did::runtime_api::DidApi as DidRuntimeApi;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H256};
+use sp_runtime::traits::Block as BlockT;
+
+use crate::{AccountId, Balance, BlockNumber, Moment, Runtime, Signature};
+
+/// Error code of a failed runtime API call.
+pub const RUNTIME_ERROR: i32 = 1;
+
+#[rpc(client, server)]
+pub trait DidApi<BlockHash, AccountId> {
+    /// DID Document of an identity, in its compact SCALE encoding.
+    #[method(name = "did_document")]
+    fn did_document(&self, identity: AccountId, at: Option<BlockHash>) -> RpcResult<Bytes>;
+
+    /// Rolling hash of the updates of an identity.
+    #[method(name = "did_documentHash")]
+    fn document_hash(&self, identity: AccountId, at: Option<BlockHash>) -> RpcResult<H256>;
+
+    /// Identity a readable handle refers to, if claimed.
+    #[method(name = "did_identityByHandle")]
+    fn identity_by_handle(&self, handle: Bytes, at: Option<BlockHash>) -> RpcResult<Option<AccountId>>;
+
+    /// Whether each `(identity, name, value)` attribute claim is valid, in order.
+    #[method(name = "did_verifyClaims")]
+    fn verify_claims(
+        &self,
+        claims: Vec<(AccountId, Bytes, Bytes)>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<bool>>;
+}
+
+/// `DidApiServer` of a client.
+pub struct Did<C, Block> {
+    client: Arc<C>,
+    _marker: PhantomData<Block>,
+}
+
+impl<C, Block> Did<C, Block> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client, _marker: Default::default() }
+    }
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> jsonrpsee::core::Error {
+    CallError::Custom(ErrorObject::owned(
+        RUNTIME_ERROR,
+        "Unable to query the DID runtime API.",
+        Some(format!("{:?}", e)),
+    ))
+    .into()
+}
+
+impl<C, Block> DidApiServer<<Block as BlockT>::Hash, AccountId> for Did<C, Block>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: DidRuntimeApi<
+        Block,
+        AccountId,
+        BlockNumber,
+        pallet_did::Call<Runtime>,
+        Balance,
+        Signature,
+        Moment,
+    >,
+{
+    fn did_document(&self, identity: AccountId, at: Option<Block::Hash>) -> RpcResult<Bytes> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        let document =
+            self.client.runtime_api().did_document(at, identity).map_err(runtime_error)?;
+        Ok(document.encode().into())
+    }
+
+    fn document_hash(&self, identity: AccountId, at: Option<Block::Hash>) -> RpcResult<H256> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        let hash =
+            self.client.runtime_api().document_hash(at, identity).map_err(runtime_error)?;
+        Ok(hash.into())
+    }
+
+    fn identity_by_handle(
+        &self,
+        handle: Bytes,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Option<AccountId>> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client.runtime_api().identity_by_handle(at, handle.to_vec()).map_err(runtime_error)
+    }
+
+    fn verify_claims(
+        &self,
+        claims: Vec<(AccountId, Bytes, Bytes)>,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Vec<bool>> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        let claims = claims
+            .into_iter()
+            .map(|(identity, name, value)| (identity, name.0, value.0))
+            .collect();
+        self.client.runtime_api().verify_claims_batch(at, claims).map_err(runtime_error)
+    }
+}
@@ -0,0 +1,102 @@
+//! Resolution over the `DidApi` runtime API, called by name with SCALE encoded arguments as
+//! `state_call` and the node RPC call it.
+
+use codec::{Decode, Encode};
+use frame_support::assert_ok;
+use pallet_did::{
+    document::{did_of, DidDocument},
+    types::{KeyType, Validity, SIGNING_DELEGATE_TYPE},
+};
+use pallet_did_runtime_integration::{
+    api, AccountId, BabeConfig, Balances, BalancesConfig, GenesisConfig, RuntimeOrigin, System,
+    BABE_GENESIS_EPOCH_CONFIG, DID, DOLLARS,
+};
+use sp_core::{sr25519, Pair};
+use sp_runtime::{traits::IdentifyAccount, BuildStorage, MultiSigner};
+
+fn account(seed: &str) -> AccountId {
+    let pair = sr25519::Pair::from_string(&format!("//{}", seed), None)
+        .expect("static values are valid; qed");
+    MultiSigner::from(pair.public()).into_account()
+}
+
+fn new_ext() -> sp_io::TestExternalities {
+    let storage = GenesisConfig {
+        balances: BalancesConfig { balances: vec![(account("Alice"), 1_000 * DOLLARS)] },
+        babe: BabeConfig { authorities: vec![], epoch_config: Some(BABE_GENESIS_EPOCH_CONFIG) },
+        ..Default::default()
+    }
+    .build_storage()
+    .unwrap();
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
+
+/// Calls a method of the `DidApi` the way a node does.
+fn runtime_api<R: Decode>(method: &str, args: impl Encode) -> R {
+    let output = api::dispatch(&format!("DidApi_{}", method), &args.encode())
+        .expect("the runtime implements the DidApi; qed");
+    R::decode(&mut &output[..]).unwrap()
+}
+
+#[test]
+fn resolves_documents_over_the_runtime_api() {
+    new_ext().execute_with(|| {
+        let alice = account("Alice");
+        let bob = account("Bob");
+        let hash: [u8; 32] = runtime_api("document_hash", &alice);
+
+        assert_ok!(DID::add_delegate(
+            RuntimeOrigin::signed(alice.clone()),
+            alice.clone(),
+            bob.clone(),
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            Some(Validity::For(100)),
+        ));
+
+        let document: DidDocument = runtime_api("did_document", &alice);
+        assert_eq!(document.id, did_of(&alice));
+        assert_eq!(document.controller, did_of(&alice));
+        assert_eq!(document.authentication.len(), 1);
+        assert_eq!(document.verification_methods[0].controller, did_of(&bob));
+        assert_ne!(runtime_api::<[u8; 32]>("document_hash", &alice), hash);
+        assert_eq!(document, DID::did_document(&alice));
+        assert_eq!(runtime_api::<u64>("next_meta_nonce", &alice), 0);
+    });
+}
+
+#[test]
+fn verifies_claims_and_handles_over_the_runtime_api() {
+    new_ext().execute_with(|| {
+        let alice = account("Alice");
+        let bob = account("Bob");
+
+        assert_ok!(DID::add_attribute(
+            RuntimeOrigin::signed(alice.clone()),
+            alice.clone(),
+            b"name".to_vec(),
+            b"Alice".to_vec(),
+            None,
+        ));
+        assert_ok!(DID::claim_handle(
+            RuntimeOrigin::signed(alice.clone()),
+            alice.clone(),
+            b"alice".to_vec(),
+        ));
+        assert_eq!(Balances::reserved_balance(&alice), 10 * DOLLARS);
+
+        let claims = vec![
+            (alice.clone(), b"name".to_vec(), b"Alice".to_vec()),
+            (alice.clone(), b"name".to_vec(), b"Bob".to_vec()),
+            (bob, b"name".to_vec(), b"Alice".to_vec()),
+        ];
+        let verified: Vec<bool> = runtime_api("verify_claims_batch", claims);
+        assert_eq!(verified, vec![true, false, false]);
+        let resolved: Option<AccountId> = runtime_api("identity_by_handle", b"alice".to_vec());
+        assert_eq!(resolved, Some(alice));
+        let resolved: Option<AccountId> = runtime_api("identity_by_handle", b"bob".to_vec());
+        assert_eq!(resolved, None);
+    });
+}
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pallet_did::multicodec::{base58_decode, base58_encode};
+
+fuzz_target!(|data: &[u8]| {
+    assert_eq!(base58_decode(&base58_encode(data)).as_deref(), Some(data));
+
+    // Decoding arbitrary input never panics.
+    if let Some(decoded) = base58_decode(data) {
+        assert_eq!(base58_encode(&decoded), data);
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pallet_did::external_did::{parse_external_did, ExternalDid};
+use pallet_did::multicodec;
+
+fuzz_target!(|did: &[u8]| {
+    // Parsed `did:key` identifiers re-encode to the identifier they were parsed from.
+    if let Some(ExternalDid::Key { key_type, public_key }) = parse_external_did(did) {
+        let multibase = multicodec::public_key_multibase(key_type, &public_key);
+        let reparsed = parse_external_did(&[b"did:key:".as_slice(), &multibase].concat());
+        assert_eq!(reparsed, Some(ExternalDid::Key { key_type, public_key }));
+    }
+});
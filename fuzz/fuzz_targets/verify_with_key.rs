@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pallet_did::{external_did::verify_with_key, types::KeyType};
+
+fuzz_target!(|input: (u8, &[u8], &[u8], &[u8])| {
+    let (key_type, public_key, message, signature) = input;
+    let key_type = match key_type % 4 {
+        0 => KeyType::Sr25519,
+        1 => KeyType::Ed25519,
+        2 => KeyType::Ecdsa,
+        _ => KeyType::X25519,
+    };
+    sp_io::TestExternalities::default().execute_with(|| {
+        // Malformed keys and signatures never panic.
+        let _ = verify_with_key(key_type, public_key, message, signature);
+    });
+});
@@ -0,0 +1,56 @@
+//! Benchmarks of the read paths of the pallet.
+
+use super::*;
+use crate::did::Did;
+use crate::types::{Delegate, KeyType, CAPABILITY_INVOCATION_DELEGATE_TYPE, SIGNING_DELEGATE_TYPE};
+use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite};
+use sp_core::{crypto::KeyTypeId, sr25519};
+
+const KEY_TYPE: KeyTypeId = KeyTypeId(*b"dids");
+
+benchmarks! {
+    where_clause { where T::Signature: From<sr25519::Signature>, T::AccountId: From<sr25519::Public> }
+
+    // Worst case: the signer is a capability invocation delegate, checked after the signing delegates.
+    valid_signer {
+        let identity: T::AccountId = account("identity", 0, 0);
+        let public = sp_io::crypto::sr25519_generate(KEY_TYPE, None);
+        let signer: T::AccountId = public.into();
+        <DelegateOf<T>>::insert(
+            &identity,
+            (CAPABILITY_INVOCATION_DELEGATE_TYPE.to_vec(), &signer),
+            Delegate { validity: u32::MAX.into(), key_type: KeyType::Sr25519 },
+        );
+        let msg = b"benchmark".to_vec();
+        let signature: T::Signature = sp_io::crypto::sr25519_sign(KEY_TYPE, &public, &msg).unwrap().into();
+    }: {
+        Pallet::<T>::valid_signer(&identity, &signature, &msg, &signer)?;
+    }
+
+    valid_attribute {
+        let identity: T::AccountId = account("identity", 0, 0);
+        let name = b"name".to_vec();
+        let value = [0u8; 256].to_vec();
+        Pallet::<T>::create_attribute(&identity, &identity, &name, &value, None)?;
+    }: {
+        Pallet::<T>::valid_attribute(&identity, &name, &value)?;
+    }
+
+    did_document {
+        let d in 0 .. 100;
+        let identity: T::AccountId = account("identity", 0, 0);
+        for i in 0 .. d {
+            let delegate: T::AccountId = account("delegate", i, 0);
+            <DelegateOf<T>>::insert(
+                &identity,
+                (SIGNING_DELEGATE_TYPE.to_vec(), &delegate),
+                Delegate { validity: u32::MAX.into(), key_type: KeyType::Sr25519 },
+            );
+        }
+    }: {
+        let document = Pallet::<T>::did_document(&identity);
+        assert_eq!(document.verification_methods.len(), d as usize);
+    }
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_benchmark_ext(), crate::mock::Test);
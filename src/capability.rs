@@ -0,0 +1,87 @@
+//! Delegations exported as authorization capabilities (zcap).
+//!
+//! A [`Capability`] describes a delegate of an identity as of the current chain state.
+//! The controller of the identity signs its [`Capability::signing_payload`] off chain, and
+//! services check the signed capability against the chain state with `verify_capability`.
+//! With the `std` feature, it serializes to a zcap-ld JSON document.
+
+use crate::document::{did_of, hex_prefixed};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::RuntimeDebug;
+use sp_io::hashing::blake2_256;
+use sp_std::vec::Vec;
+#[cfg(feature = "std")]
+use crate::document::text;
+#[cfg(feature = "std")]
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// JSON-LD context of an authorization capability.
+pub const ZCAP_CONTEXT: &str = "https://w3id.org/zcap/v1";
+
+/// Domain tag prefixed to the signing payload of the capabilities, so a capability signature
+/// is not valid as any other message signed by the same key.
+pub const CAPABILITY_TAG: &[u8; 14] = b"did/capability";
+
+/// A delegation of an identity, scoped to a delegate type.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub struct Capability<AccountId, Hash, BlockNumber> {
+    /// Genesis hash of the chain holding the delegation.
+    pub chain_id: Hash,
+    /// The delegating identity.
+    pub invocation_target: AccountId,
+    /// The delegate.
+    pub controller: AccountId,
+    /// Delegate type the capability is scoped to.
+    pub allowed_action: Vec<u8>,
+    /// First block the delegation is no longer valid at, exclusive: the delegation is valid up
+    /// to the block before.
+    pub expires: BlockNumber,
+}
+
+impl<AccountId: Encode, Hash: Encode, BlockNumber: Encode> Capability<AccountId, Hash, BlockNumber> {
+    /// Payload signed by the controller of the invocation target: the SCALE encoding of
+    /// `(CAPABILITY_TAG, capability)`, i.e. the 14 bytes of the tag, then the capability.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        (CAPABILITY_TAG, self).encode()
+    }
+
+    /// Identifier of the capability, `urn:zcap:0x` followed by the hash of its encoding.
+    pub fn id(&self) -> Vec<u8> {
+        hex_prefixed(b"urn:zcap:0x", &blake2_256(&self.encode()))
+    }
+
+    /// Identifier of the root capability of the identity.
+    pub fn parent_capability(&self) -> Vec<u8> {
+        let mut root = b"urn:zcap:root:".to_vec();
+        root.extend(did_of(&self.invocation_target));
+        root
+    }
+
+    /// Serializes the capability as zcap-ld.
+    #[cfg(feature = "std")]
+    pub fn to_json_ld(&self) -> Result<String, serde_json::Error>
+    where
+        BlockNumber: Serialize,
+    {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<AccountId: Encode, Hash: Encode, BlockNumber: Encode + Serialize> Serialize
+    for Capability<AccountId, Hash, BlockNumber>
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("@context", ZCAP_CONTEXT)?;
+        map.serialize_entry("id", &text(&self.id()))?;
+        map.serialize_entry("parentCapability", &text(&self.parent_capability()))?;
+        map.serialize_entry("invocationTarget", &text(&did_of(&self.invocation_target)))?;
+        map.serialize_entry("controller", &text(&did_of(&self.controller)))?;
+        map.serialize_entry("allowedAction", &[text(&self.allowed_action)])?;
+        map.serialize_entry("expiresAtBlock", &self.expires)?;
+        map.serialize_entry("chainId", &text(&hex_prefixed(b"0x", &self.chain_id.encode())))?;
+        map.end()
+    }
+}
@@ -0,0 +1,48 @@
+//! `ChainExtension` of `pallet-contracts` giving ink! contracts read access to the DIDs,
+//! with the `contracts` feature.
+//!
+//! The functions are the `extension` queries: `is_owner`, `valid_delegate`, `valid_attribute`
+//! and `attribute_value`. Runtimes with other chain extensions register it under its `ID`.
+//!
+//! Each query is charged its weight, and `extension::WEIGHT_PER_BYTE` per byte of its input,
+//! before reading it, and of its output, before writing it.
+
+use crate::{
+    extension::{self, DidQuery},
+    pallet::Config,
+};
+use frame_support::DefaultNoBound;
+use pallet_contracts::chain_extension::{
+    ChainExtension, Environment, Ext, InitState, RegisteredChainExtension, RetVal, SysConfig,
+};
+use sp_core::crypto::UncheckedFrom;
+use sp_runtime::DispatchError;
+use sp_std::marker::PhantomData;
+
+/// DID chain extension of the runtime `T`.
+#[derive(DefaultNoBound)]
+pub struct DidExtension<T>(PhantomData<T>);
+
+impl<T: pallet_contracts::Config + Config> ChainExtension<T> for DidExtension<T> {
+    fn call<E: Ext<T = T>>(&mut self, env: Environment<E, InitState>) -> Result<RetVal, DispatchError>
+    where
+        <E::T as SysConfig>::AccountId: UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
+    {
+        let func_id = env.func_id();
+        let mut env = env.buf_in_buf_out();
+        let len = env.in_len();
+        env.charge_weight(extension::bytes_weight(len as usize))?;
+        let input = env.read(len)?;
+        let query = DidQuery::<T::AccountId>::decode(func_id, &mut &input[..])
+            .ok_or(DispatchError::Other("Unknown DID query"))?;
+        env.charge_weight(extension::weight::<T>(&query))?;
+        let output = extension::execute::<T>(&query);
+        env.charge_weight(extension::bytes_weight(output.len()))?;
+        env.write(&output, false, None)?;
+        Ok(RetVal::Converging(0))
+    }
+}
+
+impl<T: pallet_contracts::Config + Config> RegisteredChainExtension<T> for DidExtension<T> {
+    const ID: u16 = 0x0D1D;
+}
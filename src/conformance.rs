@@ -0,0 +1,101 @@
+//! Conformance vectors, checked into `fixtures/conformance.json`, for SDKs to validate their
+//! signing payloads, storage keys and resolution outputs byte-for-byte against the pallet.
+//!
+//! After an intended change, regenerate the fixtures with
+//! `UPDATE_CONFORMANCE_FIXTURES=1 cargo test conformance`.
+
+use crate::{
+    did::Did,
+    document::did_of,
+    mock::*,
+    multicodec,
+    types::{KeyType, Validity, SIGNING_DELEGATE_TYPE},
+};
+use frame_support::assert_ok;
+use frame_system::RawOrigin;
+use serde_json::{json, Value};
+use sp_core::sr25519;
+
+const FIXTURES: &str = include_str!("../fixtures/conformance.json");
+
+fn hex(bytes: &[u8]) -> String {
+    format!("0x{}", bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<String>())
+}
+
+fn text(bytes: Vec<u8>) -> String {
+    String::from_utf8(bytes).unwrap()
+}
+
+/// Computes the vectors from fixed keys, so SDKs can reproduce them without key derivation.
+fn vectors() -> Value {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let identity = sr25519::Public::from_raw([1; 32]);
+        let delegate = sr25519::Public::from_raw([2; 32]);
+        let (name, value) = (b"name".to_vec(), b"value".to_vec());
+        let (validity, meta_nonce, valid_for) = (100u32, 0u64, 100);
+
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(identity).into(),
+            identity,
+            name.clone(),
+            value.clone(),
+            None
+        ));
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(identity).into(),
+            identity,
+            delegate,
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            Some(Validity::For(valid_for))
+        ));
+
+        let (_, attribute_id) = DID::attribute_and_id(&identity, &name).unwrap();
+        let delegate_key = (SIGNING_DELEGATE_TYPE.to_vec(), delegate);
+        let capability =
+            DID::export_capability(&identity, &delegate, SIGNING_DELEGATE_TYPE).unwrap();
+
+        json!({
+            "inputs": {
+                "identity": hex(identity.as_ref()),
+                "delegate": hex(delegate.as_ref()),
+                "name": text(name.clone()),
+                "value": text(value.clone()),
+                "validity": validity,
+                "meta_nonce": meta_nonce,
+                "delegate_type": text(SIGNING_DELEGATE_TYPE.to_vec()),
+                "delegate_valid_for": valid_for,
+                "block_number": 1,
+                "genesis_hash": hex(System::block_hash(0).as_ref()),
+            },
+            "outputs": {
+                "did": text(did_of(&identity)),
+                "meta_transaction_payload":
+                    hex(&DID::meta_transaction_payload(&name, &value, validity, &identity, meta_nonce)),
+                "attribute_id": hex(&attribute_id),
+                "attribute_storage_key":
+                    hex(&crate::AttributeOf::<Test>::hashed_key_for((identity, attribute_id))),
+                "delegate_storage_key":
+                    hex(&crate::DelegateOf::<Test>::hashed_key_for(identity, delegate_key)),
+                "public_key_multibase":
+                    text(multicodec::public_key_multibase(KeyType::Sr25519, delegate.as_ref())),
+                "capability_signing_payload": hex(&capability.signing_payload()),
+                "capability_id": text(capability.id()),
+                "did_document": serde_json::to_value(DID::did_document(&identity)).unwrap(),
+            },
+        })
+    })
+}
+
+#[test]
+fn conformance_vectors() {
+    let vectors = vectors();
+    if std::env::var_os("UPDATE_CONFORMANCE_FIXTURES").is_some() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/conformance.json");
+        std::fs::write(path, serde_json::to_string_pretty(&vectors).unwrap() + "\n").unwrap();
+        return;
+    }
+    let fixtures: Value = serde_json::from_str(FIXTURES).unwrap();
+    assert_eq!(vectors, fixtures, "conformance vectors changed, see the module documentation");
+}
@@ -0,0 +1,19 @@
+//! Delegation of identities to smart contracts, e.g. of `pallet-contracts` or EVM contracts
+//! mapped to accounts, so programmable policies can act on behalf of a DID.
+//!
+//! A contract account is added as a `ContractDelegate` delegate of an identity. Instead of
+//! signing, it authorizes callers through an `is_authorized` query of the contract, made by the
+//! `ContractAuthorizer` adapter of the runtime.
+
+/// Queries the authorization policy of contract delegates.
+pub trait ContractAuthorizer<AccountId> {
+    /// Returns `true` if the `contract` authorizes `caller` to act on behalf of `identity`
+    /// for `purpose`. Adapters bound the gas of the query.
+    fn is_authorized(contract: &AccountId, identity: &AccountId, caller: &AccountId, purpose: &[u8]) -> bool;
+}
+
+impl<AccountId> ContractAuthorizer<AccountId> for () {
+    fn is_authorized(_contract: &AccountId, _identity: &AccountId, _caller: &AccountId, _purpose: &[u8]) -> bool {
+        false
+    }
+}
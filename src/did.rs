@@ -1,10 +1,12 @@
-use crate::types::AttributedId;
+use crate::types::{AttributedId, KeyType, Validity};
 
 use frame_support::dispatch::DispatchResult;
 
 pub trait Did<AccountId, BlockNumber, Moment, Signature> {
     fn is_owner(identity: &AccountId, actual_owner: &AccountId) -> DispatchResult;
     fn identity_owner(identity: &AccountId) -> AccountId;
+    fn try_identity_owner(identity: &AccountId) -> Option<AccountId>;
+    fn registered(identity: &AccountId) -> bool;
     fn valid_delegate(
         identity: &AccountId,
         delegate_type: &[u8],
@@ -20,7 +22,8 @@ pub trait Did<AccountId, BlockNumber, Moment, Signature> {
         identity: &AccountId,
         delegate: &AccountId,
         delegate_type: &[u8],
-        valid_for: Option<BlockNumber>,
+        key_type: KeyType,
+        valid_for: Option<Validity<BlockNumber>>,
     ) -> DispatchResult;
     fn check_signature(signature: &Signature, msg: &[u8], signer: &AccountId) -> DispatchResult;
     fn valid_signer(
@@ -34,7 +37,7 @@ pub trait Did<AccountId, BlockNumber, Moment, Signature> {
         identity: &AccountId,
         name: &[u8],
         value: &[u8],
-        valid_for: Option<BlockNumber>,
+        valid_for: Option<Validity<BlockNumber>>,
     ) -> DispatchResult;
     fn reset_attribute(who: AccountId, identity: &AccountId, name: &[u8]) -> DispatchResult;
     fn valid_attribute(identity: &AccountId, name: &[u8], value: &[u8]) -> DispatchResult;
@@ -43,3 +46,13 @@ pub trait Did<AccountId, BlockNumber, Moment, Signature> {
         name: &[u8],
     ) -> Option<AttributedId<BlockNumber, Moment>>;
 }
+
+/// Read-only view of the DIDs, for the pallets consuming them.
+pub trait DidProvider<AccountId> {
+    /// Returns the owner of an identity, the identity itself unless changed.
+    fn identity_owner(identity: &AccountId) -> AccountId;
+    /// Validates that `delegate` is a valid delegate of `delegate_type` of the identity.
+    fn valid_delegate(identity: &AccountId, delegate_type: &[u8], delegate: &AccountId) -> DispatchResult;
+    /// Validates that the identity holds a valid attribute `name` of `value`.
+    fn valid_attribute(identity: &AccountId, name: &[u8], value: &[u8]) -> DispatchResult;
+}
@@ -0,0 +1,164 @@
+//! DID Documents resolved from the registry.
+//!
+//! [`DidDocument`] has a compact SCALE encoding, used by the runtime API and bridges:
+//! verification methods are referenced by their fragment instead of their full DID URL.
+//! With the `std` feature, it serializes to a JSON-LD DID Document.
+
+use crate::types::KeyType;
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::RuntimeDebug;
+use sp_std::vec::Vec;
+#[cfg(feature = "std")]
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// Prefix of the DIDs of this registry, followed by the hex encoded account.
+pub const DID_METHOD_PREFIX: &[u8] = b"did:substrate:0x";
+
+/// JSON-LD contexts of a DID Document.
+pub const DID_CONTEXTS: [&str; 2] = ["https://www.w3.org/ns/did/v1", "https://w3id.org/security/multikey/v1"];
+
+/// Returns the `did:substrate` DID of an account.
+pub fn did_of<AccountId: Encode>(account: &AccountId) -> Vec<u8> {
+    hex_prefixed(DID_METHOD_PREFIX, &account.encode())
+}
+
+/// Appends the lowercase hex encoding of `bytes` to `prefix`.
+pub(crate) fn hex_prefixed(prefix: &[u8], bytes: &[u8]) -> Vec<u8> {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut hex = Vec::with_capacity(prefix.len() + bytes.len() * 2);
+    hex.extend_from_slice(prefix);
+    for byte in bytes {
+        hex.push(HEX[(byte >> 4) as usize]);
+        hex.push(HEX[(byte & 0xf) as usize]);
+    }
+    hex
+}
+
+/// Verification method type of a key type.
+pub fn verification_method_type(key_type: KeyType) -> &'static [u8] {
+    match key_type {
+        KeyType::Sr25519 => b"Sr25519VerificationKey2020",
+        KeyType::Ed25519 => b"Ed25519VerificationKey2020",
+        KeyType::Ecdsa => b"EcdsaSecp256k1VerificationKey2019",
+        KeyType::X25519 => b"X25519KeyAgreementKey2020",
+    }
+}
+
+/// A public key of a DID Document.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, TypeInfo, RuntimeDebug)]
+pub struct VerificationMethod {
+    /// Fragment identifying the method in the document, without `#`.
+    pub fragment: Vec<u8>,
+    pub key_type: KeyType,
+    /// DID of the controller of the key.
+    pub controller: Vec<u8>,
+    pub public_key_multibase: Vec<u8>,
+}
+
+/// A service endpoint of a DID Document.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, TypeInfo, RuntimeDebug)]
+pub struct Service {
+    /// Fragment identifying the service in the document, without `#`.
+    pub fragment: Vec<u8>,
+    pub service_type: Vec<u8>,
+    pub endpoint: Vec<u8>,
+}
+
+/// A DID Document. Verification relationships hold verification method fragments.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, TypeInfo, RuntimeDebug)]
+pub struct DidDocument {
+    pub id: Vec<u8>,
+    pub controller: Vec<u8>,
+    pub verification_methods: Vec<VerificationMethod>,
+    pub authentication: Vec<Vec<u8>>,
+    pub assertion_method: Vec<Vec<u8>>,
+    pub key_agreement: Vec<Vec<u8>>,
+    pub capability_invocation: Vec<Vec<u8>>,
+    pub services: Vec<Service>,
+}
+
+impl DidDocument {
+    /// Returns the DID URL of a fragment of the document.
+    pub fn url(&self, fragment: &[u8]) -> Vec<u8> {
+        let mut url = self.id.clone();
+        url.push(b'#');
+        url.extend_from_slice(fragment);
+        url
+    }
+
+    /// Adds a verification method, unless one with the same fragment exists.
+    pub fn add_verification_method(&mut self, method: VerificationMethod) {
+        if !self.verification_methods.iter().any(|m| m.fragment == method.fragment) {
+            self.verification_methods.push(method);
+        }
+    }
+
+    /// Serializes the document as JSON-LD.
+    #[cfg(feature = "std")]
+    pub fn to_json_ld(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+#[cfg(feature = "std")]
+impl Serialize for DidDocument {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let urls = |fragments: &[Vec<u8>]| -> Vec<String> {
+            fragments.iter().map(|fragment| text(&self.url(fragment))).collect()
+        };
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("@context", &DID_CONTEXTS)?;
+        map.serialize_entry("id", &text(&self.id))?;
+        map.serialize_entry("controller", &text(&self.controller))?;
+
+        if !self.verification_methods.is_empty() {
+            let methods: Vec<_> = self
+                .verification_methods
+                .iter()
+                .map(|method| {
+                    serde_json::json!({
+                        "id": text(&self.url(&method.fragment)),
+                        "type": text(verification_method_type(method.key_type)),
+                        "controller": text(&method.controller),
+                        "publicKeyMultibase": text(&method.public_key_multibase),
+                    })
+                })
+                .collect();
+            map.serialize_entry("verificationMethod", &methods)?;
+        }
+
+        for (key, fragments) in [
+            ("authentication", &self.authentication),
+            ("assertionMethod", &self.assertion_method),
+            ("keyAgreement", &self.key_agreement),
+            ("capabilityInvocation", &self.capability_invocation),
+        ] {
+            if !fragments.is_empty() {
+                map.serialize_entry(key, &urls(fragments))?;
+            }
+        }
+
+        if !self.services.is_empty() {
+            let services: Vec<_> = self
+                .services
+                .iter()
+                .map(|service| {
+                    serde_json::json!({
+                        "id": text(&self.url(&service.fragment)),
+                        "type": text(&service.service_type),
+                        "serviceEndpoint": text(&service.endpoint),
+                    })
+                })
+                .collect();
+            map.serialize_entry("service", &services)?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,123 @@
+//! Stable numeric codes of the pallet errors, so SDKs in any language present documented
+//! failure reasons instead of matching on the indices of the `Error` variants, which shift
+//! between releases.
+//!
+//! Codes are never changed nor reused: new errors get new codes. The codes are exposed in the
+//! metadata as the `ErrorCodes` constant and by the `error_code` runtime API.
+
+use crate::pallet::{Config, Error, Pallet};
+use codec::Decode;
+use frame_support::traits::PalletInfoAccess;
+use sp_runtime::{DispatchError, ModuleError};
+
+/// `(code, name)` of the errors, `0` is the code of unknown errors.
+pub const ERROR_CODES: &[(u16, &str)] = &[
+    (1, "NotOwner"),
+    (2, "InvalidDelegate"),
+    (3, "BadSignature"),
+    (4, "AttributeCreationFailed"),
+    (5, "AttributeResetFailed"),
+    (6, "AttributeRemovalFailed"),
+    (7, "InvalidAttribute"),
+    (8, "Overflow"),
+    (9, "BadTransaction"),
+    (10, "InvalidExternalDid"),
+    (11, "DidAlreadyExists"),
+    (12, "InvalidKeyAgreementKey"),
+    (13, "AttributeValidityTooLong"),
+    (14, "EmptyName"),
+    (15, "EmptyValue"),
+    (16, "ReservedName"),
+    (17, "ZeroValidity"),
+    (18, "ValidityOverflow"),
+    (19, "HashAlreadyAnchored"),
+    (20, "TooManyRenewals"),
+    (21, "IdentityNotRegistered"),
+    (22, "PalletPaused"),
+    (23, "ReservedNamespace"),
+    (24, "PresentationNonceExists"),
+    (25, "UnknownPresentationNonce"),
+    (26, "PresentationNonceExpired"),
+    (27, "NotForSale"),
+    (28, "AccessAlreadyPurchased"),
+    (29, "UnknownAccessPurchase"),
+    (30, "EscrowNotExpired"),
+    (31, "UnknownStateRoot"),
+    (32, "InvalidStateProof"),
+    (33, "ForeignAttributeExpired"),
+    (34, "ZeroThreshold"),
+    (35, "RecoveryNotConfigured"),
+    (36, "AlreadyVouched"),
+    (37, "DisplayNameTooLong"),
+    (38, "NoProfile"),
+    (39, "IssuerAlreadySuspended"),
+    (40, "InvalidNamespace"),
+    (41, "NamespaceTaken"),
+    (42, "UnknownNamespace"),
+    (43, "NotNamespaceOwner"),
+    (44, "AttributeTooOld"),
+    (45, "InvalidCapability"),
+    (46, "MigrationOngoing"),
+    (47, "AccessNotCounted"),
+    (48, "InvalidHandle"),
+    (49, "HandleTooLong"),
+    (50, "HandleTaken"),
+    (51, "HandleAlreadySet"),
+    (52, "NoHandle"),
+    (53, "IssuerSuspended"),
+    (54, "CredentialExists"),
+    (55, "AttestationAlreadyPending"),
+    (56, "UnknownAttestation"),
+    (57, "DelegateCountTooLow"),
+    (58, "BannedName"),
+    (59, "AlreadyBanned"),
+    (60, "NotBanned"),
+    (61, "DormancyDisabled"),
+    (62, "IdentityActive"),
+    (63, "AlreadyDormant"),
+    (64, "NotDormant"),
+    (65, "GracePeriodOngoing"),
+    (66, "AttributeCountTooLow"),
+    (67, "ContractNotAuthorized"),
+    (68, "SchemeNotAllowed"),
+    (69, "AlreadyWatching"),
+    (70, "NotWatching"),
+    (71, "TooManyWatchers"),
+    (72, "InvalidDependency"),
+    (73, "TooManyDependents"),
+    (74, "CosignatureRequired"),
+    (75, "NotCosigned"),
+    (76, "StorageQuotaExceeded"),
+    (77, "NotRecoveredAccount"),
+    (78, "TooManyVouches"),
+    (79, "TooManyPendingAttestations"),
+    (80, "TooManyCredentials"),
+    (81, "UnknownCredential"),
+    (82, "StaleStateRoot"),
+    (83, "AccessPriceAboveLimit"),
+    (84, "DependentNotAllowed"),
+    (85, "TooManyPresentationNonces"),
+    (86, "TooManyRescuers"),
+];
+
+impl<T: Config> Error<T> {
+    /// Stable numeric code of the error.
+    pub fn error_code(&self) -> u16 {
+        let name = self.as_str();
+        ERROR_CODES.iter().find(|(_, n)| *n == name).map_or(0, |(code, _)| *code)
+    }
+}
+
+/// Returns the code and name of a dispatch error of the pallet, `None` for errors of other
+/// pallets or of the runtime.
+pub fn error_code_of<T: Config>(error: &DispatchError) -> Option<(u16, &'static str)> {
+    match error {
+        DispatchError::Module(ModuleError { index, error, .. })
+            if *index as usize == <Pallet<T> as PalletInfoAccess>::index() =>
+        {
+            let error = Error::<T>::decode(&mut &error[..]).ok()?;
+            Some((error.error_code(), error.as_str()))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,119 @@
+//! Read-only DID queries for smart contracts, served by the `chain_extension` of ink!
+//! contracts with the `contracts` feature, and the `precompile` of Solidity contracts with
+//! the `frontier` feature.
+//!
+//! A query is identified by its function id and SCALE decoded from its input, its output
+//! is SCALE encoded.
+
+use crate::{
+    did::Did,
+    pallet::{Config, Pallet},
+    weights::WeightInfo,
+};
+use codec::{Decode, Encode};
+use frame_support::{traits::Get, weights::Weight};
+use sp_core::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// `(identity, account) -> bool`
+pub const IS_OWNER: u16 = 1;
+/// `(identity, delegate_type, delegate) -> bool`
+pub const VALID_DELEGATE: u16 = 2;
+/// `(identity, name, value) -> bool`
+pub const VALID_ATTRIBUTE: u16 = 3;
+/// `(identity, name) -> Option<Vec<u8>>`
+pub const ATTRIBUTE_VALUE: u16 = 4;
+
+/// DID query of a contract.
+#[derive(PartialEq, Eq, Clone, RuntimeDebug)]
+pub enum DidQuery<AccountId> {
+    /// Whether the account owns the identity.
+    IsOwner(AccountId, AccountId),
+    /// Whether the delegate is a valid delegate of the type of the identity.
+    ValidDelegate(AccountId, Vec<u8>, AccountId),
+    /// Whether the identity holds a valid attribute of the name and value.
+    ValidAttribute(AccountId, Vec<u8>, Vec<u8>),
+    /// Value of a valid attribute of the identity.
+    AttributeValue(AccountId, Vec<u8>),
+}
+
+impl<AccountId: Decode> DidQuery<AccountId> {
+    /// Decodes the query of the function `func_id`, `None` if the function is unknown
+    /// or the input invalid.
+    pub fn decode(func_id: u16, input: &mut &[u8]) -> Option<Self> {
+        Some(match func_id {
+            IS_OWNER => {
+                let (identity, account) = Decode::decode(input).ok()?;
+                DidQuery::IsOwner(identity, account)
+            }
+            VALID_DELEGATE => {
+                let (identity, delegate_type, delegate) = Decode::decode(input).ok()?;
+                DidQuery::ValidDelegate(identity, delegate_type, delegate)
+            }
+            VALID_ATTRIBUTE => {
+                let (identity, name, value) = Decode::decode(input).ok()?;
+                DidQuery::ValidAttribute(identity, name, value)
+            }
+            ATTRIBUTE_VALUE => {
+                let (identity, name) = Decode::decode(input).ok()?;
+                DidQuery::AttributeValue(identity, name)
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// Weight of copying a byte in or out of a contract, charged for the input and the output
+/// of the queries on top of their `weight`. An estimate, not a benchmark result.
+pub const WEIGHT_PER_BYTE: u64 = 1_000;
+
+/// Weight of copying `bytes` bytes of the input or output of a query.
+pub fn bytes_weight(bytes: usize) -> Weight {
+    Weight::from_ref_time(WEIGHT_PER_BYTE.saturating_mul(bytes as u64))
+}
+
+/// Weight of a query, charged before executing it.
+pub fn weight<T: Config>(query: &DidQuery<T::AccountId>) -> Weight {
+    match query {
+        DidQuery::IsOwner(..) => T::DbWeight::get().reads(2),
+        DidQuery::ValidDelegate(..) => T::DbWeight::get().reads(3),
+        DidQuery::ValidAttribute(..) => T::WeightInfo::valid_attribute(),
+        DidQuery::AttributeValue(..) => T::DbWeight::get().reads(2),
+    }
+}
+
+/// Output of a query.
+#[derive(PartialEq, Eq, Clone, RuntimeDebug)]
+pub enum DidAnswer {
+    Bool(bool),
+    Value(Option<Vec<u8>>),
+}
+
+/// Answers a query.
+pub fn answer<T: Config>(query: &DidQuery<T::AccountId>) -> DidAnswer {
+    match query {
+        DidQuery::IsOwner(identity, account) => DidAnswer::Bool(Pallet::<T>::is_owner(identity, account).is_ok()),
+        DidQuery::ValidDelegate(identity, delegate_type, delegate) => {
+            DidAnswer::Bool(Pallet::<T>::valid_delegate(identity, delegate_type, delegate).is_ok())
+        }
+        DidQuery::ValidAttribute(identity, name, value) => {
+            DidAnswer::Bool(Pallet::<T>::valid_attribute(identity, name, value).is_ok())
+        }
+        DidQuery::AttributeValue(identity, name) => {
+            let now = <frame_system::Pallet<T>>::block_number();
+            DidAnswer::Value(
+                Pallet::<T>::attribute_and_id(identity, name)
+                    .filter(|(attribute, _)| attribute.validity > now)
+                    .map(|(attribute, _)| attribute.value),
+            )
+        }
+    }
+}
+
+/// Executes a query, returning its SCALE encoded output.
+pub fn execute<T: Config>(query: &DidQuery<T::AccountId>) -> Vec<u8> {
+    match answer::<T>(query) {
+        DidAnswer::Bool(answer) => answer.encode(),
+        DidAnswer::Value(value) => value.encode(),
+    }
+}
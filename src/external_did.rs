@@ -0,0 +1,82 @@
+//! Helpers for DIDs managed outside of this registry.
+//!
+//! * `did:key` identifiers embed their public key, so signatures can be checked directly.
+//! * `did:web` identifiers are resolved off-chain. Their DID Document is supplied by the
+//!   caller as a SCALE encoded [`WebDidDocument`] preimage.
+
+use crate::{multicodec, types::KeyType};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::{ecdsa, ed25519, sr25519, RuntimeDebug};
+use sp_std::{convert::TryFrom, vec::Vec};
+
+pub const DID_KEY_PREFIX: &[u8] = b"did:key:";
+pub const DID_WEB_PREFIX: &[u8] = b"did:web:";
+
+/// A DID that is not stored in this registry.
+#[derive(PartialEq, Eq, Clone, RuntimeDebug)]
+pub enum ExternalDid {
+    /// `did:key:<publicKeyMultibase>`
+    Key { key_type: KeyType, public_key: Vec<u8> },
+    /// `did:web:<domain-name>[:<path>]`
+    Web { domain: Vec<u8> },
+}
+
+/// Minimal `did:web` DID Document, supplied as a preimage by the verifier.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, TypeInfo, RuntimeDebug)]
+pub struct WebDidDocument {
+    /// The `did:web` identifier the document was resolved for.
+    pub id: Vec<u8>,
+    /// `publicKeyMultibase` values of the document verification methods.
+    pub verification_methods: Vec<Vec<u8>>,
+}
+
+/// Parses a `did:key` or `did:web` identifier. DID URL fragments are ignored.
+pub fn parse_external_did(did: &[u8]) -> Option<ExternalDid> {
+    let did = match did.iter().position(|c| *c == b'#') {
+        Some(fragment) => &did[..fragment],
+        None => did,
+    };
+
+    if let Some(multibase) = did.strip_prefix(DID_KEY_PREFIX) {
+        let (key_type, public_key) = multicodec::decode_public_key_multibase(multibase)?;
+        Some(ExternalDid::Key { key_type, public_key })
+    } else if let Some(domain) = did.strip_prefix(DID_WEB_PREFIX) {
+        if domain.is_empty() {
+            return None;
+        }
+        Some(ExternalDid::Web { domain: domain.to_vec() })
+    } else {
+        None
+    }
+}
+
+/// Verifies a raw signature against a raw public key of the given type.
+/// Ecdsa messages are hashed with blake2-256, as done by `sp_core::ecdsa`.
+/// Key agreement keys (`X25519`) can not sign.
+pub fn verify_with_key(key_type: KeyType, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    match key_type {
+        KeyType::Sr25519 => match (
+            sr25519::Public::try_from(public_key),
+            sr25519::Signature::from_slice(signature),
+        ) {
+            (Ok(public), Some(signature)) => sp_io::crypto::sr25519_verify(&signature, message, &public),
+            _ => false,
+        },
+        KeyType::Ed25519 => match (
+            ed25519::Public::try_from(public_key),
+            ed25519::Signature::from_slice(signature),
+        ) {
+            (Ok(public), Some(signature)) => sp_io::crypto::ed25519_verify(&signature, message, &public),
+            _ => false,
+        },
+        KeyType::Ecdsa => match (
+            ecdsa::Public::try_from(public_key),
+            ecdsa::Signature::from_slice(signature),
+        ) {
+            (Ok(public), Some(signature)) => sp_io::crypto::ecdsa_verify(&signature, message, &public),
+            _ => false,
+        },
+        KeyType::X25519 => false,
+    }
+}
@@ -0,0 +1,105 @@
+//! Feeless first writes of brand-new identities, to smooth onboarding on identity-first chains.
+//!
+//! A `Pays::No` returned by the call would not help: the fee is withdrawn before dispatch, so
+//! accounts without balance could still not submit it. [`SkipFeelessFirstWrite`] instead wraps
+//! the fee payment extension of the runtime, e.g. `ChargeTransactionPayment`, and skips it for
+//! the first write of an identity, within the `FeelessFirstWrites` quota of the block.
+
+use crate::pallet::{Call, Config, Pallet};
+use codec::{Decode, Encode};
+use frame_support::traits::IsSubType;
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{DispatchInfoOf, PostDispatchInfoOf, SignedExtension},
+    transaction_validity::{TransactionValidity, TransactionValidityError, ValidTransaction},
+    DispatchResult,
+};
+use sp_std::marker::PhantomData;
+
+/// Signed extension skipping the fee payment extension `S` for the feeless first writes.
+///
+/// A write is feeless if it is the `register_did`, `add_attribute` of the own identity or
+/// `add_own_attribute` of an identity never registered, updated nor written feelessly, even
+/// reaped since, of attributes within `MaxFeelessWriteBytes`, and the block quota is not exhausted.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct SkipFeelessFirstWrite<T, S>(pub S, PhantomData<T>);
+
+impl<T, S> SkipFeelessFirstWrite<T, S> {
+    pub fn new(payment: S) -> Self {
+        Self(payment, PhantomData)
+    }
+}
+
+impl<T, S> From<S> for SkipFeelessFirstWrite<T, S> {
+    fn from(payment: S) -> Self {
+        Self::new(payment)
+    }
+}
+
+impl<T, S: core::fmt::Debug> core::fmt::Debug for SkipFeelessFirstWrite<T, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "SkipFeelessFirstWrite<{:?}>", self.0)
+    }
+}
+
+impl<T, S> SignedExtension for SkipFeelessFirstWrite<T, S>
+where
+    T: Config + Clone + Eq + Send + Sync + 'static,
+    T::RuntimeCall: IsSubType<Call<T>>,
+    S: SignedExtension<AccountId = T::AccountId, Call = T::RuntimeCall>,
+{
+    const IDENTIFIER: &'static str = S::IDENTIFIER;
+    type AccountId = T::AccountId;
+    type Call = T::RuntimeCall;
+    type AdditionalSigned = S::AdditionalSigned;
+    /// `None` for the feeless writes, which skipped `S`.
+    type Pre = Option<S::Pre>;
+
+    fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
+        self.0.additional_signed()
+    }
+
+    fn validate(
+        &self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        len: usize,
+    ) -> TransactionValidity {
+        match call.is_sub_type() {
+            Some(call) if Pallet::<T>::is_feeless_first_write(who, call) => Ok(ValidTransaction::default()),
+            _ => self.0.validate(who, call, info, len),
+        }
+    }
+
+    fn pre_dispatch(
+        self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        match call.is_sub_type() {
+            Some(call) if Pallet::<T>::is_feeless_first_write(who, call) => {
+                Pallet::<T>::note_feeless_first_write(who);
+                Ok(None)
+            }
+            _ => Ok(Some(self.0.pre_dispatch(who, call, info, len)?)),
+        }
+    }
+
+    fn post_dispatch(
+        pre: Option<Self::Pre>,
+        info: &DispatchInfoOf<Self::Call>,
+        post_info: &PostDispatchInfoOf<Self::Call>,
+        len: usize,
+        result: &DispatchResult,
+    ) -> Result<(), TransactionValidityError> {
+        match pre {
+            Some(None) => Ok(()),
+            Some(Some(pre)) => S::post_dispatch(Some(pre), info, post_info, len, result),
+            None => S::post_dispatch(None, info, post_info, len, result),
+        }
+    }
+}
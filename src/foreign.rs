@@ -0,0 +1,67 @@
+//! Attestations imported from a foreign consensus, e.g. the relay chain or a bridged chain
+//! running this pallet, through storage proofs of their `AttributeOf` entries.
+//!
+//! A proof also holds the `System::Number` of the foreign block, to convert the foreign expiry
+//! into a local one, and the `AttributeIssuer` entry of the attribute, to keep its issuer.
+
+use codec::Encode;
+use sp_core::H256;
+use sp_io::hashing::{blake2_128, twox_128};
+use sp_runtime::traits::BlakeTwo256;
+use sp_std::vec::Vec;
+use sp_trie::{read_trie_value, LayoutV1, StorageProof};
+
+/// State roots of the foreign consensus, e.g. from the relay chain headers
+/// known to a parachain or from a bridge light client.
+pub trait ForeignStateRoots {
+    /// The state root of the foreign block `block`, if known.
+    fn state_root(block: u32) -> Option<H256>;
+
+    /// The latest foreign block with a known state root, against which the age of the
+    /// imported state roots is bounded. No attestation is imported while it is `None`.
+    fn latest_block() -> Option<u32>;
+}
+
+impl ForeignStateRoots for () {
+    fn state_root(_block: u32) -> Option<H256> {
+        None
+    }
+
+    fn latest_block() -> Option<u32> {
+        None
+    }
+}
+
+/// `Blake2_128Concat` storage key of a map entry, given the storage prefix of the map.
+fn map_storage_key(prefix: &[u8], key: Vec<u8>) -> Vec<u8> {
+    let mut storage_key = prefix.to_vec();
+    storage_key.extend_from_slice(&blake2_128(&key));
+    storage_key.extend(key);
+    storage_key
+}
+
+/// Storage key of an attribute on the foreign chain, given the `AttributeOf` storage prefix there.
+pub fn attribute_storage_key<AccountId: Encode>(prefix: &[u8; 32], identity: &AccountId, id: &[u8; 32]) -> Vec<u8> {
+    map_storage_key(prefix, (identity, id).encode())
+}
+
+/// Storage key of the issuer of an attribute on the foreign chain, given the `AttributeOf`
+/// storage prefix there, whose first half is the prefix of the pallet.
+pub fn issuer_storage_key<AccountId: Encode>(prefix: &[u8; 32], identity: &AccountId, id: &[u8; 32]) -> Vec<u8> {
+    let prefix = [&prefix[..16], &twox_128(b"AttributeIssuer")[..]].concat();
+    map_storage_key(&prefix, (identity, id).encode())
+}
+
+/// Storage key of the `System::Number` of the foreign block.
+pub fn block_number_storage_key() -> Vec<u8> {
+    [twox_128(b"System"), twox_128(b"Number")].concat()
+}
+
+/// Reads the values of storage keys from a proof of a foreign state root, `None` for the keys
+/// proven absent. Returns `None` if the proof does not cover all the keys.
+pub fn read_proof(state_root: &H256, proof: Vec<Vec<u8>>, keys: &[&[u8]]) -> Option<Vec<Option<Vec<u8>>>> {
+    let db = StorageProof::new(proof).into_memory_db::<BlakeTwo256>();
+    keys.iter()
+        .map(|key| read_trie_value::<LayoutV1<BlakeTwo256>, _>(&db, state_root, key, None, None).ok())
+        .collect()
+}
@@ -0,0 +1,420 @@
+//! Cross-pallet flows, run against a runtime with balances, scheduler, proxy and multisig.
+
+use crate as pallet_did;
+use crate::did::Did;
+use crate::mock::{account_key, account_pair, AccountId};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+    assert_ok, parameter_types,
+    traits::{ConstU16, ConstU32, ConstU64, EqualPrivilegeOnly, Hooks, InstanceFilter},
+    weights::Weight,
+};
+use frame_system::{EnsureRoot, EnsureSigned, RawOrigin};
+use scale_info::TypeInfo;
+use sp_core::{sr25519, Pair, H256};
+use sp_io::hashing::blake2_256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+    RuntimeDebug,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Runtime>;
+type Block = frame_system::mocking::MockBlock<Runtime>;
+type Balance = u64;
+
+frame_support::construct_runtime!(
+    pub enum Runtime where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system,
+        Timestamp: pallet_timestamp,
+        Balances: pallet_balances,
+        Preimage: pallet_preimage,
+        Scheduler: pallet_scheduler,
+        Proxy: pallet_proxy,
+        Multisig: pallet_multisig,
+        DID: pallet_did,
+    }
+);
+
+impl frame_system::Config for Runtime {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ConstU16<42>;
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_timestamp::Config for Runtime {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<5>;
+    type WeightInfo = ();
+}
+
+impl pallet_balances::Config for Runtime {
+    type Balance = Balance;
+    type DustRemoval = ();
+    type RuntimeEvent = RuntimeEvent;
+    type ExistentialDeposit = ConstU64<1>;
+    type AccountStore = System;
+    type WeightInfo = ();
+    type MaxLocks = ConstU32<50>;
+    type MaxReserves = ConstU32<50>;
+    type ReserveIdentifier = [u8; 8];
+}
+
+impl pallet_preimage::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type WeightInfo = ();
+    type Currency = Balances;
+    type ManagerOrigin = EnsureRoot<AccountId>;
+    type BaseDeposit = ConstU64<2>;
+    type ByteDeposit = ConstU64<1>;
+}
+
+parameter_types! {
+    pub ReservedNamespaces: Vec<Vec<u8>> = vec![b"svc/".to_vec()];
+    pub ForeignAttributePrefix: [u8; 32] = [0u8; 32];
+    pub MaximumSchedulerWeight: Weight = Weight::from_ref_time(1_000_000_000_000);
+}
+
+impl pallet_scheduler::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeOrigin = RuntimeOrigin;
+    type PalletsOrigin = OriginCaller;
+    type RuntimeCall = RuntimeCall;
+    type MaximumWeight = MaximumSchedulerWeight;
+    // Signed schedules dispatch with the origin of their author.
+    type ScheduleOrigin = EnsureSigned<AccountId>;
+    type MaxScheduledPerBlock = ConstU32<10>;
+    type WeightInfo = ();
+    type OriginPrivilegeCmp = EqualPrivilegeOnly;
+    type Preimages = Preimage;
+}
+
+/// Proxy types of the runtime.
+#[derive(
+    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo,
+)]
+pub enum ProxyType {
+    Any,
+    DidManagement,
+}
+
+impl Default for ProxyType {
+    fn default() -> Self {
+        Self::Any
+    }
+}
+
+impl InstanceFilter<RuntimeCall> for ProxyType {
+    fn filter(&self, c: &RuntimeCall) -> bool {
+        match self {
+            ProxyType::Any => true,
+            ProxyType::DidManagement => matches!(c, RuntimeCall::DID(call) if call.is_did_management()),
+        }
+    }
+
+    fn is_superset(&self, o: &Self) -> bool {
+        self == o || *self == ProxyType::Any
+    }
+}
+
+impl pallet_proxy::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeCall = RuntimeCall;
+    type Currency = Balances;
+    type ProxyType = ProxyType;
+    type ProxyDepositBase = ConstU64<10>;
+    type ProxyDepositFactor = ConstU64<1>;
+    type MaxProxies = ConstU32<4>;
+    type WeightInfo = ();
+    type MaxPending = ConstU32<2>;
+    type CallHasher = BlakeTwo256;
+    type AnnouncementDepositBase = ConstU64<10>;
+    type AnnouncementDepositFactor = ConstU64<1>;
+}
+
+impl pallet_multisig::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeCall = RuntimeCall;
+    type Currency = Balances;
+    type DepositBase = ConstU64<10>;
+    type DepositFactor = ConstU64<1>;
+    type MaxSignatories = ConstU32<5>;
+    type WeightInfo = ();
+}
+
+impl pallet_did::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Public = sr25519::Public;
+    type Signature = sr25519::Signature;
+    type KeyHasher = BlakeTwo256;
+    type Moment = u64;
+    type Timestamp = Timestamp;
+    type ControllerOrigin = EnsureSigned<AccountId>;
+    type DefaultAttributeValidity = ConstU64<10_000>;
+    type MaxAttributeValidity = ConstU64<100_000>;
+    type DelegateValidityDefaults = ();
+    type DefaultDelegateValidity = ConstU64<100_000>;
+    type MaxDelegateRenewals = ConstU32<16>;
+    type ExplicitOwnershipOnly = frame_support::traits::ConstBool<false>;
+    type ReservedNamespaces = ReservedNamespaces;
+    type Currency = Balances;
+    type ProfileDeposit = ConstU64<10>;
+    type NamespaceDeposit = ConstU64<50>;
+    type MaxDisplayNameLength = ConstU32<32>;
+    type HandleDeposit = ConstU64<20>;
+    type MaxHandleLength = ConstU32<16>;
+    type AccessEscrowPeriod = ConstU64<20>;
+    type ForeignStateRoots = ();
+    type ForeignAttributePrefix = ForeignAttributePrefix;
+    type ContractAuthorizer = ();
+    type WeightInfo = ();
+    type EmitMetaTransactionRejections = frame_support::traits::ConstBool<false>;
+    type RequireAttestationConsent = frame_support::traits::ConstBool<false>;
+    type FeelessFirstWrites = ConstU32<0>;
+    type MaxFeelessWriteBytes = ConstU32<128>;
+    type Migration = ();
+    type MigrationStepSize = ConstU32<0>;
+    type DormancyPeriod = ();
+    type ReapGracePeriod = ConstU64<0>;
+    type ReapBounty = ConstU64<0>;
+    type MaxWatchers = ConstU32<0>;
+    type ExpiryNotice = ConstU64<0>;
+    type MaxExpiryAlerts = ConstU32<0>;
+    type MaxDependents = ConstU32<0>;
+    type MaxBytesPerDid = ConstU32<4_096>;
+    type Randomness = crate::mock::MockRandomness;
+    type ChallengeTtl = ConstU64<10>;
+    type MaxRecoveryVouches = ConstU32<8>;
+    type AttestationDeposit = ConstU64<5>;
+    type MaxPendingAttestations = ConstU32<16>;
+    type CredentialDeposit = ConstU64<10>;
+    type MaxCredentialsPerSubject = ConstU32<16>;
+    type MaxForeignStateRootAge = ConstU32<10>;
+    type WatcherDeposit = ConstU64<5>;
+    type MaxNonceExpiries = ConstU32<0>;
+    type MaxRescuers = ConstU32<8>;
+    type AdminOrigin = EnsureRoot<AccountId>;
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+    let mut storage = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+    pallet_balances::GenesisConfig::<Runtime> {
+        balances: vec![(account_key("Alice"), 1_000), (account_key("Bob"), 1_000)],
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::from(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
+
+fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        let next = System::block_number() + 1;
+        System::set_block_number(next);
+        Scheduler::on_initialize(next);
+    }
+}
+
+#[test]
+fn proxied_did_management() {
+    new_test_ext().execute_with(|| {
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let name = b"name".to_vec();
+        let value = b"value".to_vec();
+
+        assert_ok!(Proxy::add_proxy(
+            RawOrigin::Signed(alice).into(),
+            bob,
+            ProxyType::DidManagement,
+            0
+        ));
+        // Proxy deposit base and factor are reserved from the proxied account.
+        assert_eq!(Balances::reserved_balance(alice), 11);
+
+        let call = RuntimeCall::DID(pallet_did::Call::add_attribute {
+            identity: alice,
+            name: name.clone(),
+            value: value.clone(),
+            valid_for: None,
+        });
+        assert_ok!(Proxy::proxy(RawOrigin::Signed(bob).into(), alice, None, Box::new(call)));
+        assert_ok!(DID::valid_attribute(&alice, &name, &value));
+        assert_eq!(DID::updated_by(&alice).map(|(who, _, _)| who), Some(alice));
+
+        // Calls of other pallets are filtered out.
+        let call = RuntimeCall::Balances(pallet_balances::Call::transfer { dest: bob, value: 100 });
+        assert_ok!(Proxy::proxy(RawOrigin::Signed(bob).into(), alice, None, Box::new(call)));
+        System::assert_last_event(
+            pallet_proxy::Event::ProxyExecuted {
+                result: Err(frame_system::Error::<Runtime>::CallFiltered.into()),
+            }
+            .into(),
+        );
+        assert_eq!(Balances::free_balance(bob), 1_000);
+
+        // The proxy can not take the account over through its recovery, nor spend from it.
+        let calls = [
+            pallet_did::Call::set_recovery_threshold { identity: alice, threshold: Some(1) },
+            pallet_did::Call::vouch_recovery { identity: alice, rescuer: bob },
+            pallet_did::Call::set_keyless { keyless: true },
+            pallet_did::Call::purchase_access { identity: bob, name: name.clone(), max_price: 100 },
+        ];
+        for call in calls {
+            let call = Box::new(RuntimeCall::DID(call));
+            assert_ok!(Proxy::proxy(RawOrigin::Signed(bob).into(), alice, None, call));
+            System::assert_last_event(
+                pallet_proxy::Event::ProxyExecuted {
+                    result: Err(frame_system::Error::<Runtime>::CallFiltered.into()),
+                }
+                .into(),
+            );
+        }
+        assert_eq!(DID::recovery_threshold(alice), None);
+
+        assert_ok!(Proxy::remove_proxy(
+            RawOrigin::Signed(alice).into(),
+            bob,
+            ProxyType::DidManagement,
+            0
+        ));
+        assert_eq!(Balances::reserved_balance(alice), 0);
+    });
+}
+
+#[test]
+fn scheduled_owner_change() {
+    new_test_ext().execute_with(|| {
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+
+        let call = RuntimeCall::DID(pallet_did::Call::change_owner { identity: alice, new_owner: bob });
+        assert_ok!(Scheduler::schedule(RawOrigin::Signed(alice).into(), 5, None, 0, Box::new(call)));
+
+        run_to_block(4);
+        assert_eq!(DID::identity_owner(&alice), alice);
+
+        run_to_block(5);
+        assert_eq!(DID::identity_owner(&alice), bob);
+        System::assert_has_event(pallet_did::Event::OwnerChanged(alice, alice, bob, 5).into());
+    });
+}
+
+#[test]
+fn multisig_manages_its_did_with_deposit_reservations() {
+    new_test_ext().execute_with(|| {
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let mut signatories = vec![alice, bob];
+        signatories.sort();
+        let multisig = Multisig::multi_account_id(&signatories, 2);
+        let name = b"board".to_vec();
+        let value = b"alice,bob".to_vec();
+
+        let call = RuntimeCall::DID(pallet_did::Call::add_own_attribute {
+            name: name.clone(),
+            value: value.clone(),
+            valid_for: None,
+        });
+        let call_weight = Weight::from_ref_time(1_000_000_000);
+
+        assert_ok!(Multisig::approve_as_multi(
+            RawOrigin::Signed(alice).into(),
+            2,
+            vec![bob],
+            None,
+            blake2_256(&call.encode()),
+            Weight::zero()
+        ));
+        // The deposit of the pending operation is reserved from its first approver.
+        assert_eq!(Balances::reserved_balance(alice), 12);
+        let timepoint = Multisig::timepoint();
+
+        assert_ok!(Multisig::as_multi(
+            RawOrigin::Signed(bob).into(),
+            2,
+            vec![alice],
+            Some(timepoint),
+            Box::new(call),
+            call_weight
+        ));
+        assert_eq!(Balances::reserved_balance(alice), 0);
+        assert_ok!(DID::valid_attribute(&multisig, &name, &value));
+    });
+}
+
+#[test]
+fn keyless_multisig_owner_signs_through_its_delegates() {
+    new_test_ext().execute_with(|| {
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let charlie = account_key("Charlie");
+        let mut signatories = vec![alice, bob];
+        signatories.sort();
+        let multisig = Multisig::multi_account_id(&signatories, 2);
+        let signer_pair = account_pair("Signer");
+        let signer = signer_pair.public();
+
+        // Alice approves and Bob executes each call of the multisig.
+        let dispatch_as_multisig = |call: RuntimeCall| {
+            assert_ok!(Multisig::approve_as_multi(
+                RawOrigin::Signed(alice).into(),
+                2,
+                vec![bob],
+                None,
+                blake2_256(&call.encode()),
+                Weight::zero()
+            ));
+            let timepoint = Multisig::timepoint();
+            assert_ok!(Multisig::as_multi(
+                RawOrigin::Signed(bob).into(),
+                2,
+                vec![alice],
+                Some(timepoint),
+                Box::new(call),
+                Weight::from_ref_time(1_000_000_000)
+            ));
+        };
+        dispatch_as_multisig(RuntimeCall::DID(pallet_did::Call::set_keyless { keyless: true }));
+        dispatch_as_multisig(RuntimeCall::DID(pallet_did::Call::add_own_delegate {
+            delegate: signer,
+            delegate_type: pallet_did::types::SIGNING_DELEGATE_TYPE.to_vec(),
+            key_type: pallet_did::types::KeyType::Sr25519,
+            valid_for: None,
+        }));
+        assert_ok!(DID::change_owner(RawOrigin::Signed(charlie).into(), charlie, multisig));
+
+        let msg = b"statement of charlie".to_vec();
+        let signature = signer_pair.sign(&msg);
+        assert_ok!(DID::valid_signer(&charlie, &signature, &msg, &signer));
+        assert_ok!(DID::valid_signer(&multisig, &signature, &msg, &signer));
+    });
+}
@@ -52,6 +52,7 @@
 //!
 //! ### Dispatchable Functions
 //!
+//! * `register_did` - Registers the identity of the origin with an explicit owner record.
 //! * `change_owner` - Transfers an `identity` represented as an `AccountId` from the owner account (`origin`) to a `target` account.
 //! * `add_delegate` - Creates a new delegate with an expiration period and for a specific purpose.
 //! * `revoke_delegate` - Revokes an identity's delegate by setting its expiration to the current block number.
@@ -59,18 +60,98 @@
 //! * `revoke_attribute` - Revokes an attribute/property from an identity. Sets its expiration period to the actual block number.
 //! * `delete_attribute` - Removes an attribute/property from an identity. This attribute/property becomes unavailable.
 //! * `execute` - Executes off-chain signed transactions.
+//! * `add_encrypted_attribute` - Creates a new attribute holding a value encrypted for a key agreement key of the identity.
+//! * `create_org_did` - Registers the DID of an organization controlled by a `ControllerOrigin`, such as a collective.
+//! * `add_own_attribute`, `delete_own_attribute`, `add_own_delegate` - Shortcuts acting on the identity of the origin.
+//! * `revoke_delegates_of_type` - Revokes all the delegates of a type of an identity, e.g. all its session keys.
+//! * `renew_delegates` - Extends the validity of many delegates of an identity in a single call.
+//! * `anchor_hash` - Anchors a document hash to an identity, as a timestamped proof of existence.
+//! * `pause`, `unpause` - Halts or resumes all DID mutations, from the `AdminOrigin`.
+//! * `heartbeat` - Attests that a delegate key is still in use.
+//! * `register_presentation_nonce`, `consume_presentation_nonce` - Anchors and consumes the challenge nonces of a verifier.
+//! * `request_challenge` - Registers a random challenge nonce of a verifier, from the on-chain randomness.
+//! * `import_foreign_attestation` - Imports an attribute from a storage proof of a foreign chain.
+//! * `set_recovery_threshold`, `vouch_recovery`, `clear_recovery_vouches` - Manages the guardians vouches authorizing account recovery.
+//! * `set_profile`, `clear_profile` - Manages the human-readable profile of an identity, held with a deposit.
+//! * `report_issuer_misbehavior` - Suspends an issuer, from the `AdminOrigin`, disputing its attestations.
+//! * `ban_attribute_name`, `unban_attribute_name` - Blocks or allows again new writes of attribute names under a prefix, from the `AdminOrigin`.
+//! * `import_identity` - Imports an identity exported from another DID registry, from the `AdminOrigin`.
+//! * `claim_namespace`, `transfer_namespace`, `release_namespace` - Manages the attribute namespaces an identity attests under, held with a deposit.
+//! * `set_namespace_opt_in` - Opts an identity in or out of the attestations of a namespace owner.
+//! * `claim_handle`, `unclaim_handle` - Manages the unique readable handle of an identity, held with a deposit.
+//! * `issue_credential` - Issues a credential of a schema to a subject, stored on chain, from a registered issuer.
+//! * `revoke_credential` - Revokes a credential, from its issuer or the owner of its subject.
+//! * `accept_attestation`, `reject_attestation` - Consents to or declines a third-party attribute or credential awaiting the subject.
+//! * `set_keyless` - Declares a multisig or pure proxy account keyless, its signing delegates signing for the identities it owns.
+//! * `set_attribute_cosigner`, `add_cosigned_attribute` - Requires a second party, e.g. a compliance officer, to co-sign the writes of an attribute.
+//! * `set_signature_policy` - Restricts the signature schemes an identity accepts from its signers.
+//! * `watch_identity`, `unwatch_identity` - Subscribes to or unsubscribes from the expiry alerts of the delegates and attributes of an identity.
+//! * `remove_watcher` - Removes a watcher of an identity, unreserving its deposit.
+//! * `flag_dormant`, `reap_identity` - Flags an identity without activity as dormant, and reaps it for a bounty after a grace period.
+//! * `set_attribute_dependency` - Declares that an attribute depends on a parent attribute, invalid with it.
+//! * `allow_dependent` - Allows an identity to declare its attributes dependent on an attribute attested by the origin.
+//! * `set_access_counter`, `log_access` - Opts an attribute in read counting, and counts a read of a verifier.
+//! * `set_access_price` - Prices the reads of an encrypted attribute.
+//! * `purchase_access`, `confirm_access`, `refund_access` - Escrows, releases or refunds the fee of a paid attribute read.
 //!
 //! ### Public Functions
 //!
+//! * `ensure_unreserved_namespace` - Rejects attribute names reserved to the structured extrinsics.
+//! * `paused` - Returns `true` while DID mutations are halted.
+//! * `migration_cursor` - Get the cursor of the ongoing storage migration, if any.
+//! * `ensure_controller` - Get the account acting on an identity, from a signed origin or a `ControllerOrigin`.
 //! * `is_owner` - Returns a boolean value. `True` if the `account` owns the `identity`.
 //! * `identity_owner` - Get the account owner of an `identity`.
+//! * `try_identity_owner` - Get the account owner of an `identity`, only if explicitly set.
+//! * `registered` - Returns `true` if the `identity` has an explicit owner record.
 //! * `valid_delegate` - Validates if a delegate belongs to an identity and it has not expired.
 //!    The identity owner has all provileges and is considered as delegate with all permissions.
 //! * `valid_listed_delegate` - Returns a boolean value. `True` if the `delegate` belongs the `identity` delegates list.
+//! * `delegate_key_multibase` - Get the `publicKeyMultibase` of a delegate key, encoded with its multicodec key type.
+//! * `proof_of_existence` - Get the block and time an identity anchored a document hash at.
+//! * `nonces_of` - Get all the `(name, nonce)` attribute nonces of an `identity`.
+//! * `last_seen` - Get the last block a delegate attested its liveness at.
+//! * `inactive_delegates` - Get the valid delegates of an `identity` inactive for more than a number of blocks.
+//! * `valid_delegates` - Get the not expired delegates of an `identity`.
+//! * `dry_run_execute` - Checks an off-chain transaction as `execute` would, without writing state.
+//! * `attributes_of`, `delegates_of`, `identities_of_owner` - Page through the attributes and delegates of an `identity`, or the identities of an owner, with a cursor.
+//! * `did_document` - Resolve the `DidDocument` of an `identity`, serializable to JSON-LD with the `std` feature.
+//! * `export_capability` - Get a valid delegate of an `identity` as a zcap capability, serializable to zcap-ld with the `std` feature.
+//! * `verify_capability` - Validates a capability signed off chain against the current delegations.
+//! * `valid_presentation_nonce` - Returns `true` if a challenge nonce of a verifier can still be consumed.
+//! * `operation_cost` - Get the weight of a call and the deposit it would reserve, to preview its cost before signing.
+//! * `query_cost` - Get the weight of a read query, such as `valid_signer` or `did_document`, from the `WeightInfo` of the runtime.
+//! * `attestation_status` - Get whether an attribute is valid, expired or disputed after its issuer was suspended.
+//! * `suspension_of` - Get the evidence hash an issuer was suspended for.
+//! * `namespace_of` - Get the owner identity, depositor and deposit of a claimed namespace.
+//! * `ensure_attestor` - Authorizes attribute writes: by the namespace owner for claimed namespaces, by the identity owner otherwise.
+//! * `verify_claims_batch` - Validates many `(identity, name, value)` attribute claims, reading each attribute once.
+//! * `storage_usage` - Get the bytes of attributes and delegates stored for an `identity`, up to `MaxBytesPerDid`.
+//! * `attribute_metadata` - Get the blocks and times an attribute was created and last updated at.
+//! * `valid_attribute_fresh_by_time` - Validate an attribute updated at most a wall-clock duration ago.
+//! * `valid_attribute_fresh` - Validates an attribute updated less than `max_age` blocks ago.
+//! * `access_count` - Get the number of reads logged for an attribute, if counted.
+//! * `document_hash` - Get the rolling hash of the updates of an `identity`, to detect changes since a resolution.
+//! * `identity_by_handle` - Get the identity a readable handle, e.g. `alice`, refers to.
+//! * `credentials_of`, `credential_status` - Get the credentials issued on chain to a subject, and whether they are valid.
+//! * `pending_attestations` - Get the third-party attributes and credentials awaiting the consent of an `identity`.
+//! * `banned_names` - Get the attribute name prefixes new writes are blocked under.
+//! * `attribute_id`, `key_hash` - Get the id of an attribute and the hashes of its digest, computed with the `KeyHasher`.
+//! * `valid_contract_delegate` - Validates a caller authorized by the policy of a contract delegate of an `identity`.
+//! * `last_activity`, `activity_status` - Get the last activity of an `identity`, and whether it is active, inactive or dormant.
+//! * `attribute_provenance` - Get whether an attribute was written locally or imported from a foreign chain.
+//! * `can_recover`, `recovered` - `RecoveryAuthority` of the accounts of an identity, for the recovery pallets of the runtime.
+//! * `default_delegate_validity` - Get the validity of the delegates of a type added without validity.
+//! * `profile_of` - Get the display name, avatar and biography hashes of an `identity`.
+//! * `meta_transaction_payload` - Get the payload to sign in an off-chain transaction.
+//! * `next_meta_nonce` - Get the nonce to sign in the next off-chain transaction of an `identity`.
+//! * `verify_external_did_signature` - Validates a signature from an off-chain `did:key` identity.
+//! * `verify_did_web_signature` - Validates a signature from an off-chain `did:web` identity, given its DID Document.
 //! * `valid_attribute` - Validates if an attribute belongs to an identity and it has not expired.
 //! * `attribute_and_id` - Get the `attribute` and its `hash` identifier.
+//! * `encrypted_attribute` - Get the encryption envelope of an encrypted attribute.
 //! * `check_signature` - Validates the signer from a signature.
-//! * `valid_signer` - Validates a signature from a valid signer delegate or the owner of an identity.
+//! * `valid_signer` - Validates a signature from a valid signer or capability invocation delegate, or the owner of an identity, or a signing delegate of its keyless owner.
 //!
 //! *
 
@@ -78,33 +159,80 @@
 #![recursion_limit = "256"]
 
 
+pub mod capability;
+#[cfg(feature = "contracts")]
+pub mod chain_extension;
+pub mod contract;
 pub mod did;
+pub mod document;
+pub mod error_codes;
+pub mod extension;
+pub mod external_did;
+pub mod feeless;
+pub mod foreign;
+pub mod migrations;
+pub mod multicodec;
+#[cfg(feature = "frontier")]
+pub mod precompile;
+pub mod recovery;
+pub mod runtime_api;
 pub mod types;
+pub mod weights;
 
-#[cfg(test)]
-mod mock;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock;
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(test)]
+mod integration_tests;
+
+#[cfg(test)]
+mod conformance;
+
 pub use pallet::*;
+pub use weights::WeightInfo;
 #[frame_support::pallet]
 pub mod pallet {
     use frame_support::{
         pallet_prelude::*,
-        dispatch::DispatchResult, ensure,traits::{Time, IsType},
-        sp_runtime::traits::{Scale, IdentifyAccount, Member, Verify},
+        dispatch::{DispatchResult, GetDispatchInfo, PostDispatchInfo}, ensure, storage::{with_storage_layer, with_transaction, PrefixIterator}, weights::Weight, traits::{BalanceStatus, Currency, EnsureOrigin, GenesisBuild, Randomness, ReservableCurrency, Time, IsType},
+        sp_runtime::TransactionOutcome,
+        sp_runtime::traits::{Bounded, CheckedAdd, Hash as KeyHash, One, Saturating, Scale, Zero, IdentifyAccount, Member, Verify},
     };
 	use frame_system::{pallet_prelude::*, ensure_signed};
-	use sp_std::{prelude::*, convert::TryInto, vec::Vec};
+	use sp_std::{prelude::*, collections::btree_map::BTreeMap, convert::TryInto, vec::Vec};
     use codec::{Decode, Encode};
     use sp_io::hashing::blake2_256;
     use scale_info::StaticTypeInfo;
 	use crate::types::*;
+    use crate::capability::Capability;
+    use crate::contract::ContractAuthorizer;
     use crate::did::Did;
+    use crate::document::{self, DidDocument, VerificationMethod};
+    use crate::external_did::{self, ExternalDid, WebDidDocument};
+    use crate::foreign::{self, ForeignStateRoots};
+    use crate::migrations::SteppedMigration;
+    use crate::multicodec;
+    use crate::recovery::RecoveryAuthority;
+    use crate::weights::WeightInfo;
+
 
+    pub type BalanceOf<T> =
+        <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+    /// Storage version of the pallet. Bumping it starts the `Migration` of the runtime.
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(7);
 
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	#[pallet::without_storage_info]
 	#[pallet::generate_store(pub(super) trait Store)]
 	pub struct Pallet<T>(_);
@@ -113,96 +241,1021 @@ pub mod pallet {
 	pub trait Config: frame_system::Config {
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
         type Public: IdentifyAccount<AccountId = Self::AccountId>;
-        type Signature: Verify<Signer = Self::Public> + Member + Decode + Encode + TypeInfo;
+        type Signature: Verify<Signer = Self::Public> + SignatureScheme + Member + Decode + Encode + TypeInfo;
+        /// Hashing of attribute ids and digests, e.g. `BlakeTwo256`, or `Keccak256` so that
+        /// verifiers on EVM chains recompute them with their precompiles.
+        ///
+        /// Fixed at genesis: the stored attributes keep the ids of the hasher they were written
+        /// with, so changing it orphans them. Foreign chains attestations are imported from must
+        /// use the same hasher.
+        type KeyHasher: KeyHash<Output = sp_core::H256>;
 		type Moment: Parameter
 		+ Default
+		+ Saturating
+		+ PartialOrd
 		+ Scale<Self::BlockNumber, Output = Self::Moment>
 		+ Copy
 		+ MaxEncodedLen
 		+ StaticTypeInfo;
 		type Timestamp: Time<Moment=Self::Moment> ;
+        /// Origin acting as a keyless identity, e.g. a governance origin or a pallet account.
+        /// Its success value is the account of the controlled identity.
+        type ControllerOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = Self::AccountId>;
+        /// Validity, in blocks, of attributes added without an explicit validity.
+        #[pallet::constant]
+        type DefaultAttributeValidity: Get<Self::BlockNumber>;
+        /// Maximum validity, in blocks, of an attribute.
+        #[pallet::constant]
+        type MaxAttributeValidity: Get<Self::BlockNumber>;
+        /// Validity, in blocks, of delegates added without an explicit validity, per delegate type.
+        /// E.g. authentication keys for 6 months and session keys for a day.
+        #[pallet::constant]
+        type DelegateValidityDefaults: Get<Vec<(Vec<u8>, Self::BlockNumber)>>;
+        /// Validity, in blocks, of delegates added without an explicit validity,
+        /// for the delegate types without `DelegateValidityDefaults` entry.
+        #[pallet::constant]
+        type DefaultDelegateValidity: Get<Self::BlockNumber>;
+        /// Maximum number of delegates renewed in a single call.
+        #[pallet::constant]
+        type MaxDelegateRenewals: Get<u32>;
+        /// When `true`, identities must be registered (explicitly owned) before being managed,
+        /// instead of being owned by their own key by default.
+        #[pallet::constant]
+        type ExplicitOwnershipOnly: Get<bool>;
+        /// Attribute name prefixes, e.g. `svc/`, reserved to the structured extrinsics
+        /// of the pallet. Raw attribute calls can not write them.
+        #[pallet::constant]
+        type ReservedNamespaces: Get<Vec<Vec<u8>>>;
+        /// Currency paid to read priced attributes.
+        type Currency: ReservableCurrency<Self::AccountId>;
+        /// Deposit reserved for the profile of an identity.
+        #[pallet::constant]
+        type ProfileDeposit: Get<BalanceOf<Self>>;
+        /// Deposit reserved for a claimed attribute namespace.
+        #[pallet::constant]
+        type NamespaceDeposit: Get<BalanceOf<Self>>;
+        /// Maximum length of a profile display name.
+        #[pallet::constant]
+        type MaxDisplayNameLength: Get<u32>;
+        /// Deposit reserved for the handle of an identity.
+        #[pallet::constant]
+        type HandleDeposit: Get<BalanceOf<Self>>;
+        /// Maximum length of a handle.
+        #[pallet::constant]
+        type MaxHandleLength: Get<u32>;
+        /// Blocks an identity has to confirm a paid access before the reader can be refunded.
+        #[pallet::constant]
+        type AccessEscrowPeriod: Get<Self::BlockNumber>;
+        /// State roots of the foreign consensus attestations are imported from.
+        type ForeignStateRoots: ForeignStateRoots;
+        /// Storage prefix of `AttributeOf` on the foreign chain: `twox128(pallet) ++ twox128(storage)`.
+        #[pallet::constant]
+        type ForeignAttributePrefix: Get<[u8; 32]>;
+        /// Queries the `is_authorized` policy of the contract delegates, `()` if the chain
+        /// has no contracts.
+        type ContractAuthorizer: ContractAuthorizer<Self::AccountId>;
+        /// Weights of the read paths, used to price queries.
+        type WeightInfo: WeightInfo;
+        /// When `true`, `execute` reports rejected transactions in a `MetaTransactionRejected`
+        /// event and succeeds, so relayers batching many transactions see which ones failed.
+        #[pallet::constant]
+        type EmitMetaTransactionRejections: Get<bool>;
+        /// When `true`, attributes attested by namespace owners and credentials issued to an
+        /// identity stay pending until its owner accepts them with `accept_attestation`.
+        #[pallet::constant]
+        type RequireAttestationConsent: Get<bool>;
+        /// Number of brand-new identities per block whose first `register_did` or `add_attribute`
+        /// skips the fee payment, with `feeless::SkipFeelessFirstWrite`. `0` disables feeless writes.
+        #[pallet::constant]
+        type FeelessFirstWrites: Get<u32>;
+        /// Maximum bytes of the name and value of a feeless `add_attribute`, bounding the storage
+        /// written without fees.
+        #[pallet::constant]
+        type MaxFeelessWriteBytes: Get<u32>;
+        /// Migration run across many blocks after a storage version bump, e.g.
+        /// `migrations::BackfillAttributeMetadata`. The migrations of the storage versions the
+        /// chain already has are skipped.
+        type Migration: SteppedMigration;
+        /// Maximum number of entries migrated per block.
+        #[pallet::constant]
+        type MigrationStepSize: Get<u32>;
+        /// Blocks without activity after which anyone can flag an identity dormant.
+        /// `None` disables the dormancy policy.
+        #[pallet::constant]
+        type DormancyPeriod: Get<Option<Self::BlockNumber>>;
+        /// Blocks a dormant identity has to show activity again before anyone can reap it.
+        #[pallet::constant]
+        type ReapGracePeriod: Get<Self::BlockNumber>;
+        /// Bounty paid to the reaper of a dormant identity, out of its profile and handle deposits.
+        #[pallet::constant]
+        type ReapBounty: Get<BalanceOf<Self>>;
+        /// Maximum number of watchers of an identity.
+        #[pallet::constant]
+        type MaxWatchers: Get<u32>;
+        /// Blocks before the expiry of a delegate or attribute of a watched identity its
+        /// watchers are alerted at.
+        #[pallet::constant]
+        type ExpiryNotice: Get<Self::BlockNumber>;
+        /// Maximum number of expiry alerts queued per block, further ones are dropped with an
+        /// `ExpiryAlertDropped` event.
+        #[pallet::constant]
+        type MaxExpiryAlerts: Get<u32>;
+        /// Maximum number of attributes depending on an attribute.
+        #[pallet::constant]
+        type MaxDependents: Get<u32>;
+        /// Maximum number of bytes of the attributes and delegates stored for an identity.
+        #[pallet::constant]
+        type MaxBytesPerDid: Get<u32>;
+        /// Source of the random challenges of verifiers.
+        type Randomness: Randomness<Self::Hash, Self::BlockNumber>;
+        /// Number of blocks a random challenge can be consumed for.
+        #[pallet::constant]
+        type ChallengeTtl: Get<Self::BlockNumber>;
+        /// Maximum number of guardians vouching for a rescuer, and of recovery thresholds.
+        #[pallet::constant]
+        type MaxRecoveryVouches: Get<u32>;
+        /// Deposit reserved from the issuer of a third-party claim while it awaits the consent of
+        /// its subject, returned once the claim is accepted or rejected.
+        #[pallet::constant]
+        type AttestationDeposit: Get<BalanceOf<Self>>;
+        /// Maximum number of third-party claims awaiting the consent of an identity.
+        #[pallet::constant]
+        type MaxPendingAttestations: Get<u32>;
+        /// Deposit reserved from the issuer of an on-chain credential, returned once it is revoked.
+        #[pallet::constant]
+        type CredentialDeposit: Get<BalanceOf<Self>>;
+        /// Maximum number of on-chain credentials of a subject.
+        #[pallet::constant]
+        type MaxCredentialsPerSubject: Get<u32>;
+        /// Age, in foreign blocks behind `ForeignStateRoots::latest_block`, past which the state
+        /// roots are too stale to import attestations from.
+        #[pallet::constant]
+        type MaxForeignStateRootAge: Get<u32>;
+        /// Deposit reserved from a watcher per watched identity, released when it unwatches it
+        /// or the owner removes it, so watching identities is not free.
+        #[pallet::constant]
+        type WatcherDeposit: Get<BalanceOf<Self>>;
+        /// Maximum number of presentation nonces expiring per block, further registrations fail.
+        #[pallet::constant]
+        type MaxNonceExpiries: Get<u32>;
+        /// Maximum number of rescuers with vouches per identity, bounding the vouches its
+        /// recovery clears.
+        #[pallet::constant]
+        type MaxRescuers: Get<u32>;
+        /// Origin allowed to pause and unpause the pallet, and adjudicating issuer misbehavior.
+        type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
     }
 
+    /// Delegates of identities, by identity and `(delegate_type, delegate)`, so the delegates
+    /// of an identity are iterable.
     #[pallet::storage]
     #[pallet::getter(fn delegate_of)]
     pub type DelegateOf<T: Config> =
-    StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>, T::AccountId), Option<T::BlockNumber>, OptionQuery>;
+    StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        (Vec<u8>, T::AccountId),
+        Delegate<T::BlockNumber>,
+        OptionQuery,
+    >;
+
+    /// Index of the delegates of identities by `(identity, delegate_type)`. Revoked delegates
+    /// leave it, and the expired ones when their type is revoked.
+    #[pallet::storage]
+    pub type DelegatesOfType<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), Blake2_128Concat, T::AccountId, (), OptionQuery>;
     
     
+    /// Attributes of identities, by `(identity, id)` where `id` is the hash of
+    /// `(identity, name, nonce)`.
     #[pallet::storage]
     #[pallet::getter(fn attribute_of)]
     pub type AttributeOf<T: Config> =
     StorageMap<_, Blake2_128Concat, (T::AccountId,  [u8; 32]), Attribute<T::BlockNumber, T::Moment>, OptionQuery>;
     
+    /// Nonce of the next attribute of an identity named `name`.
     #[pallet::storage]
     #[pallet::getter(fn nonce_of)]
-    pub(super) type AttributeNonce<T: Config> =
-    StorageMap<_, Twox64Concat, (T::AccountId, Vec<u8>), u64, ValueQuery>;
+    pub type AttributeNonce<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, Vec<u8>, u64, ValueQuery>;
+
+    /// `(value_hash, validity)` of the current attribute of identities, by `key_hash(name)`.
+    /// Validating an attribute is one read of its digest.
+    #[pallet::storage]
+    #[pallet::getter(fn attribute_digest)]
+    pub type AttributeDigests<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Identity, [u8; 32], ([u8; 32], T::BlockNumber), OptionQuery>;
+
+    /// Nonce of the next off-chain signed transaction of an identity.
+    #[pallet::storage]
+    #[pallet::getter(fn meta_nonce)]
+    pub type MetaNonce<T: Config> =
+    StorageMap<_, Blake2_128Concat, T::AccountId, u64, ValueQuery>;
     
+    /// Owners of the identities not owned by their own key, or explicitly registered.
     #[pallet::storage]
     #[pallet::getter(fn owner_of)]
     pub type OwnerOf<T: Config> =
     StorageMap<_, Blake2_128Concat, T::AccountId, T::AccountId, OptionQuery>;
+
+    /// Index of `OwnerOf` by owner, so the identities of an owner are iterable.
+    #[pallet::storage]
+    pub type OwnedIdentities<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, T::AccountId, (), OptionQuery>;
     
+    /// Account, block and time of the last update of an identity.
     #[pallet::storage]
     #[pallet::getter(fn updated_by)]
     pub type UpdatedBy<T: Config> =
     StorageMap<_, Blake2_128Concat, T::AccountId, (T::AccountId, T::BlockNumber, T::Moment), OptionQuery>;
 
+    /// Last block an identity was active at: a heartbeat of one of its delegates, or an update
+    /// of its owner or signers.
+    #[pallet::storage]
+    #[pallet::getter(fn last_heartbeat)]
+    pub type LastHeartbeat<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber, OptionQuery>;
+
+    /// Signature schemes accepted from the signers of identities, all if unset.
+    #[pallet::storage]
+    #[pallet::getter(fn signature_policy)]
+    pub type SignaturePolicies<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, Vec<KeyType>, OptionQuery>;
+
+    /// Bytes of the attribute names and values, and of the delegate types and accounts,
+    /// stored for identities.
+    #[pallet::storage]
+    #[pallet::getter(fn storage_usage)]
+    pub type StorageUsage<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+    /// Bytes counted in the storage usage of an identity, by identity and attribute id or
+    /// delegate key, so only counted records are released.
+    #[pallet::storage]
+    pub type StorageCharges<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, [u8; 32], u32, OptionQuery>;
+
+    /// Accounts declared keyless, e.g. multisig and pure proxy accounts, whose signing
+    /// delegates sign for the identities they own.
+    #[pallet::storage]
+    pub type KeylessAccounts<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+    /// Identity whose signers co-sign the writes of the attributes of identities, by name.
+    #[pallet::storage]
+    #[pallet::getter(fn attribute_cosigner)]
+    pub type AttributeCosigners<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, Vec<u8>, T::AccountId, OptionQuery>;
+
+    /// Accounts alerted before the delegates and attributes of identities expire.
+    #[pallet::storage]
+    #[pallet::getter(fn watchers_of)]
+    pub type Watchers<T: Config> =
+    StorageMap<_, Blake2_128Concat, T::AccountId, BoundedVec<T::AccountId, T::MaxWatchers>, ValueQuery>;
+
+    /// Deposits reserved from the watchers of identities.
+    #[pallet::storage]
+    pub type WatcherDeposits<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, T::AccountId, BalanceOf<T>, OptionQuery>;
+
+    /// `(identity, item, expiry)` expiry alerts of watched identities, by the block they are emitted at.
+    #[pallet::storage]
+    #[pallet::getter(fn expiry_alerts)]
+    pub type ExpiryAlerts<T: Config> =
+    StorageMap<
+        _,
+        Blake2_128Concat,
+        T::BlockNumber,
+        BoundedVec<(T::AccountId, WatchedItem<T::AccountId>, T::BlockNumber), T::MaxExpiryAlerts>,
+        ValueQuery,
+    >;
+
+    /// `(parent_identity, parent_name)` attribute the attributes of identities depend on, by name.
+    #[pallet::storage]
+    #[pallet::getter(fn attribute_dependency)]
+    pub type AttributeDependencies<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, Vec<u8>, (T::AccountId, Vec<u8>), OptionQuery>;
+
+    /// Index of the `(identity, name)` attributes depending on each `(identity, name)` attribute.
+    #[pallet::storage]
+    pub type Dependents<T: Config> =
+    StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        (T::AccountId, Vec<u8>),
+        Blake2_128Concat,
+        (T::AccountId, Vec<u8>),
+        (),
+        OptionQuery,
+    >;
+
+    /// Identities allowed by the attestor of an `(identity, name)` attribute to depend on it.
+    #[pallet::storage]
+    #[pallet::getter(fn dependent_allowed)]
+    pub type AllowedDependents<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+    /// `(identity, name)` attributes with dependents, by the block they expire at.
+    #[pallet::storage]
+    #[pallet::getter(fn dependency_expiries)]
+    pub type DependencyExpiries<T: Config> =
+    StorageMap<_, Blake2_128Concat, T::BlockNumber, BoundedVec<(T::AccountId, Vec<u8>), T::MaxExpiryAlerts>, ValueQuery>;
+
+    /// Block identities were flagged dormant at, cleared by any activity.
+    #[pallet::storage]
+    #[pallet::getter(fn dormant_since)]
+    pub type Dormant<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber, OptionQuery>;
+
 
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
+        /// `(identity, previous_owner, new_owner, block)`
         OwnerChanged(T::AccountId, T::AccountId, T::AccountId, T::BlockNumber),
-        DelegateAdded(T::AccountId, Vec<u8>, T::AccountId, Option<T::BlockNumber>),
+        /// `(identity, delegate_type, delegate, valid_for)`
+        DelegateAdded(T::AccountId, Vec<u8>, T::AccountId, Option<Validity<T::BlockNumber>>),
+        /// `(identity, delegate_type, delegate)`
         DelegateRevoked(T::AccountId, Vec<u8>, T::AccountId),
-        AttributeAdded(T::AccountId,Vec<u8>, Option<T::BlockNumber>),
-        AttributeRevoked(T::AccountId,Vec<u8>,T::BlockNumber),
-        AttributeDeleted(T::AccountId,Vec<u8>,T::BlockNumber),
-        AttributeTransactionExecuted(AttributeTransaction<T::Signature,T::AccountId>),     
+        /// `(identity, delegate_type, revoked)`, the number of valid delegates revoked.
+        DelegatesOfTypeRevoked(T::AccountId, Vec<u8>, u32),
+        /// `(identity, name, valid_for)`
+        AttributeAdded(T::AccountId, Vec<u8>, Option<Validity<T::BlockNumber>>),
+        /// `(identity, name, block)`
+        AttributeRevoked(T::AccountId, Vec<u8>, T::BlockNumber),
+        /// `(identity, name, block)`
+        AttributeDeleted(T::AccountId, Vec<u8>, T::BlockNumber),
+        /// An off-chain signed transaction was applied.
+        AttributeTransactionExecuted(AttributeTransaction<T::Signature, T::AccountId>),
+        /// `(identity)` of an organization controlled by a `ControllerOrigin`.
+        OrgDidCreated(T::AccountId),
+        /// `(identity, hash, block)`
+        HashAnchored(T::AccountId, [u8; 32], T::BlockNumber),
+        /// `(identity, delegate_type, delegate, validity)`
+        DelegateRenewed(T::AccountId, Vec<u8>, T::AccountId, T::BlockNumber),
+        /// `(identity)`
+        DidRegistered(T::AccountId),
+        /// DID mutations are halted.
+        PalletPaused,
+        /// DID mutations are resumed.
+        PalletUnpaused,
+        /// `(identity, delegate_type, delegate, block)`
+        DelegateHeartbeat(T::AccountId, Vec<u8>, T::AccountId, T::BlockNumber),
+        /// `(verifier, nonce, last_valid_block)`
+        PresentationNonceRegistered(T::AccountId, [u8; 32], T::BlockNumber),
+        /// `(verifier, nonce)`
+        PresentationNonceConsumed(T::AccountId, [u8; 32]),
+        /// `(identity, name, block, moment)` of the creation or revocation of an attribute.
+        AttributeTimestamped(T::AccountId, Vec<u8>, T::BlockNumber, T::Moment),
+        /// `(verifier, challenge, expiry)`
+        ChallengeIssued(T::AccountId, [u8; 32], T::BlockNumber),
+        /// `(identity, name, price)`, no price when unlisted.
+        AccessPriceSet(T::AccountId, Vec<u8>, Option<BalanceOf<T>>),
+        /// `(identity, name, reader, fee)`: the owner shares the view key off chain.
+        AccessPurchased(T::AccountId, Vec<u8>, T::AccountId, BalanceOf<T>),
+        /// `(identity, name, reader, fee)`
+        AccessGranted(T::AccountId, Vec<u8>, T::AccountId, BalanceOf<T>),
+        /// `(identity, name, reader, fee)`
+        AccessRefunded(T::AccountId, Vec<u8>, T::AccountId, BalanceOf<T>),
+        /// `(identity, name, relay_block)`
+        ForeignAttestationImported(T::AccountId, Vec<u8>, u32),
+        /// `(identity, threshold)`
+        RecoveryThresholdSet(T::AccountId, Option<u32>),
+        /// `(identity, rescuer, guardian)`
+        RecoveryVouched(T::AccountId, T::AccountId, T::AccountId),
+        /// `(identity, rescuer)`
+        RecoveryVouchesCleared(T::AccountId, T::AccountId),
+        /// `(lost, rescuer)`
+        AccountRecovered(T::AccountId, T::AccountId),
+        /// `(identity, reason)`
+        MetaTransactionRejected(T::AccountId, DispatchError),
+        /// `(identity)`
+        ProfileSet(T::AccountId),
+        /// `(identity)`
+        ProfileCleared(T::AccountId),
+        /// `(issuer, evidence_hash)`
+        IssuerSuspended(T::AccountId, [u8; 32]),
+        /// `(name_or_prefix)`
+        AttributeNameBanned(Vec<u8>),
+        /// `(name_or_prefix)`
+        AttributeNameUnbanned(Vec<u8>),
+        /// `(prefix, owner)`
+        NamespaceClaimed(Vec<u8>, T::AccountId),
+        /// `(prefix, previous_owner, new_owner)`
+        NamespaceTransferred(Vec<u8>, T::AccountId, T::AccountId),
+        /// `(prefix)`
+        NamespaceReleased(Vec<u8>),
+        /// `(identity, prefix, opted_in)`
+        NamespaceOptInSet(T::AccountId, Vec<u8>, bool),
+        /// A storage migration started, DID mutations are rejected.
+        MigrationStarted,
+        /// The storage migration completed, and the storage version is bumped.
+        MigrationCompleted,
+        /// `(identity, name, enabled)`
+        AccessCounterSet(T::AccountId, Vec<u8>, bool),
+        /// `(identity, handle)`
+        HandleClaimed(T::AccountId, Vec<u8>),
+        /// `(identity, handle)`
+        HandleReleased(T::AccountId, Vec<u8>),
+        /// `(issuer, subject, id)`
+        CredentialIssued(T::AccountId, T::AccountId, [u8; 32]),
+        /// `(revoker, subject, id)`
+        CredentialRevoked(T::AccountId, T::AccountId, [u8; 32]),
+        /// `(identity, issuer, id)` of a third-party claim awaiting the consent of the identity.
+        AttestationPending(T::AccountId, T::AccountId, [u8; 32]),
+        /// `(identity, id)`
+        AttestationAccepted(T::AccountId, [u8; 32]),
+        /// `(identity, id)`
+        AttestationRejected(T::AccountId, [u8; 32]),
+        /// `(identity, schemes)`, all schemes are accepted if empty.
+        SignaturePolicySet(T::AccountId, Vec<KeyType>),
+        /// `(account, keyless)`
+        KeylessAccountSet(T::AccountId, bool),
+        /// `(identity, name, cosigner)`, no co-signature is required if none.
+        AttributeCosignerSet(T::AccountId, Vec<u8>, Option<T::AccountId>),
+        /// `(identity, block)`
+        IdentityDormant(T::AccountId, T::BlockNumber),
+        /// `(identity, owner, attributes, delegates)`
+        IdentityImported(T::AccountId, T::AccountId, u32, u32),
+        /// `(identity, name, parent_identity, parent_name)`, no parent if removed.
+        AttributeDependencySet(T::AccountId, Vec<u8>, Option<(T::AccountId, Vec<u8>)>),
+        /// `(identity, name, dependent, allowed)`
+        DependentAllowed(T::AccountId, Vec<u8>, T::AccountId, bool),
+        /// `(identity, name, dependents)` after the attribute was revoked, deleted or expired.
+        DependentAttributesInvalidated(T::AccountId, Vec<u8>, Vec<(T::AccountId, Vec<u8>)>),
+        /// `(watcher, identity)`
+        IdentityWatched(T::AccountId, T::AccountId),
+        /// `(watcher, identity)`
+        IdentityUnwatched(T::AccountId, T::AccountId),
+        /// `(watcher, identity, item, expiry)`
+        WatchedIdentityAlert(T::AccountId, T::AccountId, WatchedItem<T::AccountId>, T::BlockNumber),
+        /// `(identity, watcher)` removed by the owner of the identity.
+        WatcherRemoved(T::AccountId, T::AccountId),
+        /// `(identity, item, expiry)` of an alert not queued, `MaxExpiryAlerts` being queued
+        /// for its block already.
+        ExpiryAlertDropped(T::AccountId, WatchedItem<T::AccountId>, T::BlockNumber),
+        /// `(identity, reaper, bounty)`
+        IdentityReaped(T::AccountId, T::AccountId, BalanceOf<T>),
+    }
+
+    /// Claimed handles, with the identity they refer to, their depositor and deposit.
+    #[pallet::storage]
+    #[pallet::getter(fn handle_record)]
+    pub type Handles<T: Config> =
+    StorageMap<
+        _,
+        Blake2_128Concat,
+        BoundedVec<u8, T::MaxHandleLength>,
+        (T::AccountId, T::AccountId, BalanceOf<T>),
+        OptionQuery,
+    >;
+
+    /// Handle of each identity, at most one.
+    #[pallet::storage]
+    #[pallet::getter(fn handle_of)]
+    pub type IdentityHandles<T: Config> =
+    StorageMap<_, Blake2_128Concat, T::AccountId, BoundedVec<u8, T::MaxHandleLength>, OptionQuery>;
+
+    /// Credentials issued on chain, by subject and id.
+    #[pallet::storage]
+    #[pallet::getter(fn credential_of)]
+    pub type Credentials<T: Config> =
+    StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        [u8; 32],
+        Credential<T::AccountId, T::BlockNumber, T::Moment>,
+        OptionQuery,
+    >;
+
+    /// Issuer and amount of the deposits of the credentials, by subject and id.
+    #[pallet::storage]
+    #[pallet::getter(fn credential_deposit)]
+    pub type CredentialDeposits<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, [u8; 32], (T::AccountId, BalanceOf<T>), OptionQuery>;
+
+    /// Number of credentials of a subject.
+    #[pallet::storage]
+    #[pallet::getter(fn credential_count)]
+    pub type CredentialCount<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+    /// Third-party claims awaiting the consent of their subject, by subject and id.
+    #[pallet::storage]
+    #[pallet::getter(fn pending_attestation)]
+    pub type PendingAttestations<T: Config> =
+    StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        [u8; 32],
+        PendingAttestation<T::AccountId, T::BlockNumber, T::Moment>,
+        OptionQuery,
+    >;
+
+    /// Depositor and amount of the deposits of the pending third-party claims, by subject and id.
+    #[pallet::storage]
+    #[pallet::getter(fn attestation_deposit)]
+    pub type AttestationDeposits<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, [u8; 32], (T::AccountId, BalanceOf<T>), OptionQuery>;
+
+    /// Number of third-party claims awaiting the consent of an identity.
+    #[pallet::storage]
+    #[pallet::getter(fn pending_attestation_count)]
+    pub type PendingAttestationCount<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+    /// Rolling hash of the updates of an identity, changed by every mutation of its DID.
+    #[pallet::storage]
+    #[pallet::getter(fn document_hash)]
+    pub type DocumentHashes<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, [u8; 32], ValueQuery>;
+
+    /// Document hashes anchored by identities, with the block and time of anchoring. Keyed by
+    /// identity too, so anchoring a hash first does not prevent other identities from anchoring it.
+    #[pallet::storage]
+    #[pallet::getter(fn proof_of_existence)]
+    pub type Anchors<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        [u8; 32],
+        (T::BlockNumber, T::Moment),
+        OptionQuery,
+    >;
+
+    /// Last block a delegate attested its liveness at, or was added at.
+    #[pallet::storage]
+    #[pallet::getter(fn last_seen)]
+    pub type LastSeen<T: Config> =
+    StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>, T::AccountId), T::BlockNumber, OptionQuery>;
+
+    /// Challenge nonces of verifiers, with the last block they can be consumed at.
+    #[pallet::storage]
+    #[pallet::getter(fn presentation_nonce)]
+    pub type PresentationNonces<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, [u8; 32], T::BlockNumber, OptionQuery>;
+
+    /// Presentation nonces by the last block they can be consumed at, removed in the next block.
+    #[pallet::storage]
+    #[pallet::getter(fn presentation_nonce_expiries)]
+    pub type PresentationNonceExpiries<T: Config> =
+    StorageMap<_, Blake2_128Concat, T::BlockNumber, BoundedVec<(T::AccountId, [u8; 32]), T::MaxNonceExpiries>, ValueQuery>;
+
+    /// Number of random challenges requested, making each challenge unique.
+    #[pallet::storage]
+    pub type ChallengeCount<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+    /// Price to read an encrypted attribute of an identity.
+    #[pallet::storage]
+    #[pallet::getter(fn access_price)]
+    pub type AccessPrices<T: Config> =
+    StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), BalanceOf<T>, OptionQuery>;
+
+    /// Fees held in escrow for the readers of an attribute, with the block they can be refunded at.
+    #[pallet::storage]
+    #[pallet::getter(fn access_purchase)]
+    pub type AccessPurchases<T: Config> =
+    StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>, T::AccountId), (BalanceOf<T>, T::BlockNumber), OptionQuery>;
+
+    /// Provenance of the attributes not written on this chain.
+    #[pallet::storage]
+    #[pallet::getter(fn provenance_of)]
+    pub type ProvenanceOf<T: Config> =
+    StorageMap<_, Blake2_128Concat, (T::AccountId, [u8; 32]), Provenance, OptionQuery>;
+
+    /// Number of guardians needed to authorize the recovery of an identity accounts.
+    #[pallet::storage]
+    #[pallet::getter(fn recovery_threshold)]
+    pub type RecoveryThreshold<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u32, OptionQuery>;
+
+    /// Guardians who vouched for a rescuer of an identity.
+    #[pallet::storage]
+    #[pallet::getter(fn recovery_vouches)]
+    pub type RecoveryVouches<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        T::AccountId,
+        BoundedVec<T::AccountId, T::MaxRecoveryVouches>,
+        ValueQuery,
+    >;
+
+    /// Number of rescuers with vouches of an identity, at most `MaxRescuers`.
+    #[pallet::storage]
+    #[pallet::getter(fn rescuer_count)]
+    pub type RescuerCount<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+    /// Human-readable profiles of identities.
+    #[pallet::storage]
+    #[pallet::getter(fn profile_of)]
+    pub type Profiles<T: Config> =
+    StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Profile<T::AccountId, BalanceOf<T>, T::MaxDisplayNameLength>,
+        OptionQuery,
+    >;
+
+    /// Account which wrote an attribute, disputed if suspended.
+    #[pallet::storage]
+    #[pallet::getter(fn attribute_issuer)]
+    pub type AttributeIssuer<T: Config> =
+    StorageMap<_, Blake2_128Concat, (T::AccountId, [u8; 32]), T::AccountId, OptionQuery>;
+
+    /// Creation and update blocks and times of attributes.
+    #[pallet::storage]
+    #[pallet::getter(fn metadata_of)]
+    pub type AttributeMetadataOf<T: Config> =
+    StorageMap<
+        _,
+        Blake2_128Concat,
+        (T::AccountId, [u8; 32]),
+        AttributeMetadata<T::BlockNumber, T::Moment>,
+        OptionQuery,
+    >;
+
+    /// Issuers suspended for misbehavior, with the hash of the evidence.
+    #[pallet::storage]
+    #[pallet::getter(fn suspension_of)]
+    pub type SuspendedIssuers<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, [u8; 32], OptionQuery>;
+
+    /// Claimed attribute namespaces, e.g. `kyc/`, with their owner identity, depositor and deposit.
+    /// Only the owner and its signing delegates write attributes under a claimed namespace:
+    /// their own, and those of the identities opted in.
+    #[pallet::storage]
+    #[pallet::getter(fn namespace_of)]
+    pub type Namespaces<T: Config> =
+    StorageMap<_, Blake2_128Concat, Vec<u8>, (T::AccountId, T::AccountId, BalanceOf<T>), OptionQuery>;
+
+    /// Namespaces identities opted in, with the namespace owner they trust. The opt-in lapses
+    /// once the namespace is transferred or released.
+    #[pallet::storage]
+    #[pallet::getter(fn namespace_opt_in)]
+    pub type NamespaceOptIns<T: Config> =
+    StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, Vec<u8>, T::AccountId, OptionQuery>;
+
+    /// Reads logged by verifiers for the attributes opted in counting.
+    #[pallet::storage]
+    #[pallet::getter(fn access_counter_of)]
+    pub type AccessCounters<T: Config> = StorageMap<_, Blake2_128Concat, (T::AccountId, [u8; 32]), u64, OptionQuery>;
+
+    /// Identities which made their feeless first write, or were reaped, so they are not
+    /// brand-new again.
+    #[pallet::storage]
+    pub type FeelessWriters<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+    /// Block of the last feeless first write, and the number of feeless writes in that block.
+    #[pallet::storage]
+    #[pallet::getter(fn feeless_writes)]
+    pub type FeelessWrites<T: Config> = StorageValue<_, (T::BlockNumber, u32), ValueQuery>;
+
+    /// Cursor of the ongoing migration, empty before its first step.
+    #[pallet::storage]
+    #[pallet::getter(fn migration_cursor)]
+    pub type MigrationCursor<T: Config> = StorageValue<_, Vec<u8>, OptionQuery>;
+
+    /// Attribute names and prefixes banned by the `AdminOrigin`, e.g. after a legal takedown.
+    #[pallet::storage]
+    #[pallet::getter(fn banned_names)]
+    pub type BannedNames<T: Config> = StorageValue<_, Vec<Vec<u8>>, ValueQuery>;
+
+    /// When `true`, all DID mutations are rejected.
+    #[pallet::storage]
+    #[pallet::getter(fn paused)]
+    pub type Paused<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+    #[pallet::genesis_config]
+    pub struct GenesisConfig<T: Config> {
+        /// `(identity, owner)`
+        pub owners: Vec<(T::AccountId, T::AccountId)>,
+        /// `(identity, delegate_type, delegate, key_type, validity)`
+        pub delegates: Vec<(T::AccountId, Vec<u8>, T::AccountId, KeyType, T::BlockNumber)>,
+        /// `(identity, name, value, kind, validity, nonce)`
+        pub attributes: Vec<(T::AccountId, Vec<u8>, Vec<u8>, AttributeKind, T::BlockNumber, u64)>,
+        /// `(identity, name, nonce)`
+        pub nonces: Vec<(T::AccountId, Vec<u8>, u64)>,
+        /// `(identity, meta_nonce)`
+        pub meta_nonces: Vec<(T::AccountId, u64)>,
+        /// `(identity, name, nonce, issuer)` of the attributes attested by a third party
+        pub issuers: Vec<(T::AccountId, Vec<u8>, u64, T::AccountId)>,
+        /// `(issuer, evidence_hash)` of the issuers suspended for misbehavior
+        pub suspended_issuers: Vec<(T::AccountId, [u8; 32])>,
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: Config> Default for GenesisConfig<T> {
+        fn default() -> Self {
+            Self {
+                owners: Vec::new(),
+                delegates: Vec::new(),
+                attributes: Vec::new(),
+                nonces: Vec::new(),
+                meta_nonces: Vec::new(),
+                issuers: Vec::new(),
+                suspended_issuers: Vec::new(),
+            }
+        }
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+        fn build(&self) {
+            for (identity, owner) in &self.owners {
+                Pallet::<T>::set_owner(identity, owner);
+            }
+            for (identity, delegate_type, delegate, key_type, validity) in &self.delegates {
+                <DelegateOf<T>>::insert(
+                    identity,
+                    (delegate_type, delegate),
+                    Delegate { validity: *validity, key_type: *key_type },
+                );
+                <DelegatesOfType<T>>::insert((identity, delegate_type), delegate, ());
+                let key = Pallet::<T>::delegate_key(delegate_type, delegate);
+                let bytes = Pallet::<T>::delegate_bytes(delegate_type, delegate);
+                <StorageCharges<T>>::insert(identity, key, bytes);
+                <StorageUsage<T>>::mutate(identity, |usage| *usage = usage.saturating_add(bytes));
+            }
+            for (identity, name, nonce) in &self.nonces {
+                <AttributeNonce<T>>::insert(identity, name, nonce);
+            }
+            for (identity, name, value, kind, validity, nonce) in &self.attributes {
+                let id = Pallet::<T>::attribute_id(identity, name, *nonce);
+                let attribute = Attribute {
+                    name: name.clone(),
+                    value: value.clone(),
+                    validity: *validity,
+                    creation: Default::default(),
+                    nonce: *nonce,
+                    kind: *kind,
+                };
+                if <AttributeNonce<T>>::get(identity, name).saturating_sub(1) == *nonce {
+                    let digest = (Pallet::<T>::key_hash(value), *validity);
+                    <AttributeDigests<T>>::insert(identity, Pallet::<T>::key_hash(name), digest);
+                }
+                <AttributeOf<T>>::insert((identity, id), attribute);
+                let bytes = Pallet::<T>::attribute_bytes(name, value);
+                <StorageCharges<T>>::insert(identity, id, bytes);
+                <StorageUsage<T>>::mutate(identity, |usage| *usage = usage.saturating_add(bytes));
+            }
+            for (identity, meta_nonce) in &self.meta_nonces {
+                <MetaNonce<T>>::insert(identity, meta_nonce);
+            }
+            for (identity, name, nonce, issuer) in &self.issuers {
+                let id = Pallet::<T>::attribute_id(identity, name, *nonce);
+                <AttributeIssuer<T>>::insert((identity, id), issuer);
+            }
+            for (issuer, evidence_hash) in &self.suspended_issuers {
+                <SuspendedIssuers<T>>::insert(issuer, evidence_hash);
+            }
+            // Genesis identities are active from the genesis block, so they can become dormant.
+            let owned = self.owners.iter().map(|(identity, _)| identity);
+            let delegating = self.delegates.iter().map(|(identity, ..)| identity);
+            let attributed = self.attributes.iter().map(|(identity, ..)| identity);
+            for identity in owned.chain(delegating).chain(attributed) {
+                <LastHeartbeat<T>>::insert(identity, T::BlockNumber::zero());
+            }
+        }
     }
 
     #[pallet::error]
 	pub enum Error<T> {
+        /// The origin does not own the identity.
         NotOwner,
+        /// The delegate is unknown, expired or of another type.
         InvalidDelegate,
+        /// The signature does not match the signer.
         BadSignature,
+        /// The attribute exists, or its name is too long.
         AttributeCreationFailed,
+        /// The attribute to revoke is unknown.
         AttributeResetFailed,
+        /// The attribute to delete is unknown, or its name is too long.
         AttributeRemovalFailed,
+        /// The attribute is unknown, expired or holds another value.
         InvalidAttribute,
+        /// A nonce overflowed.
         Overflow,
+        /// The off-chain transaction is malformed.
         BadTransaction,
+        /// The off-chain DID can not be parsed.
+        InvalidExternalDid,
+        /// The identity is already registered.
+        DidAlreadyExists,
+        /// The recipient is not a `X25519` key agreement delegate.
+        InvalidKeyAgreementKey,
+        /// The validity exceeds `MaxAttributeValidity`.
+        AttributeValidityTooLong,
+        /// Names and delegate types can not be empty.
+        EmptyName,
+        /// Values can not be empty.
+        EmptyValue,
+        /// Names can not start with the reserved `did:` prefix.
+        ReservedName,
+        /// Validity periods can not be zero.
+        ZeroValidity,
+        /// The validity period overflows the block number.
+        ValidityOverflow,
+        /// The hash is already anchored.
+        HashAlreadyAnchored,
+        /// More than `MaxDelegateRenewals` delegates are renewed.
+        TooManyRenewals,
+        /// The identity must be registered with `ExplicitOwnershipOnly`.
+        IdentityNotRegistered,
+        /// DID mutations are halted.
+        PalletPaused,
+        /// The name is in one of the `ReservedNamespaces`.
+        ReservedNamespace,
+        /// The nonce is registered and not expired.
+        PresentationNonceExists,
+        /// The nonce is not registered.
+        UnknownPresentationNonce,
+        /// The nonce expired.
+        PresentationNonceExpired,
+        /// The attribute has no access price.
+        NotForSale,
+        /// The reader already holds a purchase in escrow.
+        AccessAlreadyPurchased,
+        /// No purchase of the reader is held in escrow.
+        UnknownAccessPurchase,
+        /// The fee can not be refunded before `AccessEscrowPeriod` blocks.
+        EscrowNotExpired,
+        /// No state root is known for the foreign block.
+        UnknownStateRoot,
+        /// The storage proof does not hold the attribute.
+        InvalidStateProof,
+        /// The foreign attribute expired.
+        ForeignAttributeExpired,
+        /// Recovery thresholds can not be zero.
+        ZeroThreshold,
+        /// The identity has no recovery threshold.
+        RecoveryNotConfigured,
+        /// The guardian already vouched for the rescuer.
+        AlreadyVouched,
+        /// The display name exceeds `MaxDisplayNameLength`.
+        DisplayNameTooLong,
+        /// The identity has no profile.
+        NoProfile,
+        /// The issuer is already suspended.
+        IssuerAlreadySuspended,
+        /// Namespaces are a single segment ending with `/`.
+        InvalidNamespace,
+        /// The namespace is already claimed.
+        NamespaceTaken,
+        /// The namespace is not claimed.
+        UnknownNamespace,
+        /// The name is in a namespace the origin does not own.
+        NotNamespaceOwner,
+        /// The attribute was updated more than `max_age` blocks, or time, ago.
+        AttributeTooOld,
+        /// The capability does not match the current delegations.
+        InvalidCapability,
+        /// DID mutations are rejected until the storage migration completes.
+        MigrationOngoing,
+        /// The attribute is not opted in read counting.
+        AccessNotCounted,
+        /// Handles are lowercase ASCII letters, digits, `-` and `_`.
+        InvalidHandle,
+        /// The handle exceeds `MaxHandleLength`.
+        HandleTooLong,
+        /// The handle is already claimed.
+        HandleTaken,
+        /// The identity already has a handle.
+        HandleAlreadySet,
+        /// The identity has no handle.
+        NoHandle,
+        /// The issuer is suspended for misbehavior.
+        IssuerSuspended,
+        /// The same claim of the schema was already issued to the subject.
+        CredentialExists,
+        /// The same claim is already awaiting the consent of the identity.
+        AttestationAlreadyPending,
+        /// No such claim awaits the consent of the identity.
+        UnknownAttestation,
+        /// The identity has more delegates, of the type if any, than `delegate_count`.
+        DelegateCountTooLow,
+        /// New writes of the attribute name are banned.
+        BannedName,
+        /// The name or prefix is already banned.
+        AlreadyBanned,
+        /// The name or prefix is not banned.
+        NotBanned,
+        /// The chain has no dormancy policy.
+        DormancyDisabled,
+        /// The identity was active during the `DormancyPeriod`.
+        IdentityActive,
+        /// The identity is already flagged dormant.
+        AlreadyDormant,
+        /// The identity is not flagged dormant.
+        NotDormant,
+        /// The identity can not be reaped before the end of the `ReapGracePeriod`.
+        GracePeriodOngoing,
+        /// The identity has more attribute names than `attribute_count`.
+        AttributeCountTooLow,
+        /// The contract delegate does not authorize the caller.
+        ContractNotAuthorized,
+        /// The signature policy of the identity does not accept the signature scheme.
+        SchemeNotAllowed,
+        /// The origin already watches the identity.
+        AlreadyWatching,
+        /// The origin does not watch the identity.
+        NotWatching,
+        /// The identity has `MaxWatchers` watchers.
+        TooManyWatchers,
+        /// The parent attribute is invalid, itself depends on another attribute, or the
+        /// dependent attribute has dependents.
+        InvalidDependency,
+        /// The parent attribute has `MaxDependents` dependents.
+        TooManyDependents,
+        /// Writes of the attribute must be co-signed, with `add_cosigned_attribute`.
+        CosignatureRequired,
+        /// The attribute does not require a co-signature.
+        NotCosigned,
+        /// The write would store more than `MaxBytesPerDid` bytes for the identity.
+        StorageQuotaExceeded,
+        /// Only the account itself can configure its recovery.
+        NotRecoveredAccount,
+        /// The rescuer has `MaxRecoveryVouches` vouches, or the threshold exceeds it.
+        TooManyVouches,
+        /// The identity has `MaxPendingAttestations` claims awaiting its consent.
+        TooManyPendingAttestations,
+        /// The subject has `MaxCredentialsPerSubject` credentials.
+        TooManyCredentials,
+        /// No credential of the subject has this id.
+        UnknownCredential,
+        /// The state root of the foreign block is older than `MaxForeignStateRootAge`.
+        StaleStateRoot,
+        /// The access price is above the `max_price` of the reader.
+        AccessPriceAboveLimit,
+        /// The attestor of the parent attribute did not allow the identity to depend on it.
+        DependentNotAllowed,
+        /// More than `MaxNonceExpiries` presentation nonces would expire at the block.
+        TooManyPresentationNonces,
+        /// The identity has vouches for `MaxRescuers` rescuers.
+        TooManyRescuers,
+    }
+
+    #[pallet::extra_constants]
+    impl<T: Config> Pallet<T> {
+        /// `(code, name)` stable codes of the pallet errors, see `error_codes`.
+        #[pallet::constant_name(ErrorCodes)]
+        fn error_codes() -> Vec<(u16, Vec<u8>)> {
+            crate::error_codes::ERROR_CODES.iter().map(|(code, name)| (*code, name.as_bytes().to_vec())).collect()
+        }
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let db = T::DbWeight::get();
+            // The storage version is put once the migration completes, so its steps know the
+            // versions to migrate from. An upgrade during the migration does not restart it.
+            if Self::on_chain_storage_version() >= STORAGE_VERSION || <MigrationCursor<T>>::exists() {
+                return db.reads(2);
+            }
+            <MigrationCursor<T>>::put(Vec::<u8>::new());
+            Self::deposit_event(Event::MigrationStarted);
+            db.reads_writes(2, 1)
+        }
+
+        fn on_initialize(n: T::BlockNumber) -> Weight {
+            Self::migration_step()
+                .saturating_add(Self::alert_expiries(n))
+                .saturating_add(Self::invalidate_expired_dependencies(n))
+                .saturating_add(Self::remove_expired_nonces(n))
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn try_state(_n: T::BlockNumber) -> Result<(), &'static str> {
+            Self::do_try_state()
+        }
     }
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {
-            /// Transfers ownership of an identity.
+            /// Transfers ownership of an identity. It removes the recovery threshold of the
+            /// identity, which its account sets again to trust the guardians of the new owner.
             #[pallet::call_index(0)]
-            #[pallet::weight(0)]
+            #[pallet::weight(Pallet::<T>::clear_rescuers_weight())]
             pub fn change_owner(
                 origin: OriginFor<T>,
                 identity: T::AccountId,
                 new_owner: T::AccountId,
             ) -> DispatchResult {
-                let who = ensure_signed(origin)?;
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
                 Self::is_owner(&identity, &who)?;
     
                 let now_timestamp = T::Timestamp::now();
                 let now_block_number = <frame_system::Pallet<T>>::block_number();
     
-                if <OwnerOf<T>>::contains_key(&identity) {
-                    // Update to new owner.
-                    <OwnerOf<T>>::mutate(&identity, |o| *o = Some(new_owner.clone()));
-
-                } else {
-                    // Add to new owner.
-                    <OwnerOf<T>>::insert(&identity, &new_owner);
+                // Save the update time and block, as an update of its owner.
+                Self::note_update(&identity, &who, now_block_number, now_timestamp);
+                Self::set_owner(&identity, &new_owner);
+                // The account trusted the guardians of the previous owner only.
+                if <RecoveryThreshold<T>>::take(&identity).is_some() {
+                    Self::clear_rescuers(&identity);
                 }
-                // Save the update time and block.
-                <UpdatedBy<T>>::insert(
-                    &identity, (&who, &now_block_number, &now_timestamp),
-                );
                 Self::deposit_event(Event::OwnerChanged(
                     identity,
                     who,
@@ -220,16 +1273,19 @@ pub mod pallet {
                 identity: T::AccountId,
                 delegate: T::AccountId,
                 delegate_type: Vec<u8>,
-                valid_for: Option<T::BlockNumber>,
+                key_type: KeyType,
+                valid_for: Option<Validity<T::BlockNumber>>,
             ) -> DispatchResult {
-                let who = ensure_signed(origin)?;
-                ensure!(delegate_type.len() <= 64, Error::<T>::InvalidDelegate);
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                ensure!(delegate_type.len() <= MAX_NAME_LENGTH, Error::<T>::InvalidDelegate);
+                Self::validate_input(&delegate_type, None, valid_for)?;
     
-                Self::create_delegate( &who, &identity, &delegate, &delegate_type, valid_for)?;
+                Self::create_delegate(&who, &identity, &delegate, &delegate_type, key_type, valid_for)?;
     
                 let now_timestamp = T::Timestamp::now();
                 let now_block_number = <frame_system::Pallet<T>>::block_number();
-                <UpdatedBy<T>>::insert(&identity, (who, now_block_number, now_timestamp));
+                Self::note_update(&identity, &who, now_block_number, now_timestamp);
     
                 Self::deposit_event(Event::DelegateAdded(
                     identity,
@@ -242,30 +1298,88 @@ pub mod pallet {
     
             /// Revokes an identity's delegate by setting its expiration to the current block number.
             #[pallet::call_index(2)]
-            #[pallet::weight(0)]
+            #[pallet::weight((0, DispatchClass::Operational))]
             pub fn revoke_delegate(
                 origin: OriginFor<T>,
                 identity: T::AccountId,
                 delegate_type: Vec<u8>,
                 delegate: T::AccountId,
             ) -> DispatchResult {
-                let who = ensure_signed(origin)?;
+                Self::ensure_revocable()?;
+                let who = Self::ensure_controller(origin)?;
                 Self::is_owner(&identity, &who)?;
                 Self::valid_listed_delegate(&identity, &delegate_type, &delegate)?;
-                ensure!(delegate_type.len() <= 64, Error::<T>::InvalidDelegate);
+                ensure!(delegate_type.len() <= MAX_NAME_LENGTH, Error::<T>::InvalidDelegate);
+                Self::validate_input(&delegate_type, None, None)?;
     
                 let now_timestamp = T::Timestamp::now();
                 let now_block_number = <frame_system::Pallet<T>>::block_number();
     
                 // Update only the validity period to revoke the delegate.
-                <DelegateOf<T>>::mutate(
-                    (&identity, &delegate_type, &delegate), |b| *b = Some(Some(now_block_number)),
-                );
-                <UpdatedBy<T>>::insert(&identity, (who, now_block_number, now_timestamp));
+                <DelegateOf<T>>::mutate(&identity, (&delegate_type, &delegate), |d| {
+                    if let Some(d) = d {
+                        d.validity = now_block_number;
+                    }
+                });
+                <DelegatesOfType<T>>::remove((&identity, &delegate_type), &delegate);
+                <LastSeen<T>>::remove((&identity, &delegate_type, &delegate));
+                Self::note_update(&identity, &who, now_block_number, now_timestamp);
                 Self::deposit_event(Event::DelegateRevoked(identity, delegate_type, delegate));
                 Ok(())
             }
     
+            /// Revokes all the valid delegates of a type of an identity, e.g. in an emergency, and
+            /// prunes the expired ones from `DelegatesOfType`. `delegate_count` is an upper bound
+            /// of the delegates of the type added since its last revocation and not revoked one by
+            /// one, expired ones included, which the weight is proportional to. During a migration,
+            /// the delegates may not be indexed by type yet: it bounds all the delegates of the
+            /// identity instead.
+            #[pallet::call_index(41)]
+            #[pallet::weight((
+                T::DbWeight::get().reads_writes(*delegate_count as u64 + 1, 3 * *delegate_count as u64 + 1),
+                DispatchClass::Operational,
+            ))]
+            pub fn revoke_delegates_of_type(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                delegate_type: Vec<u8>,
+                delegate_count: u32,
+            ) -> DispatchResult {
+                Self::ensure_revocable()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+                ensure!(delegate_type.len() <= MAX_NAME_LENGTH, Error::<T>::InvalidDelegate);
+                Self::validate_input(&delegate_type, None, None)?;
+
+                let delegates: Vec<(Vec<u8>, T::AccountId)> = if <MigrationCursor<T>>::exists() {
+                    <DelegateOf<T>>::iter_key_prefix(&identity).take(delegate_count as usize + 1).collect()
+                } else {
+                    <DelegatesOfType<T>>::iter_key_prefix((&identity, &delegate_type))
+                        .take(delegate_count as usize + 1)
+                        .map(|delegate| (delegate_type.clone(), delegate))
+                        .collect()
+                };
+                ensure!(delegates.len() <= delegate_count as usize, Error::<T>::DelegateCountTooLow);
+
+                let now_timestamp = T::Timestamp::now();
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                let mut revoked = 0u32;
+                for (_, delegate) in delegates.into_iter().filter(|(of_type, _)| *of_type == delegate_type) {
+                    <DelegateOf<T>>::mutate(&identity, (&delegate_type, &delegate), |d| match d {
+                        Some(d) if d.validity > now_block_number => {
+                            d.validity = now_block_number;
+                            revoked += 1;
+                        }
+                        _ => (),
+                    });
+                    <DelegatesOfType<T>>::remove((&identity, &delegate_type), &delegate);
+                    <LastSeen<T>>::remove((&identity, &delegate_type, &delegate));
+                }
+                Self::note_update(&identity, &who, now_block_number, now_timestamp);
+                Self::deposit_event(Event::DelegatesOfTypeRevoked(identity, delegate_type, revoked));
+                Ok(())
+            }
+
             /// Creates a new attribute as part of an identity.
             /// Sets its expiration period.
             #[pallet::call_index(3)]
@@ -275,12 +1389,14 @@ pub mod pallet {
                 identity: T::AccountId,
                 name: Vec<u8>,
                 value: Vec<u8>,
-                valid_for: Option<T::BlockNumber>,
+                valid_for: Option<Validity<T::BlockNumber>>,
             ) -> DispatchResult {
-                let who = ensure_signed(origin)?;
-                ensure!(name.len() <= 64, Error::<T>::AttributeCreationFailed);
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
     
-                Self::create_attribute(&who, &identity, &name, &value, valid_for)?;
+                if !Self::checked_insert_attribute(&who, &identity, &name, &value, valid_for, false)? {
+                    return Ok(());
+                }
                 Self::deposit_event(Event::AttributeAdded(identity, name, valid_for));
                 Ok(())
             }
@@ -288,12 +1404,12 @@ pub mod pallet {
             /// Revokes an attribute/property from an identity.
             /// Sets its expiration period to the actual block number.
             #[pallet::call_index(4)]
-            #[pallet::weight(0)]
+            #[pallet::weight((0, DispatchClass::Operational))]
             pub fn revoke_attribute(origin: OriginFor<T>, identity: T::AccountId, name: Vec<u8>) -> DispatchResult {
-                let who = ensure_signed(origin)?;
-                ensure!(name.len() <= 64, Error::<T>::AttributeRemovalFailed);
+                Self::ensure_revocable()?;
+                let who = Self::ensure_controller(origin)?;
     
-                Self::reset_attribute(who, &identity, &name)?;
+                Self::checked_revoke_attribute(&who, &identity, &name, false)?;
                 Self::deposit_event(Event::AttributeRevoked(
                     identity,
                     name,
@@ -306,22 +1422,33 @@ pub mod pallet {
             #[pallet::call_index(5)]
             #[pallet::weight(0)]
             pub fn delete_attribute(origin: OriginFor<T>, identity: T::AccountId, name: Vec<u8>) -> DispatchResult {
-                let who = ensure_signed(origin)?;
-                Self::is_owner(&identity, &who)?;
-                ensure!(name.len() <= 64, Error::<T>::AttributeRemovalFailed);
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::ensure_revoker(&who, &identity, &name)?;
+                ensure!(name.len() <= MAX_NAME_LENGTH, Error::<T>::AttributeRemovalFailed);
+                Self::validate_input(&name, None, None)?;
+                Self::ensure_unreserved_namespace(&name)?;
     
                 let now_block_number = <frame_system::Pallet<T>>::block_number();
                 let result = Self::attribute_and_id(&identity, &name);
     
                 match result {
-                    Some((_, id)) => <AttributeOf<T>>::remove((&identity, &id)),
+                    Some((_, id)) => {
+                        <AttributeOf<T>>::remove((&identity, &id));
+                        Self::release_storage(&identity, &id);
+                        <AttributeDigests<T>>::remove(&identity, Self::key_hash(&name));
+                        <AttributeIssuer<T>>::remove((&identity, &id));
+                        <AttributeMetadataOf<T>>::remove((&identity, &id));
+                        <AccessCounters<T>>::remove((&identity, &id));
+                    }
                     None => return Err(Error::<T>::AttributeRemovalFailed.into()),
                 }
+                // A later attribute of the same name is not for sale at the former price.
+                <AccessPrices<T>>::remove((&identity, &name));
+                Self::forget_dependency(&identity, &name);
+                Self::invalidate_dependents(&identity, &name);
     
-                <UpdatedBy<T>>::insert(
-                    &identity,
-                    (&who, &now_block_number, T::Timestamp::now()),
-                );
+                Self::note_update(&identity, &who, now_block_number, T::Timestamp::now());
     
                 Self::deposit_event(Event::AttributeDeleted(identity, name, now_block_number));
                 Ok(())
@@ -334,270 +1461,3116 @@ pub mod pallet {
                 origin: OriginFor<T>,
                 transaction: AttributeTransaction<T::Signature, T::AccountId>,
             ) -> DispatchResult {
-                let who = ensure_signed(origin)?;
-    
-                let mut encoded = transaction.name.encode();
-                encoded.extend(transaction.value.encode());
-                encoded.extend(transaction.validity.encode());
-                encoded.extend(transaction.identity.encode());
-    
-                // Execute the storage update if the signer is valid.
-                Self::signed_attribute(who, &encoded, &transaction)?;
-                Self::deposit_event(Event::AttributeTransactionExecuted(transaction));
+                Self::ensure_not_paused()?;
+                // Anyone can relay a transaction, authorization comes from its signer.
+                Self::ensure_controller(origin)?;
+
+                if !T::EmitMetaTransactionRejections::get() {
+                    return Self::execute_transaction(transaction);
+                }
+                // Report the rejection in an event, which would be discarded with an error.
+                let identity = transaction.identity.clone();
+                if let Err(reason) = with_storage_layer(|| Self::execute_transaction(transaction)) {
+                    Self::deposit_event(Event::MetaTransactionRejected(identity, reason));
+                }
                 Ok(())
             }
-        }
 
-        impl<T: Config>
-        Did<T::AccountId, T::BlockNumber, T::Moment, T::Signature>
-        for Pallet<T>
-    {
-        /// Validates if the AccountId 'actual_owner' owns the identity.
-        fn is_owner(identity: &T::AccountId, actual_owner: &T::AccountId) -> DispatchResult {
-            let owner = Self::identity_owner(identity);
-            match owner == *actual_owner {
-                true => Ok(()),
-                false => Err(Error::<T>::NotOwner.into()),
+            /// Registers the DID of an organization, owned by the account of its `ControllerOrigin`,
+            /// e.g. a `pallet-collective` board managing it through motions.
+            #[pallet::call_index(7)]
+            #[pallet::weight(0)]
+            pub fn create_org_did(origin: OriginFor<T>) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let org = T::ControllerOrigin::ensure_origin(origin)?;
+                ensure!(!<OwnerOf<T>>::contains_key(&org), Error::<T>::DidAlreadyExists);
+
+                let now_timestamp = T::Timestamp::now();
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                Self::set_owner(&org, &org);
+                Self::note_update(&org, &org, now_block_number, now_timestamp);
+
+                Self::deposit_event(Event::OrgDidCreated(org));
+                Ok(())
             }
-        }
-    
-        /// Get the identity owner if set.
-        /// If never changed, returns the identity as its owner.
-        fn identity_owner(identity: &T::AccountId) -> T::AccountId {
-            match Self::owner_of(identity) {
-                Some(id) => id,
-                None => identity.clone(),
+
+            /// Creates a new attribute holding a value encrypted for one of the identity
+            /// key agreement keys, i.e. an `X25519` delegate of type `KEY_AGREEMENT_DELEGATE_TYPE`.
+            #[pallet::call_index(8)]
+            #[pallet::weight(0)]
+            pub fn add_encrypted_attribute(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                name: Vec<u8>,
+                recipient: T::AccountId,
+                ciphertext: Vec<u8>,
+                valid_for: Option<Validity<T::BlockNumber>>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                ensure!(name.len() <= MAX_NAME_LENGTH, Error::<T>::AttributeCreationFailed);
+                Self::validate_input(&name, Some(&ciphertext), valid_for)?;
+                Self::ensure_unreserved_namespace(&name)?;
+                Self::ensure_attestor(&who, &identity, &name)?;
+
+                Self::valid_listed_delegate(&identity, KEY_AGREEMENT_DELEGATE_TYPE, &recipient)
+                    .map_err(|_| Error::<T>::InvalidKeyAgreementKey)?;
+                let key = Self::delegate_of(&identity, (KEY_AGREEMENT_DELEGATE_TYPE, &recipient))
+                    .ok_or(Error::<T>::InvalidKeyAgreementKey)?;
+                ensure!(key.key_type == KeyType::X25519, Error::<T>::InvalidKeyAgreementKey);
+
+                let envelope = EncryptionEnvelope { recipient, ciphertext };
+                if Self::requires_consent(&who, &identity) {
+                    let attestation = PendingAttestation::Attribute {
+                        issuer: who,
+                        name,
+                        value: envelope.encode(),
+                        kind: AttributeKind::Encrypted,
+                        valid_for,
+                    };
+                    return Self::queue_attestation(&identity, attestation);
+                }
+                Self::insert_attribute(
+                    &who,
+                    &identity,
+                    &name,
+                    &envelope.encode(),
+                    AttributeKind::Encrypted,
+                    valid_for,
+                    Self::is_own_write(&who, &identity),
+                )?;
+                Self::deposit_event(Event::AttributeAdded(identity, name, valid_for));
+                Ok(())
             }
-        }
-    
-        /// Validates if a delegate belongs to an identity and it has not expired.
-        fn valid_delegate(
-            identity: &T::AccountId,
-            delegate_type: &[u8],
-            delegate: &T::AccountId,
-        ) -> DispatchResult {
-            ensure!(delegate_type.len() <= 64, Error::<T>::InvalidDelegate);
-            ensure!(
-                Self::valid_listed_delegate(identity, delegate_type, delegate).is_ok()
-                    || Self::is_owner(identity, delegate).is_ok(),
-                Error::<T>::InvalidDelegate
-            );
-            Ok(())
-        }
-    
-        /// Validates that a delegate contains_key for specific purpose and remains valid at this block high.
-        fn valid_listed_delegate(
-            identity: &T::AccountId,
-            delegate_type: &[u8],
-            delegate: &T::AccountId,
-        ) -> DispatchResult {
-            ensure!(
-                <DelegateOf<T>>::contains_key((&identity, delegate_type, &delegate)),
-                Error::<T>::InvalidDelegate
-            );
-    
-            let validity = Self::delegate_of((identity, delegate_type, delegate));
-            match validity > Some(Some(<frame_system::Pallet<T>>::block_number())) {
-                true => Ok(()),
-                false => Err(Error::<T>::InvalidDelegate.into()),
+
+            /// Anchors a document hash to an identity, as a timestamped proof of existence.
+            /// Can be called by the owner or a signing delegate of the identity, once per hash.
+            #[pallet::call_index(9)]
+            #[pallet::weight(0)]
+            pub fn anchor_hash(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                hash: [u8; 32],
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::valid_delegate(&identity, SIGNING_DELEGATE_TYPE, &who)?;
+                ensure!(!<Anchors<T>>::contains_key(&identity, hash), Error::<T>::HashAlreadyAnchored);
+
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                <Anchors<T>>::insert(&identity, hash, (now_block_number, T::Timestamp::now()));
+                Self::deposit_event(Event::HashAnchored(identity, hash, now_block_number));
+                Ok(())
             }
-        }
+
+            /// Extends the validity of many valid delegates of an identity in a single call.
+            /// Each renewal is a `(delegate, delegate_type, extra_validity)` tuple.
+            #[pallet::call_index(10)]
+            #[pallet::weight(T::DbWeight::get().reads_writes(
+                renewals.len() as u64 + 1,
+                renewals.len() as u64 + 1,
+            ))]
+            pub fn renew_delegates(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                renewals: Vec<(T::AccountId, Vec<u8>, T::BlockNumber)>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+                ensure!(
+                    renewals.len() <= T::MaxDelegateRenewals::get() as usize,
+                    Error::<T>::TooManyRenewals
+                );
+
+                for (delegate, delegate_type, extra_validity) in renewals {
+                    ensure!(!extra_validity.is_zero(), Error::<T>::ZeroValidity);
+                    // Revoked and expired delegates can not be renewed.
+                    Self::valid_listed_delegate(&identity, &delegate_type, &delegate)?;
+
+                    let validity = <DelegateOf<T>>::try_mutate(
+                        &identity,
+                        (&delegate_type, &delegate),
+                        |record| -> Result<T::BlockNumber, DispatchError> {
+                            let record = record.as_mut().ok_or(Error::<T>::InvalidDelegate)?;
+                            // Delegates that never expire stay so.
+                            if record.validity != T::BlockNumber::max_value() {
+                                record.validity = record
+                                    .validity
+                                    .checked_add(&extra_validity)
+                                    .ok_or(Error::<T>::ValidityOverflow)?;
+                            }
+                            Ok(record.validity)
+                        },
+                    )?;
+                    let item = WatchedItem::Delegate { delegate_type: delegate_type.clone(), delegate: delegate.clone() };
+                    Self::schedule_expiry_alert(&identity, item, validity);
+                    Self::deposit_event(Event::DelegateRenewed(
+                        identity.clone(),
+                        delegate_type,
+                        delegate,
+                        validity,
+                    ));
+                }
+
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                Self::note_update(&identity, &who, now_block_number, T::Timestamp::now());
+                Ok(())
+            }
+
+            /// Creates a new attribute as part of the identity of the origin.
+            #[pallet::call_index(11)]
+            #[pallet::weight(0)]
+            pub fn add_own_attribute(
+                origin: OriginFor<T>,
+                name: Vec<u8>,
+                value: Vec<u8>,
+                valid_for: Option<Validity<T::BlockNumber>>,
+            ) -> DispatchResult {
+                let identity = Self::ensure_controller(origin.clone())?;
+                Self::add_attribute(origin, identity, name, value, valid_for)
+            }
+
+            /// Removes an attribute from the identity of the origin.
+            #[pallet::call_index(12)]
+            #[pallet::weight(0)]
+            pub fn delete_own_attribute(origin: OriginFor<T>, name: Vec<u8>) -> DispatchResult {
+                let identity = Self::ensure_controller(origin.clone())?;
+                Self::delete_attribute(origin, identity, name)
+            }
+
+            /// Creates a new delegate of the identity of the origin.
+            #[pallet::call_index(13)]
+            #[pallet::weight(0)]
+            pub fn add_own_delegate(
+                origin: OriginFor<T>,
+                delegate: T::AccountId,
+                delegate_type: Vec<u8>,
+                key_type: KeyType,
+                valid_for: Option<Validity<T::BlockNumber>>,
+            ) -> DispatchResult {
+                let identity = Self::ensure_controller(origin.clone())?;
+                Self::add_delegate(origin, identity, delegate, delegate_type, key_type, valid_for)
+            }
+
+            /// Registers the identity of the origin, owned by itself.
+            #[pallet::call_index(14)]
+            #[pallet::weight(0)]
+            pub fn register_did(origin: OriginFor<T>) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                ensure!(!Self::registered(&who), Error::<T>::DidAlreadyExists);
+
+                let now_timestamp = T::Timestamp::now();
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                Self::set_owner(&who, &who);
+                Self::note_update(&who, &who, now_block_number, now_timestamp);
+
+                Self::deposit_event(Event::DidRegistered(who));
+                Ok(())
+            }
+
+            /// Attests that the delegate key of the origin is still in use.
+            /// Heartbeats are optional, they help operators detect stale keys.
+            #[pallet::call_index(17)]
+            #[pallet::weight(0)]
+            pub fn heartbeat(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                delegate_type: Vec<u8>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let delegate = Self::ensure_controller(origin)?;
+                Self::validate_input(&delegate_type, None, None)?;
+                Self::valid_listed_delegate(&identity, &delegate_type, &delegate)?;
+
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                <LastSeen<T>>::insert((&identity, &delegate_type, &delegate), now_block_number);
+                <LastHeartbeat<T>>::insert(&identity, now_block_number);
+                <Dormant<T>>::remove(&identity);
+                Self::deposit_event(Event::DelegateHeartbeat(
+                    identity,
+                    delegate_type,
+                    delegate,
+                    now_block_number,
+                ));
+                Ok(())
+            }
+
+            /// Anchors a challenge nonce of a verifier, valid for `ttl` blocks.
+            /// Any service of the verifier holding a signing delegate can register it.
+            #[pallet::call_index(18)]
+            #[pallet::weight(0)]
+            pub fn register_presentation_nonce(
+                origin: OriginFor<T>,
+                verifier: T::AccountId,
+                nonce: [u8; 32],
+                ttl: T::BlockNumber,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::valid_delegate(&verifier, SIGNING_DELEGATE_TYPE, &who)?;
+                ensure!(!ttl.is_zero(), Error::<T>::ZeroValidity);
+                // Expired nonces can be registered again.
+                ensure!(
+                    !Self::valid_presentation_nonce(&verifier, &nonce),
+                    Error::<T>::PresentationNonceExists
+                );
+
+                let expiry = Self::expiry_block(Validity::For(ttl))?;
+                Self::schedule_nonce_removal(&verifier, nonce, expiry)?;
+                <PresentationNonces<T>>::insert(&verifier, nonce, expiry);
+                Self::deposit_event(Event::PresentationNonceRegistered(verifier, nonce, expiry));
+                Ok(())
+            }
+
+            /// Registers a random challenge nonce of a verifier, bound to the verifier and valid for
+            /// `ChallengeTtl` blocks, so verifiers need not bring their own randomness.
+            /// Any service of the verifier holding a signing delegate can request it.
+            #[pallet::call_index(53)]
+            #[pallet::weight(0)]
+            pub fn request_challenge(origin: OriginFor<T>, identity: T::AccountId) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::valid_delegate(&identity, SIGNING_DELEGATE_TYPE, &who)?;
+
+                let count = <ChallengeCount<T>>::get();
+                let subject = (b"did/challenge", &identity, count).encode();
+                let (random, _) = T::Randomness::random(&subject);
+                let challenge = blake2_256(&(random, &identity, count).encode());
+                ensure!(
+                    !Self::valid_presentation_nonce(&identity, &challenge),
+                    Error::<T>::PresentationNonceExists
+                );
+
+                let expiry = Self::expiry_block(Validity::For(T::ChallengeTtl::get()))?;
+                Self::schedule_nonce_removal(&identity, challenge, expiry)?;
+                <ChallengeCount<T>>::put(count.wrapping_add(1));
+                <PresentationNonces<T>>::insert(&identity, challenge, expiry);
+                Self::deposit_event(Event::ChallengeIssued(identity, challenge, expiry));
+                Ok(())
+            }
+
+            /// Consumes a challenge nonce of a verifier, so a presentation answering it
+            /// can not be replayed to any service of the verifier.
+            #[pallet::call_index(19)]
+            #[pallet::weight(0)]
+            pub fn consume_presentation_nonce(
+                origin: OriginFor<T>,
+                verifier: T::AccountId,
+                nonce: [u8; 32],
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::valid_delegate(&verifier, SIGNING_DELEGATE_TYPE, &who)?;
+
+                let expiry = Self::presentation_nonce(&verifier, nonce)
+                    .ok_or(Error::<T>::UnknownPresentationNonce)?;
+                ensure!(
+                    expiry >= <frame_system::Pallet<T>>::block_number(),
+                    Error::<T>::PresentationNonceExpired
+                );
+                <PresentationNonces<T>>::remove(&verifier, nonce);
+                Self::deposit_event(Event::PresentationNonceConsumed(verifier, nonce));
+                Ok(())
+            }
+
+            /// Sets, or removes with `None`, the price to read an encrypted attribute.
+            #[pallet::call_index(20)]
+            #[pallet::weight(0)]
+            pub fn set_access_price(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                name: Vec<u8>,
+                price: Option<BalanceOf<T>>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+                Self::validate_input(&name, None, None)?;
+                ensure!(
+                    Self::encrypted_attribute(&identity, &name).is_some(),
+                    Error::<T>::InvalidAttribute
+                );
+
+                <AccessPrices<T>>::set((&identity, &name), price);
+                Self::deposit_event(Event::AccessPriceSet(identity, name, price));
+                Ok(())
+            }
+
+            /// Pays for reading an encrypted attribute. The fee is held in escrow until the
+            /// identity confirms sharing the view key off chain, or refunded after `AccessEscrowPeriod`.
+            ///
+            /// Fails with `AccessPriceAboveLimit` if the owner raised the price above `max_price`,
+            /// e.g. in the same block.
+            #[pallet::call_index(21)]
+            #[pallet::weight(0)]
+            pub fn purchase_access(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                name: Vec<u8>,
+                max_price: BalanceOf<T>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let reader = Self::ensure_controller(origin)?;
+                let price = Self::access_price((&identity, &name)).ok_or(Error::<T>::NotForSale)?;
+                ensure!(price <= max_price, Error::<T>::AccessPriceAboveLimit);
+                ensure!(
+                    Self::encrypted_attribute(&identity, &name).is_some(),
+                    Error::<T>::InvalidAttribute
+                );
+                ensure!(
+                    !<AccessPurchases<T>>::contains_key((&identity, &name, &reader)),
+                    Error::<T>::AccessAlreadyPurchased
+                );
+
+                T::Currency::reserve(&reader, price)?;
+                let refundable_at = Self::expiry_block(T::AccessEscrowPeriod::get())?;
+                <AccessPurchases<T>>::insert((&identity, &name, &reader), (price, refundable_at));
+                Self::deposit_event(Event::AccessPurchased(identity, name, reader, price));
+                Ok(())
+            }
+
+            /// Confirms the view key was shared with a reader, releasing its fee to the identity.
+            ///
+            /// The chain can not check the key was shared: readers trust the owner to deliver it,
+            /// as they trust it for the content of the attribute. Only the fee still reserved
+            /// from the reader is released, as paid in the `AccessGranted` event.
+            #[pallet::call_index(22)]
+            #[pallet::weight(0)]
+            pub fn confirm_access(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                name: Vec<u8>,
+                reader: T::AccountId,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+
+                let (price, _) = <AccessPurchases<T>>::take((&identity, &name, &reader))
+                    .ok_or(Error::<T>::UnknownAccessPurchase)?;
+                // The reserve may have been slashed meanwhile: only what is left is paid.
+                let unpaid = T::Currency::repatriate_reserved(&reader, &identity, price, BalanceStatus::Free)?;
+                let paid = price.saturating_sub(unpaid);
+                Self::deposit_event(Event::AccessGranted(identity, name, reader, paid));
+                Ok(())
+            }
+
+            /// Refunds a reader whose access was not confirmed within `AccessEscrowPeriod`.
+            /// Callable while the pallet is paused, so fees are never locked in escrow.
+            #[pallet::call_index(23)]
+            #[pallet::weight(0)]
+            pub fn refund_access(origin: OriginFor<T>, identity: T::AccountId, name: Vec<u8>) -> DispatchResult {
+                let reader = Self::ensure_controller(origin)?;
+
+                let (price, refundable_at) = Self::access_purchase((&identity, &name, &reader))
+                    .ok_or(Error::<T>::UnknownAccessPurchase)?;
+                ensure!(
+                    <frame_system::Pallet<T>>::block_number() >= refundable_at,
+                    Error::<T>::EscrowNotExpired
+                );
+                <AccessPurchases<T>>::remove((&identity, &name, &reader));
+                T::Currency::unreserve(&reader, price);
+                Self::deposit_event(Event::AccessRefunded(identity, name, reader, price));
+                Ok(())
+            }
+
+            /// Imports an attribute valid on a foreign chain at `relay_block`, given a storage proof
+            /// of its `AttributeOf` and `AttributeIssuer` entries and of the `System::Number` there.
+            /// Any signed account, or the `ControllerOrigin`, can relay a proof.
+            ///
+            /// The attribute keeps its foreign issuer and expires locally after the blocks it had
+            /// left on the foreign chain, capped by `MaxAttributeValidity`. State roots older than
+            /// `MaxForeignStateRootAge` are rejected, as attributes since revoked may be proven.
+            #[pallet::call_index(24)]
+            #[pallet::weight(0)]
+            pub fn import_foreign_attestation(
+                origin: OriginFor<T>,
+                relay_block: u32,
+                identity: T::AccountId,
+                name: Vec<u8>,
+                nonce: u64,
+                proof: Vec<Vec<u8>>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::validate_input(&name, None, None)?;
+
+                let state_root =
+                    T::ForeignStateRoots::state_root(relay_block).ok_or(Error::<T>::UnknownStateRoot)?;
+                // Without a latest block, the age of the state root can not be bounded.
+                let latest = T::ForeignStateRoots::latest_block().ok_or(Error::<T>::StaleStateRoot)?;
+                ensure!(
+                    latest.saturating_sub(relay_block) <= T::MaxForeignStateRootAge::get(),
+                    Error::<T>::StaleStateRoot
+                );
+                // Attribute identifier on the foreign chain, which shares the `KeyHasher`.
+                let foreign_id = Self::attribute_id(&identity, &name, nonce);
+                let prefix = T::ForeignAttributePrefix::get();
+                let attribute_key = foreign::attribute_storage_key(&prefix, &identity, &foreign_id);
+                let issuer_key = foreign::issuer_storage_key(&prefix, &identity, &foreign_id);
+                let number_key = foreign::block_number_storage_key();
+                let values = foreign::read_proof(&state_root, proof, &[&attribute_key, &issuer_key, &number_key])
+                    .ok_or(Error::<T>::InvalidStateProof)?;
+                let attribute = values[0]
+                    .as_deref()
+                    .and_then(|value| Attribute::<T::BlockNumber, T::Moment>::decode(&mut &value[..]).ok())
+                    .ok_or(Error::<T>::InvalidStateProof)?;
+                let issuer = values[1]
+                    .as_deref()
+                    .map(|value| T::AccountId::decode(&mut &value[..]))
+                    .transpose()
+                    .map_err(|_| Error::<T>::InvalidStateProof)?;
+                let foreign_block = values[2]
+                    .as_deref()
+                    .and_then(|value| T::BlockNumber::decode(&mut &value[..]).ok())
+                    .ok_or(Error::<T>::InvalidStateProof)?;
+                ensure!(attribute.name == name, Error::<T>::InvalidStateProof);
+                ensure!(attribute.validity > foreign_block, Error::<T>::ForeignAttributeExpired);
+
+                // The foreign expiry, in blocks left, assuming both chains have the same block time.
+                let max_validity = T::MaxAttributeValidity::get();
+                let never = T::BlockNumber::max_value();
+                let valid_for = if attribute.validity == never && max_validity == never {
+                    Validity::Never
+                } else {
+                    Validity::For((attribute.validity - foreign_block).min(max_validity))
+                };
+                Self::insert_attribute(&who, &identity, &name, &attribute.value, attribute.kind, Some(valid_for), false)?;
+                let (_, id) =
+                    Self::attribute_and_id(&identity, &name).ok_or(Error::<T>::AttributeCreationFailed)?;
+                // The relayer only carried the attestation: the issuer is the foreign one.
+                match issuer {
+                    Some(issuer) => <AttributeIssuer<T>>::insert((&identity, &id), issuer),
+                    None => <AttributeIssuer<T>>::remove((&identity, &id)),
+                }
+                <ProvenanceOf<T>>::insert((&identity, id), Provenance::Foreign { relay_block, state_root });
+                Self::deposit_event(Event::ForeignAttestationImported(identity, name, relay_block));
+                Ok(())
+            }
+
+            /// Sets, or removes with `None`, the number of `RecoveryGuardian` delegates
+            /// needed to authorize the recovery of the identity accounts.
+            ///
+            /// Only the identity account itself can call it, whoever owns its DID: the owner
+            /// manages the guardians, but can not make itself an authority over the account.
+            /// Removing the threshold clears the vouches.
+            #[pallet::call_index(25)]
+            #[pallet::weight(Pallet::<T>::clear_rescuers_weight())]
+            pub fn set_recovery_threshold(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                threshold: Option<u32>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = ensure_signed(origin)?;
+                ensure!(who == identity, Error::<T>::NotRecoveredAccount);
+                ensure!(threshold != Some(0), Error::<T>::ZeroThreshold);
+                ensure!(
+                    threshold.map_or(true, |threshold| threshold <= T::MaxRecoveryVouches::get()),
+                    Error::<T>::TooManyVouches
+                );
+
+                if threshold.is_none() {
+                    Self::clear_rescuers(&identity);
+                }
+                <RecoveryThreshold<T>>::set(&identity, threshold);
+                Self::deposit_event(Event::RecoveryThresholdSet(identity, threshold));
+                Ok(())
+            }
+
+            /// Vouches, as a guardian of the identity, for a rescuer of its accounts.
+            #[pallet::call_index(26)]
+            #[pallet::weight(0)]
+            pub fn vouch_recovery(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                rescuer: T::AccountId,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let guardian = Self::ensure_controller(origin)?;
+                ensure!(
+                    <RecoveryThreshold<T>>::contains_key(&identity),
+                    Error::<T>::RecoveryNotConfigured
+                );
+                Self::valid_listed_delegate(&identity, RECOVERY_GUARDIAN_DELEGATE_TYPE, &guardian)?;
+                let new_rescuer = !<RecoveryVouches<T>>::contains_key(&identity, &rescuer);
+                ensure!(
+                    !new_rescuer || Self::rescuer_count(&identity) < T::MaxRescuers::get(),
+                    Error::<T>::TooManyRescuers
+                );
+
+                <RecoveryVouches<T>>::try_mutate(&identity, &rescuer, |vouches| -> DispatchResult {
+                    ensure!(!vouches.contains(&guardian), Error::<T>::AlreadyVouched);
+                    // Vouches of revoked guardians free their slot.
+                    vouches.retain(|guardian| {
+                        Self::valid_listed_delegate(&identity, RECOVERY_GUARDIAN_DELEGATE_TYPE, guardian).is_ok()
+                    });
+                    vouches.try_push(guardian.clone()).map_err(|_| Error::<T>::TooManyVouches)?;
+                    Ok(())
+                })?;
+                if new_rescuer {
+                    <RescuerCount<T>>::mutate(&identity, |count| *count = count.saturating_add(1));
+                }
+                Self::deposit_event(Event::RecoveryVouched(identity, rescuer, guardian));
+                Ok(())
+            }
+
+            /// Clears the vouches for a rescuer, e.g. after a guardian key was compromised.
+            #[pallet::call_index(27)]
+            #[pallet::weight(0)]
+            pub fn clear_recovery_vouches(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                rescuer: T::AccountId,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+
+                Self::remove_rescuer(&identity, &rescuer);
+                Self::deposit_event(Event::RecoveryVouchesCleared(identity, rescuer));
+                Ok(())
+            }
+
+            /// Sets the profile of an identity, reserving `ProfileDeposit` from the origin.
+            #[pallet::call_index(28)]
+            #[pallet::weight(0)]
+            pub fn set_profile(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                display_name: Vec<u8>,
+                avatar_hash: Option<[u8; 32]>,
+                bio_hash: Option<[u8; 32]>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+                Self::validate_input(&display_name, None, None)?;
+                let display_name: BoundedVec<u8, T::MaxDisplayNameLength> =
+                    display_name.try_into().map_err(|_| Error::<T>::DisplayNameTooLong)?;
+
+                // The deposit moves to the new depositor, e.g. after an owner change.
+                let deposit = match Self::profile_of(&identity) {
+                    Some(profile) if profile.depositor == who => profile.deposit,
+                    previous => {
+                        let deposit = T::ProfileDeposit::get();
+                        T::Currency::reserve(&who, deposit)?;
+                        if let Some(profile) = previous {
+                            T::Currency::unreserve(&profile.depositor, profile.deposit);
+                        }
+                        deposit
+                    }
+                };
+                <Profiles<T>>::insert(
+                    &identity,
+                    Profile { display_name, avatar_hash, bio_hash, depositor: who, deposit },
+                );
+                Self::deposit_event(Event::ProfileSet(identity));
+                Ok(())
+            }
+
+            /// Removes the profile of an identity, unreserving its deposit.
+            #[pallet::call_index(29)]
+            #[pallet::weight(0)]
+            pub fn clear_profile(origin: OriginFor<T>, identity: T::AccountId) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+
+                let profile = <Profiles<T>>::take(&identity).ok_or(Error::<T>::NoProfile)?;
+                T::Currency::unreserve(&profile.depositor, profile.deposit);
+                Self::deposit_event(Event::ProfileCleared(identity));
+                Ok(())
+            }
+
+            /// Claims an attribute namespace for an identity, reserving `NamespaceDeposit` from the origin.
+            /// A namespace is a single segment ending with `/`, e.g. `kyc/`.
+            #[pallet::call_index(31)]
+            #[pallet::weight(0)]
+            pub fn claim_namespace(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                prefix: Vec<u8>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+                let single_segment =
+                    prefix.last() == Some(&b'/') && prefix.iter().filter(|b| **b == b'/').count() == 1;
+                ensure!(prefix.len() > 1 && prefix.len() <= MAX_NAME_LENGTH && single_segment, Error::<T>::InvalidNamespace);
+                Self::validate_input(&prefix, None, None)?;
+                Self::ensure_unreserved_namespace(&prefix)?;
+                ensure!(!<Namespaces<T>>::contains_key(&prefix), Error::<T>::NamespaceTaken);
+
+                let deposit = T::NamespaceDeposit::get();
+                T::Currency::reserve(&who, deposit)?;
+                <Namespaces<T>>::insert(&prefix, (&identity, &who, deposit));
+                Self::deposit_event(Event::NamespaceClaimed(prefix, identity));
+                Ok(())
+            }
+
+            /// Transfers a namespace to another identity. The deposit stays reserved from its depositor.
+            #[pallet::call_index(32)]
+            #[pallet::weight(0)]
+            pub fn transfer_namespace(
+                origin: OriginFor<T>,
+                prefix: Vec<u8>,
+                new_owner: T::AccountId,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                let (owner, depositor, deposit) =
+                    Self::namespace_of(&prefix).ok_or(Error::<T>::UnknownNamespace)?;
+                Self::is_owner(&owner, &who)?;
+
+                <Namespaces<T>>::insert(&prefix, (&new_owner, depositor, deposit));
+                Self::deposit_event(Event::NamespaceTransferred(prefix, owner, new_owner));
+                Ok(())
+            }
+
+            /// Releases a namespace, unreserving its deposit.
+            #[pallet::call_index(33)]
+            #[pallet::weight(0)]
+            pub fn release_namespace(origin: OriginFor<T>, prefix: Vec<u8>) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                let (owner, depositor, deposit) =
+                    Self::namespace_of(&prefix).ok_or(Error::<T>::UnknownNamespace)?;
+                Self::is_owner(&owner, &who)?;
+
+                <Namespaces<T>>::remove(&prefix);
+                T::Currency::unreserve(&depositor, deposit);
+                Self::deposit_event(Event::NamespaceReleased(prefix));
+                Ok(())
+            }
+
+            /// Opts an identity in or out of a claimed namespace. While opted in, the owner of the
+            /// namespace and its signing delegates add attributes of the identity under the
+            /// namespace; the owner of the identity can still revoke and delete them.
+            #[pallet::call_index(55)]
+            #[pallet::weight(0)]
+            pub fn set_namespace_opt_in(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                prefix: Vec<u8>,
+                opted_in: bool,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+
+                if opted_in {
+                    let (owner, _, _) = Self::namespace_of(&prefix).ok_or(Error::<T>::UnknownNamespace)?;
+                    <NamespaceOptIns<T>>::insert(&identity, &prefix, owner);
+                } else {
+                    <NamespaceOptIns<T>>::remove(&identity, &prefix);
+                }
+                Self::deposit_event(Event::NamespaceOptInSet(identity, prefix, opted_in));
+                Ok(())
+            }
+
+            /// Opts an attribute in or out of read counting, so its issuer can measure its use.
+            #[pallet::call_index(34)]
+            #[pallet::weight(0)]
+            pub fn set_access_counter(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                name: Vec<u8>,
+                enabled: bool,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::ensure_attestor(&who, &identity, &name)?;
+                let (_, id) =
+                    Self::attribute_and_id(&identity, &name).ok_or(Error::<T>::InvalidAttribute)?;
+
+                // Enabling again keeps the current count.
+                if enabled && !<AccessCounters<T>>::contains_key((&identity, id)) {
+                    <AccessCounters<T>>::insert((&identity, id), 0);
+                } else if !enabled {
+                    <AccessCounters<T>>::remove((&identity, id));
+                }
+                Self::deposit_event(Event::AccessCounterSet(identity, name, enabled));
+                Ok(())
+            }
+
+            /// Logs a read of a counted attribute, e.g. by a verifier integration.
+            /// Lightweight: no event is emitted.
+            #[pallet::call_index(35)]
+            #[pallet::weight(0)]
+            pub fn log_access(origin: OriginFor<T>, identity: T::AccountId, name: Vec<u8>) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                Self::ensure_controller(origin)?;
+                let (_, id) =
+                    Self::attribute_and_id(&identity, &name).ok_or(Error::<T>::InvalidAttribute)?;
+
+                <AccessCounters<T>>::try_mutate((&identity, id), |count| -> DispatchResult {
+                    let count = count.as_mut().ok_or(Error::<T>::AccessNotCounted)?;
+                    *count = count.saturating_add(1);
+                    Ok(())
+                })
+            }
+
+            /// Claims a unique handle for an identity, reserving `HandleDeposit` from the origin.
+            #[pallet::call_index(36)]
+            #[pallet::weight(0)]
+            pub fn claim_handle(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                handle: Vec<u8>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+                // Lowercase only, so that handles differing by case can not be confused.
+                let readable = handle
+                    .iter()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || *b == b'-' || *b == b'_');
+                ensure!(!handle.is_empty() && readable, Error::<T>::InvalidHandle);
+                let bounded: BoundedVec<u8, T::MaxHandleLength> =
+                    handle.clone().try_into().map_err(|_| Error::<T>::HandleTooLong)?;
+                ensure!(!<Handles<T>>::contains_key(&bounded), Error::<T>::HandleTaken);
+                ensure!(!<IdentityHandles<T>>::contains_key(&identity), Error::<T>::HandleAlreadySet);
+
+                let deposit = T::HandleDeposit::get();
+                T::Currency::reserve(&who, deposit)?;
+                <Handles<T>>::insert(&bounded, (&identity, &who, deposit));
+                <IdentityHandles<T>>::insert(&identity, bounded);
+                Self::deposit_event(Event::HandleClaimed(identity, handle));
+                Ok(())
+            }
+
+            /// Releases the handle of an identity, unreserving its deposit.
+            /// The handle can then be claimed again, by any identity.
+            #[pallet::call_index(37)]
+            #[pallet::weight(0)]
+            pub fn unclaim_handle(origin: OriginFor<T>, identity: T::AccountId) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+
+                let handle = <IdentityHandles<T>>::take(&identity).ok_or(Error::<T>::NoHandle)?;
+                if let Some((_, depositor, deposit)) = <Handles<T>>::take(&handle) {
+                    T::Currency::unreserve(&depositor, deposit);
+                }
+                Self::deposit_event(Event::HandleReleased(identity, handle.into_inner()));
+                Ok(())
+            }
+
+            /// Issues a credential to a subject, from the registered identity of the origin, reserving
+            /// `CredentialDeposit` from it. Only the hash of the claim is stored, the claim itself
+            /// is shared off chain.
+            #[pallet::call_index(38)]
+            #[pallet::weight(0)]
+            pub fn issue_credential(
+                origin: OriginFor<T>,
+                subject: T::AccountId,
+                schema_id: [u8; 32],
+                claim_hash: [u8; 32],
+                expiry: Validity<T::BlockNumber>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let issuer = Self::ensure_controller(origin)?;
+                ensure!(Self::registered(&issuer), Error::<T>::IdentityNotRegistered);
+                ensure!(Self::suspension_of(&issuer).is_none(), Error::<T>::IssuerSuspended);
+                ensure!(expiry != Validity::For(Zero::zero()), Error::<T>::ZeroValidity);
+
+                let id = (&issuer, &subject, schema_id, claim_hash).using_encoded(blake2_256);
+                ensure!(!<Credentials<T>>::contains_key(&subject, id), Error::<T>::CredentialExists);
+                let credential = Credential {
+                    issuer: issuer.clone(),
+                    schema_id,
+                    claim_hash,
+                    issued_at: (<frame_system::Pallet<T>>::block_number(), T::Timestamp::now()),
+                    expiry: Self::expiry_block(expiry)?,
+                };
+                if Self::requires_consent(&issuer, &subject) {
+                    return Self::queue_attestation(&subject, PendingAttestation::Credential(credential));
+                }
+                Self::store_credential(&subject, id, credential)?;
+                Self::deposit_event(Event::CredentialIssued(issuer, subject, id));
+                Ok(())
+            }
+
+            /// Revokes a credential, unreserving the deposit of its issuer. Callable by its issuer
+            /// and by the owner of its subject.
+            #[pallet::call_index(56)]
+            #[pallet::weight((0, DispatchClass::Operational))]
+            pub fn revoke_credential(origin: OriginFor<T>, subject: T::AccountId, id: [u8; 32]) -> DispatchResult {
+                Self::ensure_revocable()?;
+                let who = Self::ensure_controller(origin)?;
+                let credential = Self::credential_of(&subject, id).ok_or(Error::<T>::UnknownCredential)?;
+                if credential.issuer != who {
+                    Self::is_owner(&subject, &who)?;
+                }
+
+                <Credentials<T>>::remove(&subject, id);
+                if let Some((depositor, deposit)) = <CredentialDeposits<T>>::take(&subject, id) {
+                    T::Currency::unreserve(&depositor, deposit);
+                }
+                <CredentialCount<T>>::mutate_exists(&subject, |count| {
+                    *count = count.and_then(|count| count.checked_sub(1)).filter(|count| *count > 0);
+                });
+                Self::deposit_event(Event::CredentialRevoked(who, subject, id));
+                Ok(())
+            }
+
+            /// Accepts a third-party claim awaiting the consent of an identity, making it part of
+            /// its DID. The issuer must still be allowed to attest it.
+            #[pallet::call_index(39)]
+            #[pallet::weight(0)]
+            pub fn accept_attestation(origin: OriginFor<T>, identity: T::AccountId, id: [u8; 32]) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+                let attestation =
+                    Self::pending_attestation(&identity, id).ok_or(Error::<T>::UnknownAttestation)?;
+
+                match attestation {
+                    // Attributes are valid from their acceptance.
+                    PendingAttestation::Attribute { issuer, name, value, kind, valid_for } => {
+                        Self::ensure_attestor(&issuer, &identity, &name)?;
+                        Self::insert_attribute(&issuer, &identity, &name, &value, kind, valid_for, false)?;
+                        Self::deposit_event(Event::AttributeAdded(identity.clone(), name, valid_for));
+                    }
+                    PendingAttestation::Credential(credential) => {
+                        let issuer = credential.issuer.clone();
+                        ensure!(Self::suspension_of(&issuer).is_none(), Error::<T>::IssuerSuspended);
+                        ensure!(!<Credentials<T>>::contains_key(&identity, id), Error::<T>::CredentialExists);
+                        Self::store_credential(&identity, id, credential)?;
+                        Self::deposit_event(Event::CredentialIssued(issuer, identity.clone(), id));
+                    }
+                }
+                Self::dequeue_attestation(&identity, id);
+                Self::deposit_event(Event::AttestationAccepted(identity, id));
+                Ok(())
+            }
+
+            /// Declines a third-party claim awaiting the consent of an identity.
+            #[pallet::call_index(40)]
+            #[pallet::weight(0)]
+            pub fn reject_attestation(origin: OriginFor<T>, identity: T::AccountId, id: [u8; 32]) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+                ensure!(<PendingAttestations<T>>::contains_key(&identity, id), Error::<T>::UnknownAttestation);
+
+                Self::dequeue_attestation(&identity, id);
+                Self::deposit_event(Event::AttestationRejected(identity, id));
+                Ok(())
+            }
+
+            /// Sets the signature schemes the identity accepts from its signers, e.g. to forbid
+            /// deprecated schemes the runtime still supports. An empty list accepts all schemes.
+            #[pallet::call_index(46)]
+            #[pallet::weight(0)]
+            pub fn set_signature_policy(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                mut schemes: Vec<KeyType>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+
+                schemes.sort();
+                schemes.dedup();
+                if schemes.is_empty() {
+                    <SignaturePolicies<T>>::remove(&identity);
+                } else {
+                    <SignaturePolicies<T>>::insert(&identity, &schemes);
+                }
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                Self::note_update(&identity, &who, now_block_number, T::Timestamp::now());
+                Self::deposit_event(Event::SignaturePolicySet(identity, schemes));
+                Ok(())
+            }
+
+            /// Subscribes the origin to the expiry alerts of an identity, emitted `ExpiryNotice`
+            /// blocks before its delegates and attributes added or renewed from now on expire.
+            /// Reserves the `WatcherDeposit` of the origin, so the `MaxWatchers` slots of an
+            /// identity can not be filled for free.
+            #[pallet::call_index(47)]
+            #[pallet::weight(0)]
+            pub fn watch_identity(origin: OriginFor<T>, identity: T::AccountId) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let watcher = Self::ensure_controller(origin)?;
+
+                <Watchers<T>>::try_mutate(&identity, |watchers| -> DispatchResult {
+                    ensure!(!watchers.contains(&watcher), Error::<T>::AlreadyWatching);
+                    watchers.try_push(watcher.clone()).map_err(|_| Error::<T>::TooManyWatchers.into())
+                })?;
+                let deposit = T::WatcherDeposit::get();
+                T::Currency::reserve(&watcher, deposit)?;
+                <WatcherDeposits<T>>::insert(&identity, &watcher, deposit);
+                Self::deposit_event(Event::IdentityWatched(watcher, identity));
+                Ok(())
+            }
+
+            /// Unsubscribes the origin from the expiry alerts of an identity.
+            #[pallet::call_index(48)]
+            #[pallet::weight(0)]
+            pub fn unwatch_identity(origin: OriginFor<T>, identity: T::AccountId) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let watcher = Self::ensure_controller(origin)?;
+
+                Self::remove_watcher_of(&identity, &watcher)?;
+                Self::deposit_event(Event::IdentityUnwatched(watcher, identity));
+                Ok(())
+            }
+
+            /// Removes a watcher of an identity, unreserving its deposit. Callable by the owner,
+            /// who decides who monitors the identity.
+            #[pallet::call_index(57)]
+            #[pallet::weight(0)]
+            pub fn remove_watcher(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                watcher: T::AccountId,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::is_owner(&identity, &who)?;
+
+                Self::remove_watcher_of(&identity, &watcher)?;
+                Self::deposit_event(Event::WatcherRemoved(identity, watcher));
+                Ok(())
+            }
+
+            /// Declares that an attribute depends on a `parent` attribute, e.g. `employment_role`
+            /// on `employer_attestation`, or removes its dependency. The attribute is invalid
+            /// while its parent is, and its revocation or expiry is notified.
+            ///
+            /// The `MaxDependents` of a parent are spent with the consent of its attestor only:
+            /// the origin attests the parent too, or its attestor allowed the identity with
+            /// `allow_dependent`.
+            #[pallet::call_index(50)]
+            #[pallet::weight(0)]
+            pub fn set_attribute_dependency(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                name: Vec<u8>,
+                parent: Option<(T::AccountId, Vec<u8>)>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::ensure_attestor(&who, &identity, &name)?;
+                ensure!(Self::attribute_and_id(&identity, &name).is_some(), Error::<T>::InvalidAttribute);
+
+                Self::forget_dependency(&identity, &name);
+                if let Some((parent_identity, parent_name)) = &parent {
+                    // A single level of dependencies, so queries stay bounded and acyclic.
+                    ensure!(
+                        (parent_identity != &identity || parent_name != &name)
+                            && Self::attribute_active(parent_identity, parent_name)
+                            && !<AttributeDependencies<T>>::contains_key(parent_identity, parent_name)
+                            && <Dependents<T>>::iter_prefix((&identity, &name)).next().is_none(),
+                        Error::<T>::InvalidDependency
+                    );
+                    let key = (parent_identity.clone(), parent_name.clone());
+                    ensure!(
+                        Self::ensure_attestor(&who, parent_identity, parent_name).is_ok()
+                            || <AllowedDependents<T>>::contains_key(&key, &identity),
+                        Error::<T>::DependentNotAllowed
+                    );
+                    let max_dependents = T::MaxDependents::get() as usize;
+                    let dependents = <Dependents<T>>::iter_prefix(&key).take(max_dependents).count();
+                    ensure!(dependents < max_dependents, Error::<T>::TooManyDependents);
+
+                    <AttributeDependencies<T>>::insert(&identity, &name, &key);
+                    <Dependents<T>>::insert(&key, (&identity, &name), ());
+                    if let Some((_, validity)) = Self::attribute_digest(parent_identity, Self::key_hash(parent_name)) {
+                        Self::schedule_dependency_expiry(parent_identity, parent_name, validity);
+                    }
+                }
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                Self::note_update(&identity, &who, now_block_number, T::Timestamp::now());
+                Self::deposit_event(Event::AttributeDependencySet(identity, name, parent));
+                Ok(())
+            }
+
+            /// Allows, or disallows, an identity to declare its attributes dependent on an attribute
+            /// attested by the origin. Disallowing keeps the dependencies already declared.
+            #[pallet::call_index(58)]
+            #[pallet::weight(0)]
+            pub fn allow_dependent(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                name: Vec<u8>,
+                dependent: T::AccountId,
+                allowed: bool,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                Self::ensure_attestor(&who, &identity, &name)?;
+                Self::validate_input(&name, None, None)?;
+
+                let key = (identity.clone(), name.clone());
+                if allowed {
+                    <AllowedDependents<T>>::insert(&key, &dependent, ());
+                } else {
+                    <AllowedDependents<T>>::remove(&key, &dependent);
+                }
+                Self::deposit_event(Event::DependentAllowed(identity, name, dependent, allowed));
+                Ok(())
+            }
+
+            /// Declares the origin account keyless, e.g. a multisig or pure proxy account, or not:
+            /// the signing delegates of a keyless account sign for the identities it owns.
+            #[pallet::call_index(54)]
+            #[pallet::weight(0)]
+            pub fn set_keyless(origin: OriginFor<T>, keyless: bool) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+
+                if keyless {
+                    <KeylessAccounts<T>>::insert(&who, ());
+                } else {
+                    <KeylessAccounts<T>>::remove(&who);
+                }
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                Self::note_update(&who, &who, now_block_number, T::Timestamp::now());
+                Self::deposit_event(Event::KeylessAccountSet(who, keyless));
+                Ok(())
+            }
+
+            /// Requires the writes of an attribute named `name` to be co-signed by a signer of the
+            /// `cosigner` identity, e.g. a compliance officer, or lifts the requirement if `None`.
+            /// Once set, the requirement is changed by the owner of the co-signer identity.
+            #[pallet::call_index(51)]
+            #[pallet::weight(0)]
+            pub fn set_attribute_cosigner(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                name: Vec<u8>,
+                cosigner: Option<T::AccountId>,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                match Self::attribute_cosigner(&identity, &name) {
+                    Some(current) => Self::is_owner(&current, &who)?,
+                    None => Self::is_owner(&identity, &who)?,
+                }
+                Self::validate_input(&name, None, None)?;
+
+                match &cosigner {
+                    Some(cosigner) => <AttributeCosigners<T>>::insert(&identity, &name, cosigner),
+                    None => <AttributeCosigners<T>>::remove(&identity, &name),
+                }
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                Self::note_update(&identity, &who, now_block_number, T::Timestamp::now());
+                Self::deposit_event(Event::AttributeCosignerSet(identity, name, cosigner));
+                Ok(())
+            }
+
+            /// Creates an attribute requiring a co-signature, as `add_attribute`, with the
+            /// `signature` of a `signer` of the co-signer identity over `cosignature_payload`.
+            /// Not queued for the consent of the owner, so only written by the owner itself.
+            #[pallet::call_index(52)]
+            #[pallet::weight(0)]
+            pub fn add_cosigned_attribute(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                name: Vec<u8>,
+                value: Vec<u8>,
+                valid_for: Option<Validity<T::BlockNumber>>,
+                signer: T::AccountId,
+                signature: T::Signature,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let who = Self::ensure_controller(origin)?;
+                ensure!(name.len() <= MAX_NAME_LENGTH, Error::<T>::AttributeCreationFailed);
+                Self::validate_input(&name, Some(&value), valid_for)?;
+                Self::ensure_unreserved_namespace(&name)?;
+                Self::ensure_attestor(&who, &identity, &name)?;
+                ensure!(!Self::requires_consent(&who, &identity), Error::<T>::NotOwner);
+
+                let cosigner = Self::attribute_cosigner(&identity, &name).ok_or(Error::<T>::NotCosigned)?;
+                let payload = Self::cosignature_payload(&identity, &name, &value, valid_for);
+                Self::valid_signer(&cosigner, &signature, &payload, &signer)?;
+
+                let charged = Self::is_own_write(&who, &identity);
+                Self::store_attribute(&who, &identity, &name, &value, AttributeKind::Plain, valid_for, charged)?;
+                Self::deposit_event(Event::AttributeAdded(identity, name, valid_for));
+                Ok(())
+            }
+
+            /// Flags an identity without activity for the `DormancyPeriod` as dormant.
+            /// Anyone can flag an identity, any activity of the identity clears the flag.
+            #[pallet::call_index(44)]
+            #[pallet::weight(0)]
+            pub fn flag_dormant(origin: OriginFor<T>, identity: T::AccountId) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                Self::ensure_controller(origin)?;
+                ensure!(T::DormancyPeriod::get().is_some(), Error::<T>::DormancyDisabled);
+
+                match Self::activity_status(&identity) {
+                    ActivityStatus::Active => return Err(Error::<T>::IdentityActive.into()),
+                    ActivityStatus::Dormant(_) => return Err(Error::<T>::AlreadyDormant.into()),
+                    ActivityStatus::Inactive => (),
+                }
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                <Dormant<T>>::insert(&identity, now_block_number);
+                Self::deposit_event(Event::IdentityDormant(identity, now_block_number));
+                Ok(())
+            }
+
+            /// Reaps an identity dormant for the `ReapGracePeriod`: removes its attributes,
+            /// delegates, credentials, pending claims, watchers, profile, handle, policy and
+            /// activity records, unreserving their deposits, and pays the `ReapBounty` to the
+            /// origin out of the profile and handle deposits.
+            ///
+            /// Kept are the ownership and nonces, so a reaped identity can not be taken over,
+            /// nor its ids and signatures replayed; the anchored hashes, which prove existence
+            /// at their block; the access purchases, refundable by their readers; and the
+            /// namespace opt-ins and dependent consents, which their owners revoke. Only the
+            /// current version of each attribute is stored, `delete_attribute` removes the others.
+            ///
+            /// `attribute_count` and `delegate_count` are upper bounds of the attribute names
+            /// and of the delegates of the identity, which the weight is proportional to.
+            #[pallet::call_index(45)]
+            #[pallet::weight(Pallet::<T>::reap_identity_weight(*attribute_count, *delegate_count))]
+            pub fn reap_identity(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                attribute_count: u32,
+                delegate_count: u32,
+            ) -> DispatchResult {
+                Self::ensure_not_paused()?;
+                let reaper = Self::ensure_controller(origin)?;
+                let since = Self::dormant_since(&identity).ok_or(Error::<T>::NotDormant)?;
+                let now_block_number = <frame_system::Pallet<T>>::block_number();
+                ensure!(
+                    now_block_number >= since.saturating_add(T::ReapGracePeriod::get()),
+                    Error::<T>::GracePeriodOngoing
+                );
+
+                let names: Vec<(Vec<u8>, u64)> = <AttributeNonce<T>>::iter_prefix(&identity)
+                    .take(attribute_count as usize + 1)
+                    .collect();
+                ensure!(names.len() <= attribute_count as usize, Error::<T>::AttributeCountTooLow);
+                let delegates: Vec<(Vec<u8>, T::AccountId)> = <DelegateOf<T>>::iter_key_prefix(&identity)
+                    .take(delegate_count as usize + 1)
+                    .collect();
+                ensure!(delegates.len() <= delegate_count as usize, Error::<T>::DelegateCountTooLow);
+                for (name, nonce) in names {
+                    let id = Self::attribute_id(&identity, &name, nonce.saturating_sub(1));
+                    <AttributeOf<T>>::remove((&identity, &id));
+                    <AttributeDigests<T>>::remove(&identity, Self::key_hash(&name));
+                    <AttributeIssuer<T>>::remove((&identity, &id));
+                    <AttributeMetadataOf<T>>::remove((&identity, &id));
+                    <AccessCounters<T>>::remove((&identity, &id));
+                    <ProvenanceOf<T>>::remove((&identity, &id));
+                    <AccessPrices<T>>::remove((&identity, &name));
+                    <AttributeCosigners<T>>::remove(&identity, &name);
+                    Self::forget_dependency(&identity, &name);
+                    Self::invalidate_dependents(&identity, &name);
+                }
+                for (delegate_type, delegate) in delegates {
+                    <DelegateOf<T>>::remove(&identity, (&delegate_type, &delegate));
+                    <DelegatesOfType<T>>::remove((&identity, &delegate_type), &delegate);
+                    <LastSeen<T>>::remove((&identity, delegate_type, delegate));
+                }
+                let _ = <StorageCharges<T>>::clear_prefix(&identity, attribute_count.saturating_add(delegate_count), None);
+                <StorageUsage<T>>::remove(&identity);
+
+                // The credentials, pending claims and watchers are bounded by the configuration.
+                for (_, (depositor, deposit)) in <CredentialDeposits<T>>::drain_prefix(&identity) {
+                    T::Currency::unreserve(&depositor, deposit);
+                }
+                let _ = <Credentials<T>>::clear_prefix(&identity, T::MaxCredentialsPerSubject::get(), None);
+                <CredentialCount<T>>::remove(&identity);
+                for (_, (depositor, deposit)) in <AttestationDeposits<T>>::drain_prefix(&identity) {
+                    T::Currency::unreserve(&depositor, deposit);
+                }
+                let _ = <PendingAttestations<T>>::clear_prefix(&identity, T::MaxPendingAttestations::get(), None);
+                <PendingAttestationCount<T>>::remove(&identity);
+                for watcher in <Watchers<T>>::take(&identity) {
+                    if let Some(deposit) = <WatcherDeposits<T>>::take(&identity, &watcher) {
+                        T::Currency::unreserve(&watcher, deposit);
+                    }
+                }
+
+                let profile = <Profiles<T>>::take(&identity).map(|profile| (profile.depositor, profile.deposit));
+                let handle = <IdentityHandles<T>>::take(&identity)
+                    .and_then(|handle| <Handles<T>>::take(&handle))
+                    .map(|(_, depositor, deposit)| (depositor, deposit));
+                let mut bounty: BalanceOf<T> = Zero::zero();
+                for (depositor, deposit) in profile.into_iter().chain(handle) {
+                    let share = deposit.min(T::ReapBounty::get().saturating_sub(bounty));
+                    let unpaid = T::Currency::repatriate_reserved(&depositor, &reaper, share, BalanceStatus::Free)?;
+                    T::Currency::unreserve(&depositor, deposit.saturating_sub(share));
+                    bounty = bounty.saturating_add(share.saturating_sub(unpaid));
+                }
+
+                <RecoveryThreshold<T>>::remove(&identity);
+                Self::clear_rescuers(&identity);
+                <UpdatedBy<T>>::remove(&identity);
+                <LastHeartbeat<T>>::remove(&identity);
+                <Dormant<T>>::remove(&identity);
+                <DocumentHashes<T>>::remove(&identity);
+                <SignaturePolicies<T>>::remove(&identity);
+                // Reaped identities do not get another feeless first write.
+                <FeelessWriters<T>>::insert(&identity, ());
+                Self::deposit_event(Event::IdentityReaped(identity, reaper, bounty));
+                Ok(())
+            }
+
+            /// Suspends a misbehaving issuer: the attributes it wrote are reported as disputed.
+            #[pallet::call_index(30)]
+            #[pallet::weight(0)]
+            pub fn report_issuer_misbehavior(
+                origin: OriginFor<T>,
+                issuer: T::AccountId,
+                evidence_hash: [u8; 32],
+            ) -> DispatchResult {
+                T::AdminOrigin::ensure_origin(origin)?;
+                ensure!(Self::suspension_of(&issuer).is_none(), Error::<T>::IssuerAlreadySuspended);
+
+                <SuspendedIssuers<T>>::insert(&issuer, evidence_hash);
+                Self::deposit_event(Event::IssuerSuspended(issuer, evidence_hash));
+                Ok(())
+            }
+
+            /// Bans new writes of the attribute names starting with `name_or_prefix`.
+            /// Existing attributes stay readable and can still be revoked or deleted.
+            #[pallet::call_index(42)]
+            #[pallet::weight((0, DispatchClass::Operational))]
+            pub fn ban_attribute_name(origin: OriginFor<T>, name_or_prefix: Vec<u8>) -> DispatchResult {
+                T::AdminOrigin::ensure_origin(origin)?;
+                ensure!(!name_or_prefix.is_empty(), Error::<T>::EmptyName);
+                <BannedNames<T>>::try_mutate(|banned| -> DispatchResult {
+                    ensure!(!banned.contains(&name_or_prefix), Error::<T>::AlreadyBanned);
+                    banned.push(name_or_prefix.clone());
+                    Ok(())
+                })?;
+                Self::deposit_event(Event::AttributeNameBanned(name_or_prefix));
+                Ok(())
+            }
+
+            /// Lifts a ban of `ban_attribute_name`.
+            #[pallet::call_index(43)]
+            #[pallet::weight((0, DispatchClass::Operational))]
+            pub fn unban_attribute_name(origin: OriginFor<T>, name_or_prefix: Vec<u8>) -> DispatchResult {
+                T::AdminOrigin::ensure_origin(origin)?;
+                <BannedNames<T>>::try_mutate(|banned| -> DispatchResult {
+                    let index =
+                        banned.iter().position(|b| *b == name_or_prefix).ok_or(Error::<T>::NotBanned)?;
+                    banned.swap_remove(index);
+                    Ok(())
+                })?;
+                Self::deposit_event(Event::AttributeNameUnbanned(name_or_prefix));
+                Ok(())
+            }
+
+            /// Imports an identity exported from another DID registry, e.g. a uPort or 3Box dump,
+            /// during the bootstrap of the chain. The identity is registered with its `owner`,
+            /// and its attributes are marked as `Imported`.
+            #[pallet::call_index(49)]
+            #[pallet::weight((
+                T::DbWeight::get().reads_writes(
+                    2 + 3 * attributes.len() as u64 + 2 * delegates.len() as u64,
+                    1 + 8 * attributes.len() as u64 + 3 * delegates.len() as u64,
+                ),
+                DispatchClass::Operational,
+            ))]
+            pub fn import_identity(
+                origin: OriginFor<T>,
+                identity: T::AccountId,
+                owner: T::AccountId,
+                attributes: Vec<(Vec<u8>, Vec<u8>, Option<Validity<T::BlockNumber>>)>,
+                delegates: Vec<(T::AccountId, Vec<u8>, KeyType, Option<Validity<T::BlockNumber>>)>,
+            ) -> DispatchResult {
+                T::AdminOrigin::ensure_origin(origin)?;
+                ensure!(Self::brand_new(&identity), Error::<T>::DidAlreadyExists);
+
+                Self::set_owner(&identity, &owner);
+                let (attribute_count, delegate_count) = (attributes.len() as u32, delegates.len() as u32);
+                for (name, value, valid_for) in attributes {
+                    ensure!(name.len() <= MAX_NAME_LENGTH, Error::<T>::AttributeCreationFailed);
+                    Self::validate_input(&name, Some(&value), valid_for)?;
+                    Self::insert_attribute(&owner, &identity, &name, &value, AttributeKind::Plain, valid_for, false)?;
+                    let (_, id) = Self::attribute_and_id(&identity, &name).ok_or(Error::<T>::AttributeCreationFailed)?;
+                    <ProvenanceOf<T>>::insert((&identity, id), Provenance::Imported);
+                }
+                for (delegate, delegate_type, key_type, valid_for) in delegates {
+                    ensure!(delegate_type.len() <= MAX_NAME_LENGTH, Error::<T>::InvalidDelegate);
+                    Self::validate_input(&delegate_type, None, valid_for)?;
+                    Self::insert_delegate(&owner, &identity, &delegate, &delegate_type, key_type, valid_for, false)?;
+                }
+                Self::deposit_event(Event::IdentityImported(identity, owner, attribute_count, delegate_count));
+                Ok(())
+            }
+
+            /// Halts all DID mutations, e.g. during a vulnerability response.
+            #[pallet::call_index(15)]
+            #[pallet::weight((0, DispatchClass::Operational))]
+            pub fn pause(origin: OriginFor<T>) -> DispatchResult {
+                T::AdminOrigin::ensure_origin(origin)?;
+                <Paused<T>>::put(true);
+                Self::deposit_event(Event::PalletPaused);
+                Ok(())
+            }
+
+            /// Resumes DID mutations.
+            #[pallet::call_index(16)]
+            #[pallet::weight((0, DispatchClass::Operational))]
+            pub fn unpause(origin: OriginFor<T>) -> DispatchResult {
+                T::AdminOrigin::ensure_origin(origin)?;
+                <Paused<T>>::put(false);
+                Self::deposit_event(Event::PalletUnpaused);
+                Ok(())
+            }
+        }
+
+    impl<T: Config> Call<T> {
+        /// Returns the [`Callset`] entry of a DID call, matching its call index.
+        pub fn callset(&self) -> Option<Callset> {
+            match self {
+                Call::change_owner { .. } => Some(Callset::ChangeOwner),
+                Call::add_delegate { .. } => Some(Callset::AddDelegate),
+                Call::revoke_delegate { .. } => Some(Callset::RevokeDelegate),
+                Call::add_attribute { .. } => Some(Callset::AddAttribute),
+                Call::revoke_attribute { .. } => Some(Callset::RevokeAttribute),
+                Call::delete_attribute { .. } => Some(Callset::DeleteAttribute),
+                Call::execute { .. } => Some(Callset::Execute),
+                Call::create_org_did { .. } => Some(Callset::CreateOrgDid),
+                Call::add_encrypted_attribute { .. } => Some(Callset::AddEncryptedAttribute),
+                Call::anchor_hash { .. } => Some(Callset::AnchorHash),
+                Call::renew_delegates { .. } => Some(Callset::RenewDelegates),
+                Call::add_own_attribute { .. } => Some(Callset::AddOwnAttribute),
+                Call::delete_own_attribute { .. } => Some(Callset::DeleteOwnAttribute),
+                Call::add_own_delegate { .. } => Some(Callset::AddOwnDelegate),
+                Call::register_did { .. } => Some(Callset::RegisterDid),
+                Call::pause { .. } => Some(Callset::Pause),
+                Call::unpause { .. } => Some(Callset::Unpause),
+                Call::heartbeat { .. } => Some(Callset::Heartbeat),
+                Call::register_presentation_nonce { .. } => Some(Callset::RegisterPresentationNonce),
+                Call::consume_presentation_nonce { .. } => Some(Callset::ConsumePresentationNonce),
+                Call::request_challenge { .. } => Some(Callset::RequestChallenge),
+                Call::set_access_price { .. } => Some(Callset::SetAccessPrice),
+                Call::purchase_access { .. } => Some(Callset::PurchaseAccess),
+                Call::confirm_access { .. } => Some(Callset::ConfirmAccess),
+                Call::refund_access { .. } => Some(Callset::RefundAccess),
+                Call::import_foreign_attestation { .. } => Some(Callset::ImportForeignAttestation),
+                Call::set_recovery_threshold { .. } => Some(Callset::SetRecoveryThreshold),
+                Call::vouch_recovery { .. } => Some(Callset::VouchRecovery),
+                Call::clear_recovery_vouches { .. } => Some(Callset::ClearRecoveryVouches),
+                Call::set_profile { .. } => Some(Callset::SetProfile),
+                Call::clear_profile { .. } => Some(Callset::ClearProfile),
+                Call::report_issuer_misbehavior { .. } => Some(Callset::ReportIssuerMisbehavior),
+                Call::claim_namespace { .. } => Some(Callset::ClaimNamespace),
+                Call::transfer_namespace { .. } => Some(Callset::TransferNamespace),
+                Call::release_namespace { .. } => Some(Callset::ReleaseNamespace),
+                Call::set_namespace_opt_in { .. } => Some(Callset::SetNamespaceOptIn),
+                Call::set_access_counter { .. } => Some(Callset::SetAccessCounter),
+                Call::log_access { .. } => Some(Callset::LogAccess),
+                Call::claim_handle { .. } => Some(Callset::ClaimHandle),
+                Call::unclaim_handle { .. } => Some(Callset::UnclaimHandle),
+                Call::issue_credential { .. } => Some(Callset::IssueCredential),
+                Call::revoke_credential { .. } => Some(Callset::RevokeCredential),
+                Call::accept_attestation { .. } => Some(Callset::AcceptAttestation),
+                Call::reject_attestation { .. } => Some(Callset::RejectAttestation),
+                Call::revoke_delegates_of_type { .. } => Some(Callset::RevokeDelegatesOfType),
+                Call::ban_attribute_name { .. } => Some(Callset::BanAttributeName),
+                Call::unban_attribute_name { .. } => Some(Callset::UnbanAttributeName),
+                Call::set_signature_policy { .. } => Some(Callset::SetSignaturePolicy),
+                Call::watch_identity { .. } => Some(Callset::WatchIdentity),
+                Call::unwatch_identity { .. } => Some(Callset::UnwatchIdentity),
+                Call::remove_watcher { .. } => Some(Callset::RemoveWatcher),
+                Call::import_identity { .. } => Some(Callset::ImportIdentity),
+                Call::set_attribute_dependency { .. } => Some(Callset::SetAttributeDependency),
+                Call::allow_dependent { .. } => Some(Callset::AllowDependent),
+                Call::set_keyless { .. } => Some(Callset::SetKeyless),
+                Call::set_attribute_cosigner { .. } => Some(Callset::SetAttributeCosigner),
+                Call::add_cosigned_attribute { .. } => Some(Callset::AddCosignedAttribute),
+                Call::flag_dormant { .. } => Some(Callset::FlagDormant),
+                Call::reap_identity { .. } => Some(Callset::ReapIdentity),
+                _ => None,
+            }
+        }
+
+        /// Returns `true` for calls managing an identity on behalf of its owner.
+        ///
+        /// All DID calls authorize the signed origin, which `pallet-proxy` resolves to the
+        /// proxied account. Runtimes can use this helper as the `InstanceFilter` of a
+        /// `DidManagement` proxy type, so custodians can manage their clients DIDs. The calls
+        /// of the `AdminOrigin` and those acting on the account itself, such as its recovery,
+        /// are left out, so such a proxy can neither take the account over nor spend from it.
+        pub fn is_did_management(&self) -> bool {
+            matches!(self.callset(), Some(callset) if !callset.is_admin() && !callset.is_account_control())
+        }
+    }
+
+    impl<T: Config> RecoveryAuthority<T::AccountId> for Pallet<T> {
+        /// Counts the vouches of the guardians still valid when the recovery is claimed.
+        fn can_recover(lost: &T::AccountId, rescuer: &T::AccountId) -> bool {
+            let threshold = match Self::recovery_threshold(lost) {
+                Some(threshold) => threshold,
+                None => return false,
+            };
+            let vouches = Self::recovery_vouches(lost, rescuer)
+                .iter()
+                .filter(|guardian| {
+                    Self::valid_listed_delegate(lost, RECOVERY_GUARDIAN_DELEGATE_TYPE, guardian).is_ok()
+                })
+                .count();
+            vouches >= threshold as usize
+        }
+
+        fn recovered(lost: &T::AccountId, rescuer: &T::AccountId) {
+            Self::remove_rescuer(lost, rescuer);
+            Self::deposit_event(Event::AccountRecovered(lost.clone(), rescuer.clone()));
+        }
+    }
+
+    // Not imported, its methods would be ambiguous with the `Did` ones.
+    impl<T: Config> crate::did::DidProvider<T::AccountId> for Pallet<T> {
+        fn identity_owner(identity: &T::AccountId) -> T::AccountId {
+            <Self as Did<_, _, _, _>>::identity_owner(identity)
+        }
+
+        fn valid_delegate(
+            identity: &T::AccountId,
+            delegate_type: &[u8],
+            delegate: &T::AccountId,
+        ) -> DispatchResult {
+            <Self as Did<_, _, _, _>>::valid_delegate(identity, delegate_type, delegate)
+        }
+
+        fn valid_attribute(identity: &T::AccountId, name: &[u8], value: &[u8]) -> DispatchResult {
+            <Self as Did<_, _, _, _>>::valid_attribute(identity, name, value)
+        }
+    }
+
+        impl<T: Config>
+        Did<T::AccountId, T::BlockNumber, T::Moment, T::Signature>
+        for Pallet<T>
+    {
+        /// Validates if the AccountId 'actual_owner' owns the identity.
+        fn is_owner(identity: &T::AccountId, actual_owner: &T::AccountId) -> DispatchResult {
+            ensure!(
+                !T::ExplicitOwnershipOnly::get() || Self::registered(identity),
+                Error::<T>::IdentityNotRegistered
+            );
+            let owner = Self::identity_owner(identity);
+            match owner == *actual_owner {
+                true => Ok(()),
+                false => Err(Error::<T>::NotOwner.into()),
+            }
+        }
+    
+        /// Get the identity owner if set.
+        /// If never changed, returns the identity as its owner.
+        fn identity_owner(identity: &T::AccountId) -> T::AccountId {
+            match Self::owner_of(identity) {
+                Some(id) => id,
+                None => identity.clone(),
+            }
+        }
+
+        /// Get the identity owner, only if it was explicitly set.
+        fn try_identity_owner(identity: &T::AccountId) -> Option<T::AccountId> {
+            Self::owner_of(identity)
+        }
+
+        /// Returns `true` if the identity has an explicit owner record.
+        fn registered(identity: &T::AccountId) -> bool {
+            <OwnerOf<T>>::contains_key(identity)
+        }
+    
+        /// Validates if a delegate belongs to an identity and it has not expired.
+        fn valid_delegate(
+            identity: &T::AccountId,
+            delegate_type: &[u8],
+            delegate: &T::AccountId,
+        ) -> DispatchResult {
+            ensure!(delegate_type.len() <= MAX_NAME_LENGTH, Error::<T>::InvalidDelegate);
+            ensure!(
+                Self::valid_listed_delegate(identity, delegate_type, delegate).is_ok()
+                    || Self::is_owner(identity, delegate).is_ok(),
+                Error::<T>::InvalidDelegate
+            );
+            Ok(())
+        }
+    
+        /// Validates that a delegate contains_key for specific purpose and remains valid at this block high.
+        fn valid_listed_delegate(
+            identity: &T::AccountId,
+            delegate_type: &[u8],
+            delegate: &T::AccountId,
+        ) -> DispatchResult {
+            ensure!(
+                <DelegateOf<T>>::contains_key(&identity, (delegate_type, &delegate)),
+                Error::<T>::InvalidDelegate
+            );
+    
+            match Self::delegate_of(identity, (delegate_type, delegate)) {
+                Some(d) if d.validity > <frame_system::Pallet<T>>::block_number() => Ok(()),
+                _ => Err(Error::<T>::InvalidDelegate.into()),
+            }
+        }
+    
+        // Creates a new delegete for an account.
+        fn create_delegate(
+            who: &T::AccountId,
+            identity: &T::AccountId,
+            delegate: &T::AccountId,
+            delegate_type: &[u8],
+            key_type: KeyType,
+            valid_for: Option<Validity<T::BlockNumber>>,
+        ) -> DispatchResult {
+            Self::insert_delegate(who, identity, delegate, delegate_type, key_type, valid_for, true)
+        }
     
-        // Creates a new delegete for an account.
-        fn create_delegate(
+        /// Checks if a signature is valid. Used to validate off-chain transactions.
+        fn check_signature(
+            signature: &T::Signature,
+            msg: &[u8],
+            signer: &T::AccountId,
+        ) -> DispatchResult {
+            if signature.verify(msg, signer) {
+                Ok(())
+            } else {
+                Err(Error::<T>::BadSignature.into())
+            }
+        }
+    
+        /// Checks if a signature is valid. Used to validate off-chain transactions.
+        fn valid_signer(
+            identity: &T::AccountId,
+            signature: &T::Signature,
+            msg: &[u8],
+            signer: &T::AccountId,
+        ) -> DispatchResult {
+            ensure!(Self::signs_for(identity, signer), Error::<T>::InvalidDelegate);
+            if let Some(schemes) = Self::signature_policy(identity) {
+                ensure!(schemes.contains(&signature.key_type()), Error::<T>::SchemeNotAllowed);
+            }
+            Self::check_signature(&signature, &msg, &signer)
+        }
+    
+        /// Adds a new attribute to an identity and colects the storage fee.
+        fn create_attribute(
+            who: &T::AccountId,
+            identity: &T::AccountId,
+            name: &[u8],
+            value: &[u8],
+            valid_for: Option<Validity<T::BlockNumber>>,
+        ) -> DispatchResult {
+            Self::ensure_attestor(who, identity, name)?;
+            let charged = Self::is_own_write(who, identity);
+            Self::insert_attribute(who, identity, name, value, AttributeKind::Plain, valid_for, charged)
+        }
+    
+        /// Updates the attribute validity to make it expire and invalid.
+        fn reset_attribute(who: T::AccountId, identity: &T::AccountId, name: &[u8]) -> DispatchResult {
+            Self::ensure_revoker(&who, identity, name)?;
+            Self::expire_attribute(who, identity, name)
+        }
+    
+        /// Validates if an attribute belongs to an identity and it has not expired.
+        fn valid_attribute(identity: &T::AccountId, name: &[u8], value: &[u8]) -> DispatchResult {
+            ensure!(name.len() <= MAX_NAME_LENGTH, Error::<T>::InvalidAttribute);
+            // Compares the value hash instead of decoding the attribute.
+            match Self::attribute_digest(identity, Self::key_hash(name)) {
+                Some((value_hash, validity))
+                    if validity > <frame_system::Pallet<T>>::block_number()
+                        && value_hash == Self::key_hash(value)
+                        && Self::dependency_valid(identity, name) =>
+                {
+                    Ok(())
+                }
+                _ => Err(Error::<T>::InvalidAttribute.into()),
+            }
+        }
+    
+        /// Returns the attribute and its hash identifier.
+        /// Uses a nonce to keep track of identifiers making them unique after attributes deletion.
+        fn attribute_and_id(
+            identity: &T::AccountId,
+            name: &[u8],
+        ) -> Option<AttributedId<T::BlockNumber, T::Moment>> {
+            let nonce = Self::nonce_of(&identity, name);
+    
+            // Used for first time attribute creation
+            let lookup_nonce = match nonce {
+                0u64 => 0u64,
+                _ => nonce - 1u64,
+            };
+    
+            // Looks up for the existing attribute.
+            // Needs to use actual attribute nonce -1.
+            let id = Self::attribute_id(identity, name, lookup_nonce);
+    
+            let attribute = Self::attribute_of((identity, id));
+            if let Some(attribute) = attribute {
+                Some((attribute, id))
+            } else {
+                None
+            }
+        }
+    }
+    
+    impl<T: Config> Pallet<T> {
+        /// Rejects DID mutations while the pallet is paused or migrating.
+        pub fn ensure_not_paused() -> DispatchResult {
+            Self::ensure_revocable()?;
+            ensure!(!<MigrationCursor<T>>::exists(), Error::<T>::MigrationOngoing);
+            Ok(())
+        }
+
+        /// Rejects the revocations of `Callset::is_revocation` while the pallet is paused.
+        /// Unlike the other mutations, they are accepted during migrations, so compromised
+        /// keys and attributes can be revoked at any time.
+        pub fn ensure_revocable() -> DispatchResult {
+            ensure!(!Self::paused(), Error::<T>::PalletPaused);
+            Ok(())
+        }
+
+        /// Runs a step of the ongoing migration, if any.
+        fn migration_step() -> Weight {
+            let db = T::DbWeight::get();
+            let cursor = match <MigrationCursor<T>>::get() {
+                Some(cursor) => cursor,
+                None => return db.reads(1),
+            };
+            let cursor = (!cursor.is_empty()).then_some(cursor);
+            let (next, weight) = T::Migration::step(cursor, T::MigrationStepSize::get());
+            match next {
+                Some(next) => <MigrationCursor<T>>::put(next),
+                None => {
+                    <MigrationCursor<T>>::kill();
+                    STORAGE_VERSION.put::<Self>();
+                    Self::deposit_event(Event::MigrationCompleted);
+                }
+            }
+            weight.saturating_add(db.reads_writes(1, 2))
+        }
+
+        /// Returns the account acting on an identity: either the account of a `ControllerOrigin`
+        /// (DIDs without private key) or the signer of the call.
+        pub fn ensure_controller(origin: OriginFor<T>) -> Result<T::AccountId, DispatchError> {
+            match T::ControllerOrigin::try_origin(origin) {
+                Ok(controller) => Ok(controller),
+                Err(origin) => Ok(ensure_signed(origin)?),
+            }
+        }
+
+        /// Returns the `publicKeyMultibase` of a listed delegate, using the key type
+        /// it was registered with. `None` if the delegate account is not its public key,
+        /// e.g. the hash of an ecdsa key.
+        pub fn delegate_key_multibase(
+            identity: &T::AccountId,
+            delegate_type: &[u8],
+            delegate: &T::AccountId,
+        ) -> Option<Vec<u8>> {
+            let record = Self::delegate_of(identity, (delegate_type, delegate))?;
+            multicodec::account_key_multibase(record.key_type, &delegate.encode())
+        }
+
+        /// Returns all the `(name, nonce)` attribute nonces of an identity.
+        pub fn nonces_of(identity: &T::AccountId) -> Vec<(Vec<u8>, u64)> {
+            <AttributeNonce<T>>::iter_prefix(identity).collect()
+        }
+
+        /// Returns a page of the `(name, value, validity)` current attributes of an identity,
+        /// visiting at most `max` attribute names from the `cursor` of the previous page.
+        pub fn attributes_of(
+            identity: &T::AccountId,
+            cursor: Option<Vec<u8>>,
+            max: u32,
+        ) -> Page<(Vec<u8>, Vec<u8>, T::BlockNumber)> {
+            let entries = match cursor {
+                Some(cursor) => <AttributeNonce<T>>::iter_prefix_from(identity, cursor),
+                None => <AttributeNonce<T>>::iter_prefix(identity),
+            };
+            Self::paginate(entries, max, |(name, _)| {
+                let (attribute, _) = Self::attribute_and_id(identity, &name)?;
+                Some((name, attribute.value, attribute.validity))
+            })
+        }
+
+        /// Returns a page of the `(delegate_type, delegate, record)` delegates of an identity,
+        /// expired ones included, visiting at most `max` delegates from the `cursor` of the
+        /// previous page.
+        pub fn delegates_of(
+            identity: &T::AccountId,
+            cursor: Option<Vec<u8>>,
+            max: u32,
+        ) -> Page<(Vec<u8>, T::AccountId, Delegate<T::BlockNumber>)> {
+            let entries = match cursor {
+                Some(cursor) => <DelegateOf<T>>::iter_prefix_from(identity, cursor),
+                None => <DelegateOf<T>>::iter_prefix(identity),
+            };
+            Self::paginate(entries, max, |((delegate_type, delegate), record)| {
+                Some((delegate_type, delegate, record))
+            })
+        }
+
+        /// Returns a page of the identities explicitly owned by `owner`, i.e. registered or
+        /// transferred to it, visiting at most `max` owned identities from the `cursor` of the
+        /// previous page.
+        pub fn identities_of_owner(
+            owner: &T::AccountId,
+            cursor: Option<Vec<u8>>,
+            max: u32,
+        ) -> Page<T::AccountId> {
+            let entries = match cursor {
+                Some(cursor) => <OwnedIdentities<T>>::iter_prefix_from(owner, cursor),
+                None => <OwnedIdentities<T>>::iter_prefix(owner),
+            };
+            Self::paginate(entries, max, |(identity, ())| Some(identity))
+        }
+
+        /// Collects the items of at most `max` storage entries, capped to `MAX_PAGE_SIZE`.
+        fn paginate<Entry, Item>(
+            mut entries: PrefixIterator<Entry>,
+            max: u32,
+            mut item_of: impl FnMut(Entry) -> Option<Item>,
+        ) -> Page<Item> {
+            let mut items = Vec::new();
+            for _ in 0..max.min(MAX_PAGE_SIZE) {
+                match entries.next() {
+                    Some(entry) => items.extend(item_of(entry)),
+                    None => return Page { items, next: None },
+                }
+            }
+            Page { items, next: Some(entries.last_raw_key().to_vec()) }
+        }
+
+        /// Returns the `(delegate_type, delegate, last_seen)` valid delegates of an identity
+        /// inactive for more than `inactive_for` blocks.
+        ///
+        /// Iterates over the delegates of the identity, as `valid_delegates`.
+        pub fn inactive_delegates(
+            identity: &T::AccountId,
+            inactive_for: T::BlockNumber,
+        ) -> Vec<(Vec<u8>, T::AccountId, T::BlockNumber)> {
+            let now = <frame_system::Pallet<T>>::block_number();
+            Self::valid_delegates(identity)
+                .into_iter()
+                .filter_map(|(delegate_type, delegate, _)| {
+                    let last_seen =
+                        Self::last_seen((identity, &delegate_type, &delegate)).unwrap_or_else(Zero::zero);
+                    (now.saturating_sub(last_seen) > inactive_for)
+                        .then_some((delegate_type, delegate, last_seen))
+                })
+                .collect()
+        }
+
+        /// Returns the `(delegate_type, delegate, record)` not expired delegates of an identity.
+        ///
+        /// Iterates over the delegates of the identity, expired ones included.
+        pub fn valid_delegates(
+            identity: &T::AccountId,
+        ) -> Vec<(Vec<u8>, T::AccountId, Delegate<T::BlockNumber>)> {
+            let now = <frame_system::Pallet<T>>::block_number();
+            <DelegateOf<T>>::iter_prefix(identity)
+                .filter(|(_, record)| record.validity > now)
+                .map(|((delegate_type, delegate), record)| (delegate_type, delegate, record))
+                .collect()
+        }
+
+        /// Resolves the DID Document of an identity from its owner and valid delegates.
+        ///
+        /// Signing delegates are authentication and assertion methods,
+        /// key agreement and capability invocation delegates are listed in their relationship.
+        /// Delegates whose account is not their public key, e.g. the hash of an ecdsa key,
+        /// are left out, as are the accounts of contract delegates, even when listed under
+        /// another type: a contract authorizes through its `is_authorized` query, not a key.
+        pub fn did_document(identity: &T::AccountId) -> DidDocument {
+            let mut did = DidDocument {
+                id: document::did_of(identity),
+                controller: document::did_of(&Self::identity_owner(identity)),
+                ..Default::default()
+            };
+
+            let delegates = Self::valid_delegates(identity);
+            let contracts: Vec<T::AccountId> = delegates
+                .iter()
+                .filter(|(delegate_type, ..)| delegate_type == CONTRACT_DELEGATE_TYPE)
+                .map(|(_, contract, _)| contract.clone())
+                .collect();
+            for (delegate_type, delegate, record) in delegates {
+                if contracts.contains(&delegate) {
+                    continue;
+                }
+                let public_key_multibase =
+                    match multicodec::account_key_multibase(record.key_type, &delegate.encode()) {
+                        Some(multibase) => multibase,
+                        None => continue,
+                    };
+                let fragment = public_key_multibase.clone();
+                if delegate_type == SIGNING_DELEGATE_TYPE {
+                    did.authentication.push(fragment.clone());
+                    did.assertion_method.push(fragment.clone());
+                } else if delegate_type == KEY_AGREEMENT_DELEGATE_TYPE {
+                    did.key_agreement.push(fragment.clone());
+                } else if delegate_type == CAPABILITY_INVOCATION_DELEGATE_TYPE {
+                    did.capability_invocation.push(fragment.clone());
+                } else {
+                    continue;
+                }
+                did.add_verification_method(VerificationMethod {
+                    fragment,
+                    key_type: record.key_type,
+                    controller: document::did_of(&delegate),
+                    public_key_multibase,
+                });
+            }
+            did
+        }
+
+        /// Exports a valid delegate of an identity as an authorization capability, to be signed
+        /// off chain by the identity owner or one of its signers.
+        pub fn export_capability(
+            identity: &T::AccountId,
+            delegate: &T::AccountId,
+            delegate_type: &[u8],
+        ) -> Option<Capability<T::AccountId, T::Hash, T::BlockNumber>> {
+            Self::valid_listed_delegate(identity, delegate_type, delegate).ok()?;
+            let record = Self::delegate_of(identity, (delegate_type, delegate))?;
+            Some(Capability {
+                chain_id: <frame_system::Pallet<T>>::block_hash(T::BlockNumber::zero()),
+                invocation_target: identity.clone(),
+                controller: delegate.clone(),
+                allowed_action: delegate_type.to_vec(),
+                expires: record.validity,
+            })
+        }
+
+        /// Validates a signed capability: it must match the current delegations of this chain,
+        /// and be signed by the owner or a signer of the delegating identity.
+        pub fn verify_capability(
+            capability: &Capability<T::AccountId, T::Hash, T::BlockNumber>,
+            signer: &T::AccountId,
+            signature: &T::Signature,
+        ) -> DispatchResult {
+            let current = Self::export_capability(
+                &capability.invocation_target,
+                &capability.controller,
+                &capability.allowed_action,
+            );
+            ensure!(current.as_ref() == Some(capability), Error::<T>::InvalidCapability);
+            Self::valid_signer(&capability.invocation_target, signature, &capability.signing_payload(), signer)
+        }
+
+        /// Returns `true` if a challenge nonce of a verifier can still be consumed.
+        pub fn valid_presentation_nonce(verifier: &T::AccountId, nonce: &[u8; 32]) -> bool {
+            Self::presentation_nonce(verifier, nonce)
+                .map_or(false, |expiry| expiry >= <frame_system::Pallet<T>>::block_number())
+        }
+
+        /// Returns the payload signed to answer a challenge of a verifier: the SCALE encoding of
+        /// `(CHALLENGE_RESPONSE_TAG, verifier, challenge)`, i.e. the 22 bytes of the tag, then
+        /// the encoded verifier account, then the 32 bytes of the challenge.
+        pub fn challenge_response_payload(verifier: &T::AccountId, challenge: &[u8; 32]) -> Vec<u8> {
+            (CHALLENGE_RESPONSE_TAG, verifier, challenge).encode()
+        }
+
+        /// Authenticates an identity answering a challenge of a verifier: the challenge has not
+        /// expired nor been consumed, and its `challenge_response_payload` is signed by the owner
+        /// or a signer of the identity.
+        pub fn verify_challenge_response(
+            verifier: &T::AccountId,
+            challenge: &[u8; 32],
+            identity: &T::AccountId,
+            signer: &T::AccountId,
+            signature: &T::Signature,
+        ) -> DispatchResult {
+            ensure!(Self::valid_presentation_nonce(verifier, challenge), Error::<T>::UnknownPresentationNonce);
+            let payload = Self::challenge_response_payload(verifier, challenge);
+            Self::valid_signer(identity, signature, &payload, signer)
+        }
+
+        /// Returns the cost of a read query from the `WeightInfo` of the runtime, for RPC layers
+        /// to apply per-request limits or meter paid APIs.
+        pub fn query_cost(query: ReadQuery) -> Weight {
+            match query {
+                ReadQuery::ValidSigner => T::WeightInfo::valid_signer(),
+                ReadQuery::ValidAttribute => T::WeightInfo::valid_attribute(),
+                ReadQuery::DidDocument { delegates } => T::WeightInfo::did_document(delegates),
+            }
+        }
+
+        /// Performs all the checks of `execute` on an off-chain transaction, e.g. of its signature,
+        /// nonce, validity and limits, without writing state, so relayers can filter out the
+        /// transactions which would be rejected before paying fees to submit them.
+        pub fn dry_run_execute(
+            transaction: AttributeTransaction<T::Signature, T::AccountId>,
+        ) -> Result<PostDispatchInfo, DispatchError> {
+            let info = Call::<T>::execute { transaction: transaction.clone() }.get_dispatch_info();
+            with_transaction(|| {
+                let result = Self::ensure_not_paused().and_then(|()| Self::execute_transaction(transaction));
+                TransactionOutcome::Rollback(result)
+            })?;
+            Ok(PostDispatchInfo { actual_weight: Some(info.weight), pays_fee: info.pays_fee })
+        }
+
+        /// Returns the declared weight of a call and the deposit it would reserve from `who`, or
+        /// the price it would escrow, given the current state. The weights are not benchmarked
+        /// yet: most calls declare a zero weight, the others estimates of their storage accesses.
+        pub fn operation_cost(who: &T::AccountId, call: &Call<T>) -> (Weight, BalanceOf<T>) {
+            let deposit = match call {
+                Call::set_profile { identity, .. } => match Self::profile_of(identity) {
+                    Some(profile) if profile.depositor == *who => Zero::zero(),
+                    _ => T::ProfileDeposit::get(),
+                },
+                Call::claim_namespace { .. } => T::NamespaceDeposit::get(),
+                Call::claim_handle { .. } => T::HandleDeposit::get(),
+                Call::issue_credential { subject, .. } if Self::requires_consent(who, subject) => {
+                    T::AttestationDeposit::get()
+                }
+                Call::issue_credential { .. } => T::CredentialDeposit::get(),
+                // Attestations awaiting the consent of their subject.
+                Call::add_attribute { identity, name, .. }
+                | Call::add_encrypted_attribute { identity, name, .. }
+                    if Self::requires_consent(who, identity)
+                        && Self::ensure_attestor(who, identity, name).is_ok() =>
+                {
+                    T::AttestationDeposit::get()
+                }
+                Call::watch_identity { identity } if !Self::watchers_of(identity).contains(who) => {
+                    T::WatcherDeposit::get()
+                }
+                Call::purchase_access { identity, name, .. } => {
+                    Self::access_price((identity, name)).unwrap_or_else(Zero::zero)
+                }
+                _ => Zero::zero(),
+            };
+            (call.get_dispatch_info().weight, deposit)
+        }
+
+        /// Validates attribute claims in bulk, e.g. for airdrops or allowlists, as `valid_attribute`
+        /// would. Claims on the same attribute share a single storage read.
+        pub fn verify_claims_batch(claims: Vec<(T::AccountId, Vec<u8>, Vec<u8>)>) -> Vec<bool> {
+            let now = <frame_system::Pallet<T>>::block_number();
+            let mut attributes = BTreeMap::new();
+            claims
+                .into_iter()
+                .map(|(identity, name, value)| {
+                    if name.len() > MAX_NAME_LENGTH {
+                        return false;
+                    }
+                    let attribute = attributes
+                        .entry((identity, name))
+                        .or_insert_with_key(|(identity, name)| {
+                            Self::attribute_and_id(identity, name)
+                                .filter(|_| Self::dependency_valid(identity, name))
+                                .map(|(attribute, _)| attribute)
+                        });
+                    matches!(
+                        attribute,
+                        Some(attribute) if attribute.validity > now && attribute.value == value
+                    )
+                })
+                .collect()
+        }
+
+        /// Returns the blocks and times the current attribute of an identity named `name`
+        /// was created and last updated at. Unknown for the attributes of the genesis.
+        pub fn attribute_metadata(
+            identity: &T::AccountId,
+            name: &[u8],
+        ) -> Option<AttributeMetadata<T::BlockNumber, T::Moment>> {
+            let (_, id) = Self::attribute_and_id(identity, name)?;
+            Self::metadata_of((identity, id))
+        }
+
+        /// Validates an attribute as `valid_attribute`, and that it was updated at most `max_age`
+        /// blocks ago, for verifiers requiring fresh attestations.
+        pub fn valid_attribute_fresh(
+            identity: &T::AccountId,
+            name: &[u8],
+            value: &[u8],
+            max_age: T::BlockNumber,
+        ) -> DispatchResult {
+            Self::valid_attribute(identity, name, value)?;
+            let (updated_at, _) = Self::attribute_metadata(identity, name)
+                .ok_or(Error::<T>::AttributeTooOld)?
+                .updated_at;
+            let age = <frame_system::Pallet<T>>::block_number().saturating_sub(updated_at);
+            ensure!(age <= max_age, Error::<T>::AttributeTooOld);
+            Ok(())
+        }
+
+        /// Validates an attribute as `valid_attribute`, and that it was updated at most `max_age`
+        /// ago in wall-clock time, for verifiers not relying on block times.
+        pub fn valid_attribute_fresh_by_time(
+            identity: &T::AccountId,
+            name: &[u8],
+            value: &[u8],
+            max_age: T::Moment,
+        ) -> DispatchResult {
+            Self::valid_attribute(identity, name, value)?;
+            let (_, updated_at) = Self::attribute_metadata(identity, name)
+                .ok_or(Error::<T>::AttributeTooOld)?
+                .updated_at;
+            let age = T::Timestamp::now().saturating_sub(updated_at);
+            ensure!(age <= max_age, Error::<T>::AttributeTooOld);
+            Ok(())
+        }
+
+        /// Returns the number of reads logged for the current attribute of an identity named
+        /// `name`, `None` if it is not counted.
+        pub fn access_count(identity: &T::AccountId, name: &[u8]) -> Option<u64> {
+            let (_, id) = Self::attribute_and_id(identity, name)?;
+            Self::access_counter_of((identity, id))
+        }
+
+        /// Returns the identity a handle refers to, if claimed.
+        pub fn identity_by_handle(handle: &[u8]) -> Option<T::AccountId> {
+            let handle: BoundedVec<u8, T::MaxHandleLength> = handle.to_vec().try_into().ok()?;
+            Self::handle_record(handle).map(|(identity, _, _)| identity)
+        }
+
+        /// Validates that `contract` is a valid `ContractDelegate` of the identity, authorizing
+        /// `caller` to act on its behalf for `purpose` according to its `is_authorized` query.
+        pub fn valid_contract_delegate(
+            identity: &T::AccountId,
+            contract: &T::AccountId,
+            caller: &T::AccountId,
+            purpose: &[u8],
+        ) -> DispatchResult {
+            Self::valid_listed_delegate(identity, CONTRACT_DELEGATE_TYPE, contract)?;
+            ensure!(
+                T::ContractAuthorizer::is_authorized(contract, identity, caller, purpose),
+                Error::<T>::ContractNotAuthorized
+            );
+            Ok(())
+        }
+
+        /// Returns the weight of `reap_identity` for upper bounds of the attribute names and of
+        /// the delegates of the identity. The credentials, pending claims and watchers of the
+        /// identity are bounded by the configuration.
+        pub fn reap_identity_weight(attribute_count: u32, delegate_count: u32) -> Weight {
+            // Dormancy, storage usage, profile, handle and its owner, recovery threshold, activity,
+            // document hash, policy, counters, feeless writes and the bounty transfers.
+            const FIXED_READS_WRITES: u64 = 18;
+            // Nonce and dependency of a name, then its dependents.
+            const READS_PER_ATTRIBUTE: u64 = 2;
+            // Record, digest, issuer, metadata, counter, provenance, price, cosigner,
+            // dependency, dependent index and storage charge of a name.
+            const WRITES_PER_ATTRIBUTE: u64 = 11;
+            // Record, type index, liveness and storage charge of a delegate.
+            const WRITES_PER_DELEGATE: u64 = 4;
+            // Record, deposit and depositor of each credential, pending claim and watcher.
+            const READS_WRITES_PER_DEPOSIT: u64 = 3;
+
+            let attributes = attribute_count as u64;
+            let delegates = delegate_count as u64;
+            let deposits = T::MaxCredentialsPerSubject::get() as u64
+                + T::MaxPendingAttestations::get() as u64
+                + T::MaxWatchers::get() as u64;
+            let reads = FIXED_READS_WRITES
+                + attributes * (READS_PER_ATTRIBUTE + T::MaxDependents::get() as u64)
+                + delegates
+                + deposits * READS_WRITES_PER_DEPOSIT;
+            let writes = FIXED_READS_WRITES
+                + attributes * WRITES_PER_ATTRIBUTE
+                + delegates * WRITES_PER_DELEGATE
+                + deposits * READS_WRITES_PER_DEPOSIT;
+            T::DbWeight::get().reads_writes(reads, writes).saturating_add(Self::clear_rescuers_weight())
+        }
+
+        /// Weight of clearing the vouches of all the rescuers of an identity.
+        pub fn clear_rescuers_weight() -> Weight {
+            T::DbWeight::get().reads_writes(1, T::MaxRescuers::get() as u64 + 1)
+        }
+
+        /// Clears the vouches of all the rescuers of an identity, at most `MaxRescuers`.
+        fn clear_rescuers(identity: &T::AccountId) {
+            let count = <RescuerCount<T>>::take(identity);
+            let _ = <RecoveryVouches<T>>::clear_prefix(identity, count, None);
+        }
+
+        /// Clears the vouches for a rescuer of an identity.
+        fn remove_rescuer(identity: &T::AccountId, rescuer: &T::AccountId) {
+            if <RecoveryVouches<T>>::contains_key(identity, rescuer) {
+                <RecoveryVouches<T>>::remove(identity, rescuer);
+                <RescuerCount<T>>::mutate(identity, |count| *count = count.saturating_sub(1));
+            }
+        }
+
+        /// Returns the last block an identity was updated at by its owner or signers, or one of
+        /// its delegates sent a heartbeat at, `None` if it never was.
+        pub fn last_activity(identity: &T::AccountId) -> Option<T::BlockNumber> {
+            // Identities not updated since activity is recorded apart read it from their last update.
+            Self::last_heartbeat(identity).or_else(|| Self::updated_by(identity).map(|(_, block, _)| block))
+        }
+
+        /// Returns the activity status of an identity under the dormancy policy.
+        pub fn activity_status(identity: &T::AccountId) -> ActivityStatus<T::BlockNumber> {
+            if let Some(since) = Self::dormant_since(identity) {
+                return ActivityStatus::Dormant(since);
+            }
+            let now_block_number = <frame_system::Pallet<T>>::block_number();
+            match (T::DormancyPeriod::get(), Self::last_activity(identity)) {
+                (Some(period), Some(last)) if now_block_number >= last.saturating_add(period) => {
+                    ActivityStatus::Inactive
+                }
+                _ => ActivityStatus::Active,
+            }
+        }
+
+        /// Returns the `(id, credential)` credentials issued on chain to a subject.
+        pub fn credentials_of(
+            subject: &T::AccountId,
+        ) -> Vec<([u8; 32], Credential<T::AccountId, T::BlockNumber, T::Moment>)> {
+            <Credentials<T>>::iter_prefix(subject).collect()
+        }
+
+        /// Returns the status of a credential of a subject, `None` if unknown.
+        pub fn credential_status(subject: &T::AccountId, id: &[u8; 32]) -> Option<AttestationStatus> {
+            let credential = Self::credential_of(subject, id)?;
+            Some(if <SuspendedIssuers<T>>::contains_key(&credential.issuer) {
+                AttestationStatus::Disputed
+            } else if credential.expiry > <frame_system::Pallet<T>>::block_number() {
+                AttestationStatus::Valid
+            } else {
+                AttestationStatus::Expired
+            })
+        }
+
+        /// Returns the `(id, attestation)` third-party claims awaiting the consent of an identity.
+        pub fn pending_attestations(
+            identity: &T::AccountId,
+        ) -> Vec<([u8; 32], PendingAttestation<T::AccountId, T::BlockNumber, T::Moment>)> {
+            <PendingAttestations<T>>::iter_prefix(identity).collect()
+        }
+
+        /// Returns where the current attribute of an identity named `name` was written.
+        pub fn attribute_provenance(identity: &T::AccountId, name: &[u8]) -> Option<Provenance> {
+            let (_, id) = Self::attribute_and_id(identity, name)?;
+            Some(<ProvenanceOf<T>>::get((identity, id)).unwrap_or_default())
+        }
+
+        /// Returns the status of the current attribute of an identity named `name`.
+        /// Attributes written by a suspended issuer are disputed, even once expired.
+        pub fn attestation_status(identity: &T::AccountId, name: &[u8]) -> Option<AttestationStatus> {
+            let (attribute, id) = Self::attribute_and_id(identity, name)?;
+            let disputed = Self::attribute_issuer((identity, id))
+                .map_or(false, |issuer| <SuspendedIssuers<T>>::contains_key(issuer));
+            Some(if disputed {
+                AttestationStatus::Disputed
+            } else if attribute.validity <= <frame_system::Pallet<T>>::block_number() {
+                AttestationStatus::Expired
+            } else if !Self::dependency_valid(identity, name) {
+                AttestationStatus::Orphaned
+            } else {
+                AttestationStatus::Valid
+            })
+        }
+
+        /// Returns the nonce to sign in the next off-chain transaction of an identity.
+        pub fn next_meta_nonce(identity: &T::AccountId) -> u64 {
+            Self::meta_nonce(identity)
+        }
+
+        /// Verifies a signature issued by an off-chain `did:key` identity.
+        pub fn verify_external_did_signature(
+            did: &[u8],
+            message: &[u8],
+            signature: &[u8],
+        ) -> DispatchResult {
+            match external_did::parse_external_did(did) {
+                Some(ExternalDid::Key { key_type, public_key }) => {
+                    ensure!(
+                        external_did::verify_with_key(key_type, &public_key, message, signature),
+                        Error::<T>::BadSignature
+                    );
+                    Ok(())
+                }
+                _ => Err(Error::<T>::InvalidExternalDid.into()),
+            }
+        }
+
+        /// Verifies a signature issued by an off-chain `did:web` identity.
+        /// The resolved DID Document is supplied as a SCALE encoded `WebDidDocument` preimage,
+        /// and any of its verification methods may have produced the signature.
+        pub fn verify_did_web_signature(
+            did: &[u8],
+            document: &[u8],
+            message: &[u8],
+            signature: &[u8],
+        ) -> DispatchResult {
+            ensure!(
+                matches!(external_did::parse_external_did(did), Some(ExternalDid::Web { .. })),
+                Error::<T>::InvalidExternalDid
+            );
+            let document = WebDidDocument::decode(&mut &document[..])
+                .map_err(|_| Error::<T>::InvalidExternalDid)?;
+            ensure!(document.id == did, Error::<T>::InvalidExternalDid);
+
+            let signed = document.verification_methods.iter().any(|method| {
+                match multicodec::decode_public_key_multibase(method) {
+                    Some((key_type, public_key)) => {
+                        external_did::verify_with_key(key_type, &public_key, message, signature)
+                    }
+                    None => false,
+                }
+            });
+            ensure!(signed, Error::<T>::BadSignature);
+            Ok(())
+        }
+
+        /// Validates the user supplied name (or delegate type), value and validity of a call,
+        /// so that no unreadable state is created.
+        pub fn validate_input(
+            name: &[u8],
+            value: Option<&[u8]>,
+            valid_for: Option<Validity<T::BlockNumber>>,
+        ) -> DispatchResult {
+            ensure!(!name.is_empty(), Error::<T>::EmptyName);
+            ensure!(!name.starts_with(RESERVED_NAME_PREFIX), Error::<T>::ReservedName);
+            if let Some(value) = value {
+                ensure!(!value.is_empty(), Error::<T>::EmptyValue);
+            }
+            if let Some(Validity::For(valid_for)) = valid_for {
+                ensure!(!valid_for.is_zero(), Error::<T>::ZeroValidity);
+                Self::expiry_block(Validity::For(valid_for))?;
+            }
+            Ok(())
+        }
+
+        /// Rejects attribute names in a `ReservedNamespaces` namespace, which are only
+        /// written by the structured extrinsics of the pallet.
+        pub fn ensure_unreserved_namespace(name: &[u8]) -> DispatchResult {
+            ensure!(
+                !T::ReservedNamespaces::get().iter().any(|namespace| name.starts_with(namespace)),
+                Error::<T>::ReservedNamespace
+            );
+            Ok(())
+        }
+
+        /// Rejects writes under a claimed namespace, of any identity, unless `who` is the owner
+        /// of the namespace or one of its signing delegates. Returns `true` if the identity
+        /// opted in the namespace, so that `who` attests it.
+        fn ensure_namespace_attestor(
             who: &T::AccountId,
             identity: &T::AccountId,
-            delegate: &T::AccountId,
-            delegate_type: &[u8],
-            valid_for: Option<T::BlockNumber>,
+            name: &[u8],
+        ) -> Result<bool, DispatchError> {
+            let prefix = match name.iter().position(|b| *b == b'/') {
+                Some(end) => &name[..=end],
+                None => return Ok(false),
+            };
+            let owner = match Self::namespace_of(prefix) {
+                Some((owner, _, _)) => owner,
+                None => return Ok(false),
+            };
+            Self::valid_delegate(&owner, SIGNING_DELEGATE_TYPE, who).map_err(|_| Error::<T>::NotNamespaceOwner)?;
+            Ok(Self::namespace_opt_in(identity, prefix) == Some(owner))
+        }
+
+        /// Authorizes `who` to write the attribute `name` of an identity: attributes under a
+        /// claimed namespace are attested by the namespace owner, of the identities opted in,
+        /// the others by the identity owner.
+        pub fn ensure_attestor(who: &T::AccountId, identity: &T::AccountId, name: &[u8]) -> DispatchResult {
+            if !Self::ensure_namespace_attestor(who, identity, name)? {
+                Self::is_owner(identity, who)?;
+            }
+            Ok(())
+        }
+
+        /// Authorizes `who` to revoke or delete the attribute `name` of an identity: its owner
+        /// always can, as can the attestor of its namespace.
+        fn ensure_revoker(who: &T::AccountId, identity: &T::AccountId, name: &[u8]) -> DispatchResult {
+            if Self::is_owner(identity, who).is_ok() {
+                return Ok(());
+            }
+            Self::ensure_attestor(who, identity, name)
+        }
+
+        /// Returns `true` if a claim of `who` about an identity needs the consent of its owner.
+        fn requires_consent(who: &T::AccountId, identity: &T::AccountId) -> bool {
+            T::RequireAttestationConsent::get() && Self::is_owner(identity, who).is_err()
+        }
+
+        /// Stores a third-party claim until the owner of the identity accepts or rejects it,
+        /// reserving `AttestationDeposit` from its issuer.
+        fn queue_attestation(
+            identity: &T::AccountId,
+            attestation: PendingAttestation<T::AccountId, T::BlockNumber, T::Moment>,
         ) -> DispatchResult {
-            Self::is_owner(&identity, who)?;
-            ensure!(who != delegate, Error::<T>::InvalidDelegate);
+            let (issuer, id) = match &attestation {
+                PendingAttestation::Attribute { issuer, name, value, .. } => {
+                    (issuer.clone(), (issuer, identity, name, value).using_encoded(blake2_256))
+                }
+                // Credentials keep their id once accepted.
+                PendingAttestation::Credential(credential) => (
+                    credential.issuer.clone(),
+                    (&credential.issuer, identity, credential.schema_id, credential.claim_hash)
+                        .using_encoded(blake2_256),
+                ),
+            };
             ensure!(
-                !Self::valid_listed_delegate(identity, delegate_type, delegate).is_ok(),
-                Error::<T>::InvalidDelegate
+                !<PendingAttestations<T>>::contains_key(identity, id),
+                Error::<T>::AttestationAlreadyPending
             );
-    
-            let now_block_number = <frame_system::Pallet<T>>::block_number();
-            let validity: T::BlockNumber = match valid_for {
-                Some(blocks) => now_block_number + blocks,
-                None => u32::max_value().into(),
-            };
-    
-            <DelegateOf<T>>::insert((&identity, delegate_type, delegate), Some(&validity));
+            let pending = Self::pending_attestation_count(identity);
+            ensure!(pending < T::MaxPendingAttestations::get(), Error::<T>::TooManyPendingAttestations);
+
+            let deposit = T::AttestationDeposit::get();
+            T::Currency::reserve(&issuer, deposit)?;
+            <AttestationDeposits<T>>::insert(identity, id, (&issuer, deposit));
+            <PendingAttestationCount<T>>::insert(identity, pending + 1);
+            <PendingAttestations<T>>::insert(identity, id, attestation);
+            Self::deposit_event(Event::AttestationPending(identity.clone(), issuer, id));
             Ok(())
         }
-    
-        /// Checks if a signature is valid. Used to validate off-chain transactions.
-        fn check_signature(
-            signature: &T::Signature,
-            msg: &[u8],
-            signer: &T::AccountId,
+
+        /// Stores a credential of a subject, reserving `CredentialDeposit` from its issuer.
+        fn store_credential(
+            subject: &T::AccountId,
+            id: [u8; 32],
+            credential: Credential<T::AccountId, T::BlockNumber, T::Moment>,
         ) -> DispatchResult {
-            if signature.verify(msg, signer) {
-                Ok(())
+            let count = Self::credential_count(subject);
+            ensure!(count < T::MaxCredentialsPerSubject::get(), Error::<T>::TooManyCredentials);
+
+            let deposit = T::CredentialDeposit::get();
+            T::Currency::reserve(&credential.issuer, deposit)?;
+            <CredentialDeposits<T>>::insert(subject, id, (&credential.issuer, deposit));
+            <CredentialCount<T>>::insert(subject, count + 1);
+            <Credentials<T>>::insert(subject, id, credential);
+            Ok(())
+        }
+
+        /// Removes a pending third-party claim, unreserving the deposit of its issuer.
+        fn dequeue_attestation(identity: &T::AccountId, id: [u8; 32]) {
+            <PendingAttestations<T>>::remove(identity, id);
+            if let Some((depositor, deposit)) = <AttestationDeposits<T>>::take(identity, id) {
+                T::Currency::unreserve(&depositor, deposit);
+            }
+            <PendingAttestationCount<T>>::mutate_exists(identity, |count| {
+                *count = count.and_then(|count| count.checked_sub(1)).filter(|count| *count > 0);
+            });
+        }
+
+        /// Returns the id of the attribute of an identity named `name` created with `nonce`.
+        pub fn attribute_id(identity: &T::AccountId, name: &[u8], nonce: u64) -> [u8; 32] {
+            T::KeyHasher::hash_of(&(identity, name, nonce)).into()
+        }
+
+        /// Returns the `KeyHasher` hash of raw bytes, e.g. of an attribute name or value.
+        pub fn key_hash(data: &[u8]) -> [u8; 32] {
+            T::KeyHasher::hash(data).into()
+        }
+
+        /// Returns `true` for identities never registered, updated, written feelessly nor reaped.
+        fn brand_new(identity: &T::AccountId) -> bool {
+            !Self::registered(identity)
+                && !<UpdatedBy<T>>::contains_key(identity)
+                && !<FeelessWriters<T>>::contains_key(identity)
+        }
+
+        /// Returns the feeless writes of the current block.
+        fn feeless_writes_used() -> u32 {
+            let (block, used) = <FeelessWrites<T>>::get();
+            if block == <frame_system::Pallet<T>>::block_number() { used } else { 0 }
+        }
+
+        /// Returns `true` if `call` of `who` is the first write of its `brand_new` identity, small
+        /// enough to be feeless, and the `FeelessFirstWrites` quota of the block is not exhausted.
+        pub fn is_feeless_first_write(who: &T::AccountId, call: &Call<T>) -> bool {
+            let small = |name: &[u8], value: &[u8]| {
+                Self::attribute_bytes(name, value) <= T::MaxFeelessWriteBytes::get()
+            };
+            let first_write = match call {
+                Call::register_did {} => true,
+                Call::add_attribute { identity, name, value, .. } => identity == who && small(name, value),
+                Call::add_own_attribute { name, value, .. } => small(name, value),
+                _ => false,
+            };
+            first_write && Self::brand_new(who) && Self::feeless_writes_used() < T::FeelessFirstWrites::get()
+        }
+
+        /// Consumes the feeless first write of `who` and a write of the quota of the block.
+        pub fn note_feeless_first_write(who: &T::AccountId) {
+            let now = <frame_system::Pallet<T>>::block_number();
+            <FeelessWrites<T>>::put((now, Self::feeless_writes_used().saturating_add(1)));
+            <FeelessWriters<T>>::insert(who, ());
+        }
+
+        /// Returns the validity, in blocks, of a delegate of the given type added without validity.
+        pub fn default_delegate_validity(delegate_type: &[u8]) -> T::BlockNumber {
+            T::DelegateValidityDefaults::get()
+                .into_iter()
+                .find(|(default_type, _)| default_type == delegate_type)
+                .map_or_else(T::DefaultDelegateValidity::get, |(_, valid_for)| valid_for)
+        }
+
+        /// Returns the first block something valid for `valid_for` from now is no longer valid at.
+        /// `Validity::Never` is stored as the maximum block number, never reached.
+        fn expiry_block(valid_for: Validity<T::BlockNumber>) -> Result<T::BlockNumber, DispatchError> {
+            match valid_for {
+                Validity::For(blocks) => <frame_system::Pallet<T>>::block_number()
+                    .checked_add(&blocks)
+                    .ok_or_else(|| Error::<T>::ValidityOverflow.into()),
+                Validity::Never => Ok(T::BlockNumber::max_value()),
+            }
+        }
+
+        /// Validates and writes a plain attribute of an identity for `who`, or queues it until the
+        /// owner consents to a third-party attestation. Returns `false` if it was queued.
+        ///
+        /// `signer` is `true` once `who` proved it signs for the identity, as in off-chain
+        /// transactions, otherwise `ensure_attestor` authorizes it.
+        fn checked_insert_attribute(
+            who: &T::AccountId,
+            identity: &T::AccountId,
+            name: &[u8],
+            value: &[u8],
+            valid_for: Option<Validity<T::BlockNumber>>,
+            signer: bool,
+        ) -> Result<bool, DispatchError> {
+            ensure!(name.len() <= MAX_NAME_LENGTH, Error::<T>::AttributeCreationFailed);
+            Self::validate_input(name, Some(value), valid_for)?;
+            Self::ensure_unreserved_namespace(name)?;
+            if signer {
+                Self::ensure_namespace_attestor(who, identity, name)?;
             } else {
-                Err(Error::<T>::BadSignature.into())
+                Self::ensure_attestor(who, identity, name)?;
+                if Self::requires_consent(who, identity) {
+                    let attestation = PendingAttestation::Attribute {
+                        issuer: who.clone(),
+                        name: name.to_vec(),
+                        value: value.to_vec(),
+                        kind: AttributeKind::Plain,
+                        valid_for,
+                    };
+                    Self::queue_attestation(identity, attestation)?;
+                    return Ok(false);
+                }
             }
+            let charged = Self::is_own_write(who, identity);
+            Self::insert_attribute(who, identity, name, value, AttributeKind::Plain, valid_for, charged)?;
+            Ok(true)
         }
-    
-        /// Checks if a signature is valid. Used to validate off-chain transactions.
-        fn valid_signer(
+
+        /// Validates and revokes an attribute of an identity for `who`, authorized as by
+        /// `checked_insert_attribute`. Signers of the identity revoke any of its attributes.
+        fn checked_revoke_attribute(
+            who: &T::AccountId,
             identity: &T::AccountId,
-            signature: &T::Signature,
-            msg: &[u8],
-            signer: &T::AccountId,
+            name: &[u8],
+            signer: bool,
         ) -> DispatchResult {
-            // Owner or a delegate signer.
-            Self::valid_delegate(&identity, b"x25519VerificationKey2022", &signer)?;
-            Self::check_signature(&signature, &msg, &signer)
+            ensure!(name.len() <= MAX_NAME_LENGTH, Error::<T>::AttributeRemovalFailed);
+            Self::validate_input(name, None, None)?;
+            Self::ensure_unreserved_namespace(name)?;
+            if signer {
+                Self::expire_attribute(who.clone(), identity, name)
+            } else {
+                Self::reset_attribute(who.clone(), identity, name)
+            }
         }
-    
-        /// Adds a new attribute to an identity and colects the storage fee.
-        fn create_attribute(
+
+        /// Stores a new attribute of the given kind, unless it requires a co-signature.
+        /// Authorization is checked by the caller.
+        fn insert_attribute(
             who: &T::AccountId,
             identity: &T::AccountId,
             name: &[u8],
             value: &[u8],
-            valid_for: Option<T::BlockNumber>,
+            kind: AttributeKind,
+            valid_for: Option<Validity<T::BlockNumber>>,
+            charged: bool,
         ) -> DispatchResult {
-            Self::is_owner(&identity, &who)?;
-    
+            ensure!(Self::attribute_cosigner(identity, name).is_none(), Error::<T>::CosignatureRequired);
+            Self::store_attribute(who, identity, name, value, kind, valid_for, charged)
+        }
+
+        /// Stores a new attribute of the given kind, counted in the storage usage of the
+        /// identity if `charged`. Authorization and co-signature are checked by the caller.
+        fn store_attribute(
+            who: &T::AccountId,
+            identity: &T::AccountId,
+            name: &[u8],
+            value: &[u8],
+            kind: AttributeKind,
+            valid_for: Option<Validity<T::BlockNumber>>,
+            charged: bool,
+        ) -> DispatchResult {
+            ensure!(
+                !Self::banned_names().iter().any(|banned| name.starts_with(banned)),
+                Error::<T>::BannedName
+            );
             if Self::attribute_and_id(identity, name).is_some() {
                 Err(Error::<T>::AttributeCreationFailed.into())
             } else {
+                let valid_for =
+                    valid_for.unwrap_or_else(|| Validity::For(T::DefaultAttributeValidity::get()));
+                // Attributes never expire only if `MaxAttributeValidity` is unbounded.
+                let blocks = match valid_for {
+                    Validity::For(blocks) => blocks,
+                    Validity::Never => T::BlockNumber::max_value(),
+                };
+                ensure!(blocks <= T::MaxAttributeValidity::get(), Error::<T>::AttributeValidityTooLong);
+
                 let now_timestamp = T::Timestamp::now();
                 let now_block_number = <frame_system::Pallet<T>>::block_number();
-                let validity: T::BlockNumber = match valid_for {
-                    Some(blocks) => now_block_number + blocks,
-                    None => u32::max_value().into(),
-                };
+                let validity = Self::expiry_block(valid_for)?;
     
-                let mut nonce = Self::nonce_of((&identity, name.to_vec()));
-                let id = (&identity, name, nonce).using_encoded(blake2_256);
+                let mut nonce = Self::nonce_of(&identity, name);
+                let id = Self::attribute_id(identity, name, nonce);
+                if charged {
+                    Self::charge_storage(identity, &id, Self::attribute_bytes(name, value))?;
+                }
                 let new_attribute = Attribute {
                     name: (&name).to_vec(),
                     value: (&value).to_vec(),
                     validity,
                     creation: now_timestamp,
                     nonce,
+                    kind,
                 };
     
                 // Prevent panic overflow
                 nonce = nonce.checked_add(1).ok_or(Error::<T>::Overflow)?;
                 <AttributeOf<T>>::insert((&identity, &id), new_attribute);
-                <AttributeNonce<T>>::mutate((&identity, name.to_vec()), |n| *n = nonce);
-                <UpdatedBy<T>>::insert(identity, (who, now_block_number, now_timestamp));
+                <AttributeDigests<T>>::insert(identity, Self::key_hash(name), (Self::key_hash(value), validity));
+                Self::schedule_expiry_alert(identity, WatchedItem::Attribute { name: name.to_vec() }, validity);
+                Self::schedule_dependency_expiry(identity, name, validity);
+                <AttributeNonce<T>>::insert(&identity, name, nonce);
+                <AttributeIssuer<T>>::insert((&identity, &id), who);
+                let now = (now_block_number, now_timestamp);
+                let metadata = AttributeMetadata { created_at: now, updated_at: now };
+                <AttributeMetadataOf<T>>::insert((&identity, &id), metadata);
+                Self::note_update(identity, who, now_block_number, now_timestamp);
+                Self::deposit_event(Event::AttributeTimestamped(
+                    identity.clone(),
+                    name.to_vec(),
+                    now_block_number,
+                    now_timestamp,
+                ));
                 Ok(())
             }
         }
-    
-        /// Updates the attribute validity to make it expire and invalid.
-        fn reset_attribute(who: T::AccountId, identity: &T::AccountId, name: &[u8]) -> DispatchResult {
-            Self::is_owner(&identity, &who)?;
+
+        /// Sets the attribute validity to the current block. Authorization is checked by the caller.
+        fn expire_attribute(who: T::AccountId, identity: &T::AccountId, name: &[u8]) -> DispatchResult {
             // If the attribute contains_key, the latest valid block is set to the current block.
             let result = Self::attribute_and_id(identity, name);
+            let (now_block_number, now_timestamp) = (<frame_system::Pallet<T>>::block_number(), T::Timestamp::now());
             match result {
                 Some((mut attribute, id)) => {
-                    attribute.validity = <frame_system::Pallet<T>>::block_number();
+                    attribute.validity = now_block_number;
+                    <AttributeDigests<T>>::mutate(identity, Self::key_hash(name), |digest| {
+                        if let Some((_, validity)) = digest {
+                            *validity = attribute.validity;
+                        }
+                    });
                     <AttributeOf<T>>::mutate((&identity, id), |a| *a = Some(attribute));
+                    <AttributeMetadataOf<T>>::mutate((&identity, id), |metadata| {
+                        if let Some(metadata) = metadata {
+                            metadata.updated_at = (now_block_number, now_timestamp);
+                        }
+                    });
                 }
                 None => return Err(Error::<T>::AttributeResetFailed.into()),
             }
+            // A revoked attribute is no longer for sale.
+            <AccessPrices<T>>::remove((identity, name));
+            Self::invalidate_dependents(identity, name);
     
             // Keep track of the updates.
-            <UpdatedBy<T>>::insert(
-                identity,
-                (
-                    who,
-                    <frame_system::Pallet<T>>::block_number(),
-                    T::Timestamp::now(),
-                ),
-            );
+            Self::note_update(identity, &who, now_block_number, now_timestamp);
+            Self::deposit_event(Event::AttributeTimestamped(
+                identity.clone(),
+                name.to_vec(),
+                now_block_number,
+                now_timestamp,
+            ));
+            Ok(())
+        }
+
+        /// Removes a watcher of an identity and unreserves its deposit.
+        fn remove_watcher_of(identity: &T::AccountId, watcher: &T::AccountId) -> DispatchResult {
+            <Watchers<T>>::try_mutate(identity, |watchers| -> DispatchResult {
+                let index = watchers.iter().position(|w| w == watcher).ok_or(Error::<T>::NotWatching)?;
+                watchers.swap_remove(index);
+                Ok(())
+            })?;
+            if let Some(deposit) = <WatcherDeposits<T>>::take(identity, watcher) {
+                T::Currency::unreserve(watcher, deposit);
+            }
             Ok(())
         }
+
+        /// Queues the alert of the watchers of an identity before a delegate or attribute
+        /// expires at `validity`, if the identity is watched.
+        fn schedule_expiry_alert(identity: &T::AccountId, item: WatchedItem<T::AccountId>, validity: T::BlockNumber) {
+            let now_block_number = <frame_system::Pallet<T>>::block_number();
+            if validity <= now_block_number
+                || validity == T::BlockNumber::max_value()
+                || Self::watchers_of(identity).is_empty()
+            {
+                return;
+            }
+            let at = validity
+                .saturating_sub(T::ExpiryNotice::get())
+                .max(now_block_number.saturating_add(One::one()));
+            let alert = (identity.clone(), item.clone(), validity);
+            let dropped = <ExpiryAlerts<T>>::mutate(at, |alerts| alerts.try_push(alert).is_err());
+            if dropped {
+                Self::deposit_event(Event::ExpiryAlertDropped(identity.clone(), item, validity));
+            }
+        }
+
+        /// Emits the expiry alerts queued for block `n`, unless the delegate or attribute was
+        /// renewed, revoked or replaced since.
+        fn alert_expiries(n: T::BlockNumber) -> Weight {
+            let db = T::DbWeight::get();
+            let alerts = <ExpiryAlerts<T>>::take(n);
+            let mut weight = db.reads_writes(1, 1);
+            for (identity, item, validity) in alerts {
+                weight = weight.saturating_add(db.reads(2));
+                let watchers = Self::watchers_of(&identity);
+                let current = match &item {
+                    WatchedItem::Delegate { delegate_type, delegate } => {
+                        Self::delegate_of(&identity, (delegate_type, delegate)).map(|d| d.validity)
+                    }
+                    WatchedItem::Attribute { name } => {
+                        Self::attribute_digest(&identity, Self::key_hash(name)).map(|(_, validity)| validity)
+                    }
+                };
+                if current != Some(validity) {
+                    continue;
+                }
+                // Each event is appended to the events of the block.
+                weight = weight.saturating_add(db.writes(watchers.len() as u64));
+                for watcher in watchers {
+                    Self::deposit_event(Event::WatchedIdentityAlert(
+                        watcher,
+                        identity.clone(),
+                        item.clone(),
+                        validity,
+                    ));
+                }
+            }
+            weight
+        }
+
+        /// Returns the bytes counted in the storage usage of an attribute.
+        fn attribute_bytes(name: &[u8], value: &[u8]) -> u32 {
+            name.len().saturating_add(value.len()).try_into().unwrap_or(u32::MAX)
+        }
+
+        /// Returns the bytes counted in the storage usage of a delegate.
+        fn delegate_bytes(delegate_type: &[u8], delegate: &T::AccountId) -> u32 {
+            delegate_type.len().saturating_add(delegate.encoded_size()).try_into().unwrap_or(u32::MAX)
+        }
+
+        /// Creates a new delegate for an account, counted in its storage usage if `charged`.
+        fn insert_delegate(
+            who: &T::AccountId,
+            identity: &T::AccountId,
+            delegate: &T::AccountId,
+            delegate_type: &[u8],
+            key_type: KeyType,
+            valid_for: Option<Validity<T::BlockNumber>>,
+            charged: bool,
+        ) -> DispatchResult {
+            Self::is_owner(&identity, who)?;
+            ensure!(who != delegate, Error::<T>::InvalidDelegate);
+            ensure!(
+                !Self::valid_listed_delegate(identity, delegate_type, delegate).is_ok(),
+                Error::<T>::InvalidDelegate
+            );
     
-        /// Validates if an attribute belongs to an identity and it has not expired.
-        fn valid_attribute(identity: &T::AccountId, name: &[u8], value: &[u8]) -> DispatchResult {
-            ensure!(name.len() <= 64, Error::<T>::InvalidAttribute);
-            let result = Self::attribute_and_id(identity, name);
+            let valid_for = valid_for
+                .unwrap_or_else(|| Validity::For(Self::default_delegate_validity(delegate_type)));
+            let validity = Self::expiry_block(valid_for)?;
+            // Revoked records are overwritten, and stay counted once.
+            let key = Self::delegate_key(delegate_type, delegate);
+            if charged && !<StorageCharges<T>>::contains_key(identity, key) {
+                Self::charge_storage(identity, &key, Self::delegate_bytes(delegate_type, delegate))?;
+            }
     
-            let (attr, _) = match result {
-                Some((attr, id)) => (attr, id),
-                None => return Err(Error::<T>::InvalidAttribute.into()),
+            <DelegateOf<T>>::insert(
+                identity,
+                (delegate_type, delegate),
+                Delegate { validity, key_type },
+            );
+            <DelegatesOfType<T>>::insert((identity, delegate_type), delegate, ());
+            let item = WatchedItem::Delegate { delegate_type: delegate_type.to_vec(), delegate: delegate.clone() };
+            Self::schedule_expiry_alert(identity, item, validity);
+            <LastSeen<T>>::insert(
+                (&identity, delegate_type, delegate),
+                <frame_system::Pallet<T>>::block_number(),
+            );
+            Ok(())
+        }
+
+        /// Sets the owner of an identity, indexed in `OwnedIdentities`.
+        fn set_owner(identity: &T::AccountId, owner: &T::AccountId) {
+            if let Some(previous) = <OwnerOf<T>>::get(identity) {
+                <OwnedIdentities<T>>::remove(&previous, identity);
+            }
+            <OwnerOf<T>>::insert(identity, owner);
+            <OwnedIdentities<T>>::insert(owner, identity, ());
+        }
+
+        /// Returns the key of a delegate in the `StorageCharges` of its identity.
+        fn delegate_key(delegate_type: &[u8], delegate: &T::AccountId) -> [u8; 32] {
+            (delegate_type, delegate).using_encoded(blake2_256)
+        }
+
+        /// Returns `true` if `who` writes for the identity, as one of its signers: its owner, its
+        /// signing and capability invocation delegates, or those of a keyless owner. Only these
+        /// writes are counted in its storage usage: third parties, e.g. namespace owners, issuers
+        /// and relayers, can not fill it.
+        fn is_own_write(who: &T::AccountId, identity: &T::AccountId) -> bool {
+            Self::signs_for(identity, who)
+        }
+
+        /// Adds the `bytes` of the record `key` to the storage usage of an identity, within
+        /// `MaxBytesPerDid`.
+        fn charge_storage(identity: &T::AccountId, key: &[u8; 32], bytes: u32) -> DispatchResult {
+            <StorageUsage<T>>::try_mutate(identity, |usage| -> DispatchResult {
+                *usage = usage
+                    .checked_add(bytes)
+                    .filter(|usage| *usage <= T::MaxBytesPerDid::get())
+                    .ok_or(Error::<T>::StorageQuotaExceeded)?;
+                Ok(())
+            })?;
+            <StorageCharges<T>>::insert(identity, key, bytes);
+            Ok(())
+        }
+
+        /// Removes the bytes of the record `key`, if counted, from the storage usage of an identity.
+        fn release_storage(identity: &T::AccountId, key: &[u8; 32]) {
+            let bytes = match <StorageCharges<T>>::take(identity, key) {
+                Some(bytes) => bytes,
+                None => return,
             };
-    
-            if (attr.validity > (<frame_system::Pallet<T>>::block_number()))
-                && (attr.value == value.to_vec())
+            <StorageUsage<T>>::mutate_exists(identity, |usage| {
+                *usage = usage.map(|usage| usage.saturating_sub(bytes)).filter(|usage| *usage > 0);
+            });
+        }
+
+        /// Returns `true` if `signer` signs for an identity: its owner, a delegate signer, a
+        /// capability invocation delegate, or a delegate signer of a keyless owner.
+        fn signs_for(identity: &T::AccountId, signer: &T::AccountId) -> bool {
+            Self::valid_delegate(identity, SIGNING_DELEGATE_TYPE, signer).is_ok()
+                || Self::valid_listed_delegate(identity, CAPABILITY_INVOCATION_DELEGATE_TYPE, signer).is_ok()
+                || Self::keyless_owner_signer(identity, signer)
+        }
+
+        /// Returns `true` if the owner of an identity is keyless and `signer` is one of its
+        /// valid signing delegates.
+        fn keyless_owner_signer(identity: &T::AccountId, signer: &T::AccountId) -> bool {
+            let owner = Self::identity_owner(identity);
+            <KeylessAccounts<T>>::contains_key(&owner)
+                && Self::valid_listed_delegate(&owner, SIGNING_DELEGATE_TYPE, signer).is_ok()
+        }
+
+        /// Returns whether the current attribute of an identity named `name` has not expired.
+        fn attribute_active(identity: &T::AccountId, name: &[u8]) -> bool {
+            Self::attribute_digest(identity, Self::key_hash(name))
+                .map_or(false, |(_, validity)| validity > <frame_system::Pallet<T>>::block_number())
+        }
+
+        /// Returns whether the parent attribute of an attribute, if any, has not expired.
+        fn dependency_valid(identity: &T::AccountId, name: &[u8]) -> bool {
+            Self::attribute_dependency(identity, name)
+                .map_or(true, |(parent_identity, parent_name)| Self::attribute_active(&parent_identity, &parent_name))
+        }
+
+        /// Removes the dependency of an attribute on its parent, if any.
+        fn forget_dependency(identity: &T::AccountId, name: &[u8]) {
+            if let Some(parent) = <AttributeDependencies<T>>::take(identity, name) {
+                <Dependents<T>>::remove(&parent, (identity.clone(), name.to_vec()));
+            }
+        }
+
+        /// Notifies the dependents of an attribute no longer valid, if any.
+        fn invalidate_dependents(identity: &T::AccountId, name: &[u8]) {
+            let dependents: Vec<_> = <Dependents<T>>::iter_key_prefix((identity.clone(), name.to_vec()))
+                .take(T::MaxDependents::get() as usize)
+                .collect();
+            if !dependents.is_empty() {
+                Self::deposit_event(Event::DependentAttributesInvalidated(
+                    identity.clone(),
+                    name.to_vec(),
+                    dependents,
+                ));
+            }
+        }
+
+        /// Queues the notification of the dependents of an attribute expiring at `validity`,
+        /// if it has dependents. At most `MaxExpiryAlerts` are queued per block.
+        fn schedule_dependency_expiry(identity: &T::AccountId, name: &[u8], validity: T::BlockNumber) {
+            let key = (identity.clone(), name.to_vec());
+            if validity <= <frame_system::Pallet<T>>::block_number()
+                || validity == T::BlockNumber::max_value()
+                || <Dependents<T>>::iter_prefix(&key).next().is_none()
             {
-                Ok(())
-            } else {
-                Err(Error::<T>::InvalidAttribute.into())
+                return;
             }
+            <DependencyExpiries<T>>::mutate(validity, |expiries| {
+                if !expiries.contains(&key) {
+                    let _ = expiries.try_push(key);
+                }
+            });
         }
-    
-        /// Returns the attribute and its hash identifier.
-        /// Uses a nonce to keep track of identifiers making them unique after attributes deletion.
-        fn attribute_and_id(
+
+        /// Notifies the dependents of the attributes expiring at block `n`, unless renewed,
+        /// replaced or already revoked since.
+        fn invalidate_expired_dependencies(n: T::BlockNumber) -> Weight {
+            let db = T::DbWeight::get();
+            let expiries = <DependencyExpiries<T>>::take(n);
+            let mut weight = db.reads_writes(1, 1);
+            for (identity, name) in expiries {
+                weight = weight.saturating_add(db.reads(1 + T::MaxDependents::get() as u64));
+                let current = Self::attribute_digest(&identity, Self::key_hash(&name)).map(|(_, validity)| validity);
+                if current == Some(n) {
+                    Self::invalidate_dependents(&identity, &name);
+                }
+            }
+            weight
+        }
+
+        /// Queues the removal of a presentation nonce after its last block `expiry`.
+        /// At most `MaxNonceExpiries` nonces expire per block, further ones are rejected.
+        fn schedule_nonce_removal(verifier: &T::AccountId, nonce: [u8; 32], expiry: T::BlockNumber) -> DispatchResult {
+            <PresentationNonceExpiries<T>>::try_mutate(expiry, |expiries| {
+                expiries
+                    .try_push((verifier.clone(), nonce))
+                    .map_err(|_| Error::<T>::TooManyPresentationNonces.into())
+            })
+        }
+
+        /// Removes the presentation nonces which could be consumed until the block before `n`,
+        /// unless already consumed, or registered again since.
+        fn remove_expired_nonces(n: T::BlockNumber) -> Weight {
+            let db = T::DbWeight::get();
+            let expiry = n.saturating_sub(One::one());
+            let expiries = <PresentationNonceExpiries<T>>::take(expiry);
+            let mut weight = db.reads_writes(1, 1);
+            for (verifier, nonce) in expiries {
+                weight = weight.saturating_add(db.reads(1));
+                if Self::presentation_nonce(&verifier, nonce) == Some(expiry) {
+                    <PresentationNonces<T>>::remove(&verifier, nonce);
+                    weight = weight.saturating_add(db.writes(1));
+                }
+            }
+            weight
+        }
+
+        /// Records the update of an identity by `who`, rolling its document hash. Only the
+        /// updates of the identity itself, i.e. of its owner or signers, count as its activity:
+        /// third parties, e.g. attestors and importers, do not keep it from becoming dormant.
+        fn note_update(
+            identity: &T::AccountId,
+            who: &T::AccountId,
+            block: T::BlockNumber,
+            moment: T::Moment,
+        ) {
+            if Self::signs_for(identity, who) {
+                <LastHeartbeat<T>>::insert(identity, block);
+                <Dormant<T>>::remove(identity);
+            } else if !<LastHeartbeat<T>>::contains_key(identity) {
+                // Keep the activity read from the last update. An identity first updated by a third
+                // party is active from that update.
+                let last = Self::updated_by(identity).map_or(block, |(_, last, _)| last);
+                <LastHeartbeat<T>>::insert(identity, last);
+            }
+            <UpdatedBy<T>>::insert(identity, (who, block, moment));
+            let extrinsic = <frame_system::Pallet<T>>::extrinsic_index();
+            <DocumentHashes<T>>::mutate(identity, |hash| {
+                *hash = (*hash, who, block, extrinsic).using_encoded(blake2_256);
+            });
+        }
+
+        /// Returns the encryption envelope stored in an encrypted attribute, unless it expired or
+        /// was revoked.
+        pub fn encrypted_attribute(
             identity: &T::AccountId,
             name: &[u8],
-        ) -> Option<AttributedId<T::BlockNumber, T::Moment>> {
-            let nonce = Self::nonce_of((&identity, name.to_vec()));
-    
-            // Used for first time attribute creation
-            let lookup_nonce = match nonce {
-                0u64 => 0u64,
-                _ => nonce - 1u64,
-            };
-    
-            // Looks up for the existing attribute.
-            // Needs to use actual attribute nonce -1.
-            let id = (&identity, name, lookup_nonce).using_encoded(blake2_256);
-    
-            let attribute = Self::attribute_of((identity, id));
-            if let Some(attribute) = attribute {
-                Some((attribute, id))
-            } else {
-                None
+        ) -> Option<EncryptionEnvelope<T::AccountId>> {
+            let (attribute, _) = Self::attribute_and_id(identity, name)?;
+            if attribute.validity <= <frame_system::Pallet<T>>::block_number() {
+                return None;
+            }
+            match attribute.kind {
+                AttributeKind::Encrypted => EncryptionEnvelope::decode(&mut &attribute.value[..]).ok(),
+                AttributeKind::Plain => None,
             }
         }
-    }
-    
-    impl<T: Config> Pallet<T> {
-        /// Creates a new attribute from a off-chain transaction.
+
+        /// Returns the payload signed in an off-chain `AttributeTransaction`.
+        ///
+        /// The meta nonce was appended to the payload of the original release: transactions
+        /// signed without it fail with `BadSignature` and have to be signed again.
+        pub fn meta_transaction_payload(
+            name: &[u8],
+            value: &[u8],
+            validity: u32,
+            identity: &T::AccountId,
+            meta_nonce: u64,
+        ) -> Vec<u8> {
+            let mut encoded = name.encode();
+            encoded.extend(value.encode());
+            encoded.extend(validity.encode());
+            encoded.extend(identity.encode());
+            encoded.extend(meta_nonce.encode());
+            encoded
+        }
+
+        /// Returns the payload the co-signer of an attribute signs to approve its value.
+        /// The attribute nonce increments with every write, so approvals can not be replayed.
+        pub fn cosignature_payload(
+            identity: &T::AccountId,
+            name: &[u8],
+            value: &[u8],
+            valid_for: Option<Validity<T::BlockNumber>>,
+        ) -> Vec<u8> {
+            let mut encoded = identity.encode();
+            encoded.extend(name.encode());
+            encoded.extend(value.encode());
+            encoded.extend(valid_for.encode());
+            encoded.extend(Self::nonce_of(identity, name).encode());
+            encoded
+        }
+
+        /// Verifies and applies an off-chain signed transaction.
+        fn execute_transaction(
+            transaction: AttributeTransaction<T::Signature, T::AccountId>,
+        ) -> DispatchResult {
+            // Replay protection.
+            let meta_nonce = Self::meta_nonce(&transaction.identity);
+            let encoded = Self::meta_transaction_payload(
+                &transaction.name,
+                &transaction.value,
+                transaction.validity,
+                &transaction.identity,
+                meta_nonce,
+            );
+
+            // Execute the storage update if the signer is valid.
+            Self::signed_attribute(&encoded, &transaction)?;
+            let meta_nonce = meta_nonce.checked_add(1).ok_or(Error::<T>::Overflow)?;
+            <MetaNonce<T>>::insert(&transaction.identity, meta_nonce);
+            Self::deposit_event(Event::AttributeTransactionExecuted(transaction));
+            Ok(())
+        }
+
+        /// Creates a new attribute from a off-chain transaction, with the checks of
+        /// `add_attribute` and `revoke_attribute`.
+        /// The relayer submitting the transaction needs no rights on the identity;
+        /// the update is recorded as made by the signer.
         fn signed_attribute(
-            who: T::AccountId,
             encoded: &[u8],
             transaction: &AttributeTransaction<T::Signature, T::AccountId>,
         ) -> DispatchResult {
-            // Verify that the Data was signed by the owner or a not expired signer delegate.
+            // Verify that the Data was signed by the owner, a not expired signer delegate
+            // or a not expired capability invocation delegate.
             Self::valid_signer(
                 &transaction.identity,
                 &transaction.signature,
                 &encoded,
                 &transaction.signer,
             )?;
-            Self::is_owner(&transaction.identity, &transaction.signer)?;
-            ensure!(transaction.name.len() <= 64, Error::<T>::BadTransaction);
-    
-            let now_block_number = <frame_system::Pallet<T>>::block_number();
-            let validity = now_block_number + transaction.validity.into();
     
             // If validity was set to 0 in the transaction,
             // it will set the attribute latest valid block to the actual block.
-            if validity > now_block_number {
-                Self::create_attribute(
-                    &who,
+            if transaction.validity > 0 {
+                Self::checked_insert_attribute(
+                    &transaction.signer,
                     &transaction.identity,
                     &transaction.name,
                     &transaction.value,
-                    Some(transaction.validity.into()),
+                    Some(Validity::For(transaction.validity.into())),
+                    true,
                 )?;
             } else {
-                Self::reset_attribute(who, &transaction.identity, &transaction.name)?;
+                Self::checked_revoke_attribute(&transaction.signer, &transaction.identity, &transaction.name, true)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[cfg(any(feature = "try-runtime", test))]
+    impl<T: Config> Pallet<T> {
+        /// Checks that `AttributeDigests` holds the digest of the current attribute of every
+        /// name, and nothing else. Skipped while a migration, which may backfill the digests,
+        /// is ongoing.
+        pub fn do_try_state() -> Result<(), &'static str> {
+            if <MigrationCursor<T>>::exists() {
+                return Ok(());
             }
+            let mut current = 0usize;
+            for ((identity, _), attribute) in <AttributeOf<T>>::iter() {
+                // Older attributes of a name are not resolved, so have no digest.
+                if Self::nonce_of(&identity, &attribute.name).saturating_sub(1) != attribute.nonce {
+                    continue;
+                }
+                current += 1;
+                ensure!(
+                    Self::attribute_digest(&identity, Self::key_hash(&attribute.name))
+                        == Some((Self::key_hash(&attribute.value), attribute.validity)),
+                    "AttributeDigests disagrees with AttributeOf"
+                );
+            }
+            ensure!(
+                <AttributeDigests<T>>::iter_keys().count() == current,
+                "AttributeDigests holds the digests of deleted attributes"
+            );
             Ok(())
         }
     }
-        
-}
\ No newline at end of file
+
+    #[cfg(feature = "std")]
+    impl<T: Config> Pallet<T> {
+        /// Exports the DID registry as a genesis config, e.g. to relaunch a chain
+        /// or migrate identities from a solochain to a parachain, with the issuers of the
+        /// attestations and the suspended issuers.
+        ///
+        /// Not carried over: the update records and attribute metadata, the state backed by a
+        /// deposit (profiles, handles, namespaces, credentials, pending attestations, watchers,
+        /// access prices and purchases), and the recovery settings, anchors, presentation nonces,
+        /// provenances, dependencies, cosigners and signature policies.
+        pub fn export_state() -> GenesisConfig<T> {
+            GenesisConfig {
+                owners: <OwnerOf<T>>::iter().collect(),
+                delegates: <DelegateOf<T>>::iter()
+                    .map(|(identity, (delegate_type, delegate), record)| {
+                        (identity, delegate_type, delegate, record.key_type, record.validity)
+                    })
+                    .collect(),
+                attributes: <AttributeOf<T>>::iter()
+                    .map(|((identity, _), attribute)| {
+                        (
+                            identity,
+                            attribute.name,
+                            attribute.value,
+                            attribute.kind,
+                            attribute.validity,
+                            attribute.nonce,
+                        )
+                    })
+                    .collect(),
+                nonces: <AttributeNonce<T>>::iter().collect(),
+                meta_nonces: <MetaNonce<T>>::iter().collect(),
+                issuers: <AttributeIssuer<T>>::iter()
+                    .filter_map(|(key, issuer)| {
+                        let attribute = <AttributeOf<T>>::get(&key)?;
+                        Some((key.0, attribute.name, attribute.nonce, issuer))
+                    })
+                    .collect(),
+                suspended_issuers: <SuspendedIssuers<T>>::iter().collect(),
+            }
+        }
+
+        /// Exports the DID registry as the JSON of its genesis config.
+        pub fn build_genesis_json() -> Result<String, serde_json::Error> {
+            serde_json::to_string_pretty(&Self::export_state())
+        }
+    }
+}
@@ -0,0 +1,545 @@
+//! Storage migrations stepped across many blocks.
+//!
+//! On a runtime upgrade bumping the storage version, the pallet starts the `Migration` of
+//! the runtime and runs a step of at most `MigrationStepSize` entries in every block, so
+//! chains with millions of attributes migrate without exceeding the PoV limits.
+//! DID mutations are rejected until the migration completes, except the revocations.
+//!
+//! The on-chain storage version is only bumped once the migration completes, so that the
+//! migrations which can not run twice are skipped by the chains past the storage version
+//! introducing them. The values whose layout changed before the storage was versioned are translated by
+//! [`v1`], and the keys changed by storage version 4 are re-keyed by [`v4`]: they run first,
+//! before the other migrations decode the values.
+
+use crate::pallet::{
+    Anchors, AttributeDigests, Pallet, AttributeMetadataOf, AttributeNonce, AttributeOf, Config, DelegateOf, DelegatesOfType,
+    DependencyExpiries, Event, ExpiryAlerts, OwnedIdentities, OwnerOf, PresentationNonceExpiries, PresentationNonces,
+    RecoveryVouches, RescuerCount, WatcherDeposits, Watchers,
+};
+use crate::types::{Attribute, AttributeKind, AttributeMetadata, Delegate, KeyType, WatchedItem};
+use codec::{Decode, Encode};
+use frame_support::{
+    storage::{storage_prefix, unhashed},
+    traits::{Get, GetStorageVersion, PalletInfoAccess, ReservableCurrency},
+    weights::Weight,
+};
+use sp_io::hashing::{blake2_128, twox_64};
+use sp_runtime::traits::Zero;
+use sp_std::{marker::PhantomData, vec::Vec};
+
+/// A migration resumable from a cursor.
+pub trait SteppedMigration {
+    /// Migrates at most `limit` entries from `cursor`, the raw storage key of the last migrated
+    /// entry (`None` on the first step). Returns the cursor of the next step, `None` once done,
+    /// and the consumed weight.
+    fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight);
+}
+
+impl SteppedMigration for () {
+    fn step(_cursor: Option<Vec<u8>>, _limit: u32) -> (Option<Vec<u8>>, Weight) {
+        (None, Weight::zero())
+    }
+}
+
+/// Runs `A` then `B`. The first byte of the cursor is the migration in progress.
+impl<A: SteppedMigration, B: SteppedMigration> SteppedMigration for (A, B) {
+    fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+        let with_phase = |phase: u8, next: Option<Vec<u8>>| {
+            next.map(|next| [sp_std::vec![phase], next].concat())
+        };
+        match cursor.as_deref() {
+            None | Some([0, ..]) => {
+                let cursor = cursor.map(|cursor| cursor[1..].to_vec());
+                let (next, weight) = A::step(cursor, limit);
+                // `B` starts in the next step.
+                (with_phase(0, next).or(Some(sp_std::vec![1])), weight)
+            }
+            Some([_, rest @ ..]) => {
+                let cursor = (!rest.is_empty()).then(|| rest.to_vec());
+                let (next, weight) = B::step(cursor, limit);
+                (with_phase(1, next), weight)
+            }
+            Some([]) => (None, Weight::zero()),
+        }
+    }
+}
+
+/// Returns `true` if the on-chain storage version is older than `version`, i.e. the chain has
+/// yet to run the migrations of `version`.
+fn older_than<T: Config>(version: u16) -> bool {
+    Pallet::<T>::on_chain_storage_version() < version
+}
+
+/// Migrates at most `limit` entries of the storage item `item` from `cursor`, iterating their
+/// raw keys, for the items whose values or keys can not be decoded with their current type.
+/// `migrate` gets the key of each entry and its part after the prefix of the item, and returns
+/// the number of writes.
+fn step_raw_keys<T: Config>(
+    item: &[u8],
+    cursor: Option<Vec<u8>>,
+    limit: u32,
+    mut migrate: impl FnMut(&[u8], &[u8]) -> u64,
+) -> (Option<Vec<u8>>, Weight) {
+    let prefix = storage_prefix(<Pallet<T> as PalletInfoAccess>::name().as_bytes(), item);
+    let mut previous = cursor.unwrap_or_else(|| prefix.to_vec());
+    let (mut reads, mut writes) = (0u64, 0u64);
+    for _ in 0..limit.max(1) {
+        let key = match sp_io::storage::next_key(&previous).filter(|key| key.starts_with(&prefix)) {
+            Some(key) => key,
+            None => return (None, T::DbWeight::get().reads_writes(reads + 1, writes)),
+        };
+        reads += 2;
+        writes += migrate(&key, &key[prefix.len()..]);
+        previous = key;
+    }
+    (Some(previous), T::DbWeight::get().reads_writes(reads, writes))
+}
+
+/// Decodes a key hashed with `Twox64Concat`. `None` if `hashed` is not, e.g. it was re-keyed
+/// already: the re-keyed entries share the prefix of the old ones.
+fn twox_64_concat<K: Decode>(hashed: &[u8]) -> Option<K> {
+    let (hash, key) = (hashed.get(..8)?, hashed.get(8..)?);
+    if twox_64(key)[..] != *hash {
+        return None;
+    }
+    K::decode(&mut &key[..]).ok()
+}
+
+/// Decodes a key hashed with `Blake2_128Concat`, as `twox_64_concat`.
+fn blake2_128_concat<K: Decode>(hashed: &[u8]) -> Option<K> {
+    let (hash, key) = (hashed.get(..16)?, hashed.get(16..)?);
+    if blake2_128(key)[..] != *hash {
+        return None;
+    }
+    K::decode(&mut &key[..]).ok()
+}
+
+/// Records the creation time of the attributes written before attribute metadata existed.
+/// Their creation block is unknown: it is set to zero, so they are never considered fresh.
+pub struct BackfillAttributeMetadata<T>(PhantomData<T>);
+
+impl<T: Config> SteppedMigration for BackfillAttributeMetadata<T> {
+    fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+        let mut attributes = match cursor {
+            Some(cursor) => <AttributeOf<T>>::iter_from(cursor),
+            None => <AttributeOf<T>>::iter(),
+        };
+        let (mut reads, mut writes) = (0u64, 0u64);
+        for _ in 0..limit.max(1) {
+            let (key, attribute) = match attributes.next() {
+                Some(entry) => entry,
+                None => return (None, T::DbWeight::get().reads_writes(reads + 1, writes)),
+            };
+            reads += 2;
+            if !<AttributeMetadataOf<T>>::contains_key(&key) {
+                let created_at = (Zero::zero(), attribute.creation);
+                let metadata = AttributeMetadata { created_at, updated_at: created_at };
+                <AttributeMetadataOf<T>>::insert(&key, metadata);
+                writes += 1;
+            }
+        }
+        let next = Some(attributes.last_raw_key().to_vec());
+        (next, T::DbWeight::get().reads_writes(reads, writes))
+    }
+}
+
+/// Indexes the delegates added before `DelegatesOfType` existed, so that
+/// `revoke_delegates_of_type` revokes them too. Revoked and expired delegates are left out.
+pub struct IndexDelegatesByType<T>(PhantomData<T>);
+
+impl<T: Config> SteppedMigration for IndexDelegatesByType<T> {
+    fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+        let mut delegates = match cursor {
+            Some(cursor) => <DelegateOf<T>>::iter_from(cursor),
+            None => <DelegateOf<T>>::iter(),
+        };
+        let now = <frame_system::Pallet<T>>::block_number();
+        let (mut reads, mut writes) = (0u64, 0u64);
+        for _ in 0..limit.max(1) {
+            let (identity, (delegate_type, delegate), record) = match delegates.next() {
+                Some(entry) => entry,
+                None => return (None, T::DbWeight::get().reads_writes(reads + 1, writes)),
+            };
+            reads += 1;
+            if record.validity > now {
+                <DelegatesOfType<T>>::insert((identity, delegate_type), delegate, ());
+                writes += 1;
+            }
+        }
+        let next = Some(delegates.last_raw_key().to_vec());
+        (next, T::DbWeight::get().reads_writes(reads, writes))
+    }
+}
+
+/// Records the digests of the attributes written before `AttributeDigests` existed,
+/// which `valid_attribute` reads.
+pub struct BackfillAttributeDigests<T>(PhantomData<T>);
+
+impl<T: Config> SteppedMigration for BackfillAttributeDigests<T> {
+    fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+        let mut attributes = match cursor {
+            Some(cursor) => <AttributeOf<T>>::iter_from(cursor),
+            None => <AttributeOf<T>>::iter(),
+        };
+        let (mut reads, mut writes) = (0u64, 0u64);
+        for _ in 0..limit.max(1) {
+            let ((identity, _), attribute) = match attributes.next() {
+                Some(entry) => entry,
+                None => return (None, T::DbWeight::get().reads_writes(reads + 1, writes)),
+            };
+            reads += 2;
+            // Only the attribute of the current nonce of its name is resolved.
+            let nonce = <AttributeNonce<T>>::get(&identity, &attribute.name).saturating_sub(1);
+            if nonce == attribute.nonce {
+                let digest = (Pallet::<T>::key_hash(&attribute.value), attribute.validity);
+                <AttributeDigests<T>>::insert(&identity, Pallet::<T>::key_hash(&attribute.name), digest);
+                writes += 1;
+            }
+        }
+        let next = Some(attributes.last_raw_key().to_vec());
+        (next, T::DbWeight::get().reads_writes(reads, writes))
+    }
+}
+
+/// Truncates the vector stored at `key` to `bound` items, returning the removed ones.
+/// Reads the raw vector, which does not decode as a bounded vector once over its bound.
+fn truncate<V: Decode + Encode>(key: &[u8], bound: u32) -> Vec<V> {
+    let mut values: Vec<V> = unhashed::get(key).unwrap_or_default();
+    if values.len() <= bound as usize {
+        return Vec::new();
+    }
+    let removed = values.split_off(bound as usize);
+    unhashed::put(key, &values);
+    removed
+}
+
+/// Trims the watchers of each identity to `MaxWatchers`, e.g. after the bound was lowered,
+/// unreserving the deposits of the last watchers.
+pub struct TrimWatchers<T>(PhantomData<T>);
+
+impl<T: Config> SteppedMigration for TrimWatchers<T> {
+    fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+        let mut identities = match cursor {
+            Some(cursor) => <Watchers<T>>::iter_keys_from(cursor),
+            None => <Watchers<T>>::iter_keys(),
+        };
+        let (mut reads, mut writes) = (0u64, 0u64);
+        for _ in 0..limit.max(1) {
+            let identity = match identities.next() {
+                Some(identity) => identity,
+                None => return (None, T::DbWeight::get().reads_writes(reads + 1, writes)),
+            };
+            reads += 1;
+            let key = <Watchers<T>>::hashed_key_for(&identity);
+            let removed: Vec<T::AccountId> = truncate(&key, T::MaxWatchers::get());
+            for watcher in removed {
+                if let Some(deposit) = <WatcherDeposits<T>>::take(&identity, &watcher) {
+                    T::Currency::unreserve(&watcher, deposit);
+                }
+                Pallet::<T>::deposit_event(Event::WatcherRemoved(identity.clone(), watcher));
+                reads += 1;
+                writes += 2;
+            }
+            writes += 1;
+        }
+        let next = Some(identities.last_raw_key().to_vec());
+        (next, T::DbWeight::get().reads_writes(reads, writes))
+    }
+}
+
+/// Trims the alerts queued for each block to `MaxExpiryAlerts`, emitting `ExpiryAlertDropped`
+/// for the last ones.
+pub struct TrimExpiryAlerts<T>(PhantomData<T>);
+
+impl<T: Config> SteppedMigration for TrimExpiryAlerts<T> {
+    fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+        let mut blocks = match cursor {
+            Some(cursor) => <ExpiryAlerts<T>>::iter_keys_from(cursor),
+            None => <ExpiryAlerts<T>>::iter_keys(),
+        };
+        let mut migrated = 0u64;
+        for _ in 0..limit.max(1) {
+            let block = match blocks.next() {
+                Some(block) => block,
+                None => return (None, T::DbWeight::get().reads_writes(migrated + 1, migrated)),
+            };
+            let key = <ExpiryAlerts<T>>::hashed_key_for(block);
+            let removed: Vec<(T::AccountId, WatchedItem<T::AccountId>, T::BlockNumber)> =
+                truncate(&key, T::MaxExpiryAlerts::get());
+            for (identity, item, validity) in removed {
+                Pallet::<T>::deposit_event(Event::ExpiryAlertDropped(identity, item, validity));
+            }
+            migrated += 1;
+        }
+        let next = Some(blocks.last_raw_key().to_vec());
+        (next, T::DbWeight::get().reads_writes(migrated, migrated))
+    }
+}
+
+/// Trims the attributes queued for each block in `DependencyExpiries` to `MaxExpiryAlerts`,
+/// as `schedule_dependency_expiry` drops the attributes queued beyond it.
+pub struct TrimDependencyExpiries<T>(PhantomData<T>);
+
+impl<T: Config> SteppedMigration for TrimDependencyExpiries<T> {
+    fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+        let mut blocks = match cursor {
+            Some(cursor) => <DependencyExpiries<T>>::iter_keys_from(cursor),
+            None => <DependencyExpiries<T>>::iter_keys(),
+        };
+        let mut migrated = 0u64;
+        for _ in 0..limit.max(1) {
+            let block = match blocks.next() {
+                Some(block) => block,
+                None => return (None, T::DbWeight::get().reads_writes(migrated + 1, migrated)),
+            };
+            let key = <DependencyExpiries<T>>::hashed_key_for(block);
+            let _: Vec<(T::AccountId, Vec<u8>)> = truncate(&key, T::MaxExpiryAlerts::get());
+            migrated += 1;
+        }
+        let next = Some(blocks.last_raw_key().to_vec());
+        (next, T::DbWeight::get().reads_writes(migrated, migrated))
+    }
+}
+
+/// Trims the values bounded in storage version 5, which stored them as unbounded vectors.
+pub type TrimBoundedStorage<T> = (TrimWatchers<T>, (TrimExpiryAlerts<T>, TrimDependencyExpiries<T>));
+
+/// Removes the presentation nonces registered before storage version 6 which expired, and
+/// queues the removal of the others. Nonces beyond `MaxNonceExpiries` per block stay until
+/// consumed.
+pub struct PrunePresentationNonces<T>(PhantomData<T>);
+
+impl<T: Config> SteppedMigration for PrunePresentationNonces<T> {
+    fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+        if !older_than::<T>(6) {
+            return (None, T::DbWeight::get().reads(1));
+        }
+        let mut nonces = match cursor {
+            Some(cursor) => <PresentationNonces<T>>::iter_from(cursor),
+            None => <PresentationNonces<T>>::iter(),
+        };
+        let now = <frame_system::Pallet<T>>::block_number();
+        let mut migrated = 0u64;
+        for _ in 0..limit.max(1) {
+            let (verifier, nonce, expiry) = match nonces.next() {
+                Some(entry) => entry,
+                None => return (None, T::DbWeight::get().reads_writes(migrated + 1, migrated)),
+            };
+            if expiry < now {
+                <PresentationNonces<T>>::remove(&verifier, nonce);
+            } else {
+                <PresentationNonceExpiries<T>>::mutate(expiry, |expiries| {
+                    let _ = expiries.try_push((verifier, nonce));
+                });
+            }
+            migrated += 1;
+        }
+        let next = Some(nonces.last_raw_key().to_vec());
+        (next, T::DbWeight::get().reads_writes(2 * migrated, migrated))
+    }
+}
+
+/// Counts the rescuers with vouches of each identity in `RescuerCount`, introduced in storage
+/// version 7, so that clearing the vouches of an identity is bounded by its count.
+pub struct CountRescuers<T>(PhantomData<T>);
+
+impl<T: Config> SteppedMigration for CountRescuers<T> {
+    fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+        if !older_than::<T>(7) {
+            return (None, T::DbWeight::get().reads(1));
+        }
+        let mut rescuers = match cursor {
+            Some(cursor) => <RecoveryVouches<T>>::iter_keys_from(cursor),
+            None => <RecoveryVouches<T>>::iter_keys(),
+        };
+        let mut migrated = 0u64;
+        for _ in 0..limit.max(1) {
+            let (identity, _) = match rescuers.next() {
+                Some(key) => key,
+                None => return (None, T::DbWeight::get().reads_writes(migrated + 1, migrated)),
+            };
+            <RescuerCount<T>>::mutate(identity, |count| *count = count.saturating_add(1));
+            migrated += 1;
+        }
+        let next = Some(rescuers.last_raw_key().to_vec());
+        (next, T::DbWeight::get().reads_writes(2 * migrated, migrated))
+    }
+}
+
+/// Translation from storage version 0, the layout of the original release.
+pub mod v1 {
+    use super::*;
+
+    /// `Attribute` of the original release, without `kind`.
+    #[derive(Encode, Decode)]
+    pub struct OldAttribute<BlockNumber, Moment> {
+        pub name: Vec<u8>,
+        pub value: Vec<u8>,
+        pub validity: BlockNumber,
+        pub creation: Moment,
+        pub nonce: u64,
+    }
+
+    /// Translates the `Option<BlockNumber>` validity `DelegateOf` stored to a `Delegate`. Their
+    /// key type is unknown: it is set to `Sr25519`, the scheme of the accounts of the original
+    /// release.
+    pub struct TranslateDelegates<T>(PhantomData<T>);
+
+    impl<T: Config> SteppedMigration for TranslateDelegates<T> {
+        fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+            if !older_than::<T>(1) {
+                return (None, T::DbWeight::get().reads(1));
+            }
+            step_raw_keys::<T>(b"DelegateOf", cursor, limit, |key, _| {
+                match unhashed::get::<Option<T::BlockNumber>>(key) {
+                    Some(validity) => {
+                        let validity = validity.unwrap_or_else(Zero::zero);
+                        unhashed::put(key, &Delegate { validity, key_type: KeyType::Sr25519 });
+                        1
+                    }
+                    None => 0,
+                }
+            })
+        }
+    }
+
+    /// Translates the attributes `AttributeOf` stored without `kind`: they are `Plain`.
+    pub struct TranslateAttributes<T>(PhantomData<T>);
+
+    impl<T: Config> SteppedMigration for TranslateAttributes<T> {
+        fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+            if !older_than::<T>(1) {
+                return (None, T::DbWeight::get().reads(1));
+            }
+            step_raw_keys::<T>(b"AttributeOf", cursor, limit, |key, _| {
+                match unhashed::get::<OldAttribute<T::BlockNumber, T::Moment>>(key) {
+                    Some(old) => {
+                        let attribute = Attribute {
+                            name: old.name,
+                            value: old.value,
+                            validity: old.validity,
+                            creation: old.creation,
+                            nonce: old.nonce,
+                            kind: AttributeKind::Plain,
+                        };
+                        unhashed::put(key, &attribute);
+                        1
+                    }
+                    None => 0,
+                }
+            })
+        }
+    }
+
+    /// Re-keys `AttributeNonce`, a map of `Twox64Concat` `(identity, name)` keys, by `identity`
+    /// then `name` so the nonces of an identity are iterable.
+    pub struct RekeyNonces<T>(PhantomData<T>);
+
+    impl<T: Config> SteppedMigration for RekeyNonces<T> {
+        fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+            if !older_than::<T>(1) {
+                return (None, T::DbWeight::get().reads(1));
+            }
+            step_raw_keys::<T>(b"AttributeNonce", cursor, limit, |key, suffix| {
+                // The nonces already re-keyed are skipped.
+                let (identity, name) = match twox_64_concat::<(T::AccountId, Vec<u8>)>(suffix) {
+                    Some(old_key) => old_key,
+                    None => return 0,
+                };
+                let nonce: u64 = unhashed::take(key).unwrap_or_default();
+                <AttributeNonce<T>>::insert(identity, name, nonce);
+                2
+            })
+        }
+    }
+
+    /// Translates the values of the original layout, in sequence.
+    pub type TranslateOriginalLayout<T> = (TranslateDelegates<T>, (TranslateAttributes<T>, RekeyNonces<T>));
+}
+
+/// Re-keying of storage version 3.
+pub mod v4 {
+    use super::*;
+
+    /// Re-keys `DelegateOf`, a map of `(identity, delegate_type, delegate)` keys, by `identity`
+    /// then `(delegate_type, delegate)` so the delegates of an identity are iterable.
+    pub struct RekeyDelegates<T>(PhantomData<T>);
+
+    impl<T: Config> SteppedMigration for RekeyDelegates<T> {
+        fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+            if !older_than::<T>(4) {
+                return (None, T::DbWeight::get().reads(1));
+            }
+            step_raw_keys::<T>(b"DelegateOf", cursor, limit, |key, suffix| {
+                // The delegates already re-keyed are skipped.
+                let old_key = blake2_128_concat::<(T::AccountId, Vec<u8>, T::AccountId)>(suffix);
+                let (identity, delegate_type, delegate) = match old_key {
+                    Some(old_key) => old_key,
+                    None => return 0,
+                };
+                match unhashed::take::<Delegate<T::BlockNumber>>(key) {
+                    Some(record) => {
+                        <DelegateOf<T>>::insert(identity, (delegate_type, delegate), record);
+                        2
+                    }
+                    None => 0,
+                }
+            })
+        }
+    }
+
+    /// Indexes the `OwnerOf` entries by owner in `OwnedIdentities`.
+    pub struct IndexOwnedIdentities<T>(PhantomData<T>);
+
+    impl<T: Config> SteppedMigration for IndexOwnedIdentities<T> {
+        fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+            if !older_than::<T>(4) {
+                return (None, T::DbWeight::get().reads(1));
+            }
+            let mut owners = match cursor {
+                Some(cursor) => <OwnerOf<T>>::iter_from(cursor),
+                None => <OwnerOf<T>>::iter(),
+            };
+            let mut migrated = 0u64;
+            for _ in 0..limit.max(1) {
+                let (identity, owner) = match owners.next() {
+                    Some(entry) => entry,
+                    None => return (None, T::DbWeight::get().reads_writes(migrated + 1, migrated)),
+                };
+                <OwnedIdentities<T>>::insert(owner, identity, ());
+                migrated += 1;
+            }
+            let next = Some(owners.last_raw_key().to_vec());
+            (next, T::DbWeight::get().reads_writes(migrated, migrated))
+        }
+    }
+
+    /// Re-keys `Anchors`, a map of `hash` keys to `(identity, block, time)`, by `identity` then
+    /// `hash` so an identity can not prevent others from anchoring a hash.
+    pub struct RekeyAnchors<T>(PhantomData<T>);
+
+    impl<T: Config> SteppedMigration for RekeyAnchors<T> {
+        fn step(cursor: Option<Vec<u8>>, limit: u32) -> (Option<Vec<u8>>, Weight) {
+            if !older_than::<T>(4) {
+                return (None, T::DbWeight::get().reads(1));
+            }
+            step_raw_keys::<T>(b"Anchors", cursor, limit, |key, suffix| {
+                // The anchors already re-keyed are skipped.
+                let hash = match blake2_128_concat::<[u8; 32]>(suffix) {
+                    Some(hash) => hash,
+                    None => return 0,
+                };
+                match unhashed::take::<(T::AccountId, T::BlockNumber, T::Moment)>(key) {
+                    Some((identity, block, moment)) => {
+                        <Anchors<T>>::insert(identity, hash, (block, moment));
+                        2
+                    }
+                    None => 0,
+                }
+            })
+        }
+    }
+
+    /// Re-keys the values of storage version 3, in sequence.
+    pub type RekeyByIdentity<T> = (RekeyDelegates<T>, (IndexOwnedIdentities<T>, RekeyAnchors<T>));
+}
@@ -1,11 +1,18 @@
 use crate as pallet_did;
-use frame_support::traits::{ConstU16, ConstU64,OnTimestampSet};
+use frame_support::{
+    parameter_types,
+    traits::{ConstU16, ConstU32, ConstU64, EnsureOrigin, Get, OnTimestampSet, Randomness},
+    PalletId,
+};
 use frame_system as system;
 use sp_core::{sr25519, Pair, H256};
 use sp_core::sr25519::Signature;
 use sp_runtime::{
     testing::Header,
-    traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentifyAccount, IdentityLookup, Verify},
+    traits::{
+        AccountIdConversion, BlakeTwo256, Extrinsic as ExtrinsicT, IdentifyAccount, IdentityLookup,
+        Verify,
+    },
 };
 use sp_std::cell::RefCell;
 
@@ -25,6 +32,8 @@ frame_support::construct_runtime!(
         System: frame_system,
         DID: pallet_did,
         Timestamp: pallet_timestamp,
+        Balances: pallet_balances,
+        Board: pallet_collective::<Instance1>,
     }
 );
 
@@ -46,7 +55,7 @@ impl system::Config for Test {
     type BlockHashCount = ConstU64<250>;
     type Version = ();
     type PalletInfo = PalletInfo;
-    type AccountData = ();
+    type AccountData = pallet_balances::AccountData<u64>;
     type OnNewAccount = ();
     type OnKilledAccount = ();
     type SystemWeightInfo = ();
@@ -63,7 +72,7 @@ impl frame_system::offchain::SigningTypes for Test {
 }
 
 type Extrinsic = sp_runtime::testing::TestXt<RuntimeCall, ()>;
-type AccountId = <<sp_core::sr25519::Signature as Verify>::Signer as IdentifyAccount>::AccountId;
+pub type AccountId = <<sp_core::sr25519::Signature as Verify>::Signer as IdentifyAccount>::AccountId;
 
 impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Test
 where
@@ -105,12 +114,176 @@ impl pallet_timestamp::Config for Test {
 	type WeightInfo = ();
 }
 
+impl pallet_balances::Config for Test {
+    type Balance = u64;
+    type DustRemoval = ();
+    type RuntimeEvent = RuntimeEvent;
+    type ExistentialDeposit = ConstU64<1>;
+    type AccountStore = System;
+    type WeightInfo = ();
+    type MaxLocks = ConstU32<50>;
+    type MaxReserves = ConstU32<50>;
+    type ReserveIdentifier = [u8; 8];
+}
+
+pub type BoardCollective = pallet_collective::Instance1;
+impl pallet_collective::Config<BoardCollective> for Test {
+    type RuntimeOrigin = RuntimeOrigin;
+    type Proposal = RuntimeCall;
+    type RuntimeEvent = RuntimeEvent;
+    type MotionDuration = ConstU64<5>;
+    type MaxProposals = ConstU32<10>;
+    type MaxMembers = ConstU32<10>;
+    type DefaultVote = pallet_collective::PrimeDefaultVote;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    /// Keyless identity controlled by the root origin.
+    pub TreasuryAccount: AccountId = PalletId(*b"py/trsry").into_account_truncating();
+    /// Keyless identity controlled by a majority of the board.
+    pub BoardAccount: AccountId = PalletId(*b"py/board").into_account_truncating();
+    pub static ExplicitOwnershipOnly: bool = false;
+    pub static EmitMetaTransactionRejections: bool = false;
+    pub static RequireAttestationConsent: bool = false;
+    pub static DormancyPeriod: Option<u64> = None;
+    pub static MaxBytesPerDid: u32 = 4_096;
+    /// `(caller, purpose)` authorized by every contract delegate.
+    pub static ContractPolicy: Vec<(AccountId, Vec<u8>)> = vec![];
+    /// Foreign block and state root known to the chain.
+    pub static ForeignStateRoot: Option<(u32, H256)> = None;
+    /// Latest foreign block known to the chain, if after the one of `ForeignStateRoot`.
+    pub static ForeignLatestBlock: Option<u32> = None;
+    /// The foreign chain runs this pallet under the same name.
+    pub ForeignAttributePrefix: [u8; 32] = {
+        let mut prefix = [0u8; 32];
+        prefix[..16].copy_from_slice(&sp_io::hashing::twox_128(b"DID"));
+        prefix[16..].copy_from_slice(&sp_io::hashing::twox_128(b"AttributeOf"));
+        prefix
+    };
+    pub DelegateValidityDefaults: Vec<(Vec<u8>, u64)> = vec![(b"SessionKey".to_vec(), 14_400)];
+    pub ReservedNamespaces: Vec<Vec<u8>> = vec![b"did/".to_vec(), b"svc/".to_vec(), b"sys/".to_vec()];
+}
+
+pub struct MockForeignStateRoots;
+impl pallet_did::foreign::ForeignStateRoots for MockForeignStateRoots {
+    fn state_root(block: u32) -> Option<H256> {
+        ForeignStateRoot::get().filter(|(known, _)| *known == block).map(|(_, root)| root)
+    }
+
+    fn latest_block() -> Option<u32> {
+        ForeignLatestBlock::get().or_else(|| ForeignStateRoot::get().map(|(block, _)| block))
+    }
+}
+
+pub struct MockContractAuthorizer;
+impl pallet_did::contract::ContractAuthorizer<AccountId> for MockContractAuthorizer {
+    fn is_authorized(_contract: &AccountId, _identity: &AccountId, caller: &AccountId, purpose: &[u8]) -> bool {
+        ContractPolicy::get().iter().any(|(c, p)| c == caller && p.as_slice() == purpose)
+    }
+}
+
+/// Deterministic randomness, the hash of the subject.
+pub struct MockRandomness;
+impl Randomness<H256, u64> for MockRandomness {
+    fn random(subject: &[u8]) -> (H256, u64) {
+        (H256(sp_io::hashing::blake2_256(subject)), 0)
+    }
+}
+
+/// Root acts as the treasury identity, a board majority acts as the board identity.
+pub struct EnsureDidController;
+impl EnsureOrigin<RuntimeOrigin> for EnsureDidController {
+    type Success = AccountId;
+
+    fn try_origin(o: RuntimeOrigin) -> Result<AccountId, RuntimeOrigin> {
+        let o = match system::EnsureRoot::<AccountId>::try_origin(o) {
+            Ok(()) => return Ok(TreasuryAccount::get()),
+            Err(o) => o,
+        };
+        pallet_collective::EnsureProportionMoreThan::<AccountId, BoardCollective, 1, 2>::try_origin(o)
+            .map(|()| BoardAccount::get())
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn try_successful_origin() -> Result<RuntimeOrigin, ()> {
+        Ok(RuntimeOrigin::root())
+    }
+}
+
 impl pallet_did::Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type Public = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
     type Signature = sr25519::Signature;
+    type KeyHasher = BlakeTwo256;
     type Moment = Moment;
     type Timestamp = Timestamp;
+    type ControllerOrigin = EnsureDidController;
+    type DefaultAttributeValidity = ConstU64<10_000>;
+    type MaxAttributeValidity = ConstU64<100_000>;
+    type DelegateValidityDefaults = DelegateValidityDefaults;
+    type DefaultDelegateValidity = ConstU64<100_000>;
+    type MaxDelegateRenewals = ConstU32<16>;
+    type ExplicitOwnershipOnly = ExplicitOwnershipOnly;
+    type ReservedNamespaces = ReservedNamespaces;
+    type Currency = Balances;
+    type ProfileDeposit = ConstU64<10>;
+    type NamespaceDeposit = ConstU64<50>;
+    type MaxDisplayNameLength = ConstU32<32>;
+    type HandleDeposit = ConstU64<20>;
+    type MaxHandleLength = ConstU32<16>;
+    type AccessEscrowPeriod = ConstU64<20>;
+    type ForeignStateRoots = MockForeignStateRoots;
+    type ForeignAttributePrefix = ForeignAttributePrefix;
+    type ContractAuthorizer = MockContractAuthorizer;
+    type WeightInfo = ();
+    type EmitMetaTransactionRejections = EmitMetaTransactionRejections;
+    type RequireAttestationConsent = RequireAttestationConsent;
+    type FeelessFirstWrites = ConstU32<2>;
+    type MaxFeelessWriteBytes = ConstU32<128>;
+    type Migration = (
+        (
+            pallet_did::migrations::v1::TranslateOriginalLayout<Test>,
+            pallet_did::migrations::v4::RekeyByIdentity<Test>,
+        ),
+        (
+            (
+                pallet_did::migrations::BackfillAttributeMetadata<Test>,
+                pallet_did::migrations::IndexDelegatesByType<Test>,
+            ),
+            (
+                pallet_did::migrations::BackfillAttributeDigests<Test>,
+                (
+                    pallet_did::migrations::TrimBoundedStorage<Test>,
+                    (
+                        pallet_did::migrations::PrunePresentationNonces<Test>,
+                        pallet_did::migrations::CountRescuers<Test>,
+                    ),
+                ),
+            ),
+        ),
+    );
+    type MigrationStepSize = ConstU32<2>;
+    type DormancyPeriod = DormancyPeriod;
+    type ReapGracePeriod = ConstU64<10>;
+    type ReapBounty = ConstU64<15>;
+    type MaxWatchers = ConstU32<2>;
+    type ExpiryNotice = ConstU64<10>;
+    type MaxExpiryAlerts = ConstU32<16>;
+    type MaxDependents = ConstU32<2>;
+    type MaxBytesPerDid = MaxBytesPerDid;
+    type Randomness = MockRandomness;
+    type ChallengeTtl = ConstU64<10>;
+    type MaxRecoveryVouches = ConstU32<2>;
+    type AttestationDeposit = ConstU64<5>;
+    type MaxPendingAttestations = ConstU32<2>;
+    type CredentialDeposit = ConstU64<10>;
+    type MaxCredentialsPerSubject = ConstU32<2>;
+    type MaxForeignStateRootAge = ConstU32<10>;
+    type WatcherDeposit = ConstU64<5>;
+    type MaxNonceExpiries = ConstU32<4>;
+    type MaxRescuers = ConstU32<2>;
+    type AdminOrigin = system::EnsureRoot<AccountId>;
 }
 
 // Build genesis storage according to the mock runtime.
@@ -121,6 +294,15 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
         .into()
 }
 
+// Build genesis storage with a keystore, used by the benchmarks to sign.
+#[cfg(all(test, feature = "runtime-benchmarks"))]
+pub fn new_benchmark_ext() -> sp_io::TestExternalities {
+    use sp_keystore::{testing::KeyStore, KeystoreExt};
+    let mut ext = new_test_ext();
+    ext.register_extension(KeystoreExt(std::sync::Arc::new(KeyStore::new())));
+    ext
+}
+
 pub fn account_pair(s: &str) -> sr25519::Pair {
     sr25519::Pair::from_string(&format!("//{}", s), None).expect("static values are valid; qed")
 }
@@ -0,0 +1,117 @@
+//! Multicodec and multibase (base58-btc) encoding of public keys,
+//! as used by the `publicKeyMultibase` property of DID Documents.
+
+use crate::types::KeyType;
+use sp_std::vec::Vec;
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Multibase prefix for base58-btc.
+pub const MULTIBASE_BASE58_BTC: u8 = b'z';
+
+/// Unsigned varint multicodec prefix of a public key type.
+pub fn multicodec_prefix(key_type: KeyType) -> [u8; 2] {
+    match key_type {
+        KeyType::Sr25519 => [0xef, 0x01],
+        KeyType::Ed25519 => [0xed, 0x01],
+        KeyType::Ecdsa => [0xe7, 0x01],
+        KeyType::X25519 => [0xec, 0x01],
+    }
+}
+
+/// Returns the key type matching a multicodec prefix.
+pub fn key_type_of_prefix(prefix: &[u8]) -> Option<KeyType> {
+    [KeyType::Sr25519, KeyType::Ed25519, KeyType::Ecdsa, KeyType::X25519]
+        .into_iter()
+        .find(|key_type| multicodec_prefix(*key_type) == prefix)
+}
+
+/// Encodes a public key as `z<base58-btc(multicodec-prefix ++ key)>`.
+pub fn public_key_multibase(key_type: KeyType, public_key: &[u8]) -> Vec<u8> {
+    let mut prefixed = multicodec_prefix(key_type).to_vec();
+    prefixed.extend_from_slice(public_key);
+
+    let mut multibase = Vec::with_capacity(prefixed.len() * 138 / 100 + 2);
+    multibase.push(MULTIBASE_BASE58_BTC);
+    multibase.extend(base58_encode(&prefixed));
+    multibase
+}
+
+/// Length of the raw public keys of a key type.
+pub fn public_key_len(key_type: KeyType) -> usize {
+    match key_type {
+        KeyType::Ecdsa => 33,
+        _ => 32,
+    }
+}
+
+/// Encodes an encoded account as the `publicKeyMultibase` of a key type, `None` if the account
+/// is not the public key itself, e.g. the hash of an ecdsa key in a `MultiSigner` account.
+pub fn account_key_multibase(key_type: KeyType, account: &[u8]) -> Option<Vec<u8>> {
+    (account.len() == public_key_len(key_type)).then(|| public_key_multibase(key_type, account))
+}
+
+/// Decodes a `publicKeyMultibase` value into its key type and raw public key.
+pub fn decode_public_key_multibase(multibase: &[u8]) -> Option<(KeyType, Vec<u8>)> {
+    let (base, encoded) = multibase.split_first()?;
+    if *base != MULTIBASE_BASE58_BTC {
+        return None;
+    }
+    let decoded = base58_decode(encoded)?;
+    if decoded.len() < 2 {
+        return None;
+    }
+    let key_type = key_type_of_prefix(&decoded[..2])?;
+    Some((key_type, decoded[2..].to_vec()))
+}
+
+/// Base58 encoding using the bitcoin alphabet.
+pub fn base58_encode(input: &[u8]) -> Vec<u8> {
+    let zeros = input.iter().take_while(|b| **b == 0).count();
+    // Little-endian base58 digits.
+    let mut digits: Vec<u8> = Vec::with_capacity(input.len() * 138 / 100 + 1);
+
+    for byte in &input[zeros..] {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut encoded = Vec::with_capacity(zeros + digits.len());
+    encoded.resize(zeros, BASE58_ALPHABET[0]);
+    encoded.extend(digits.iter().rev().map(|d| BASE58_ALPHABET[*d as usize]));
+    encoded
+}
+
+/// Base58 decoding using the bitcoin alphabet.
+/// Returns `None` if the input contains characters outside the alphabet.
+pub fn base58_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let zeros = input.iter().take_while(|c| **c == BASE58_ALPHABET[0]).count();
+    // Little-endian bytes.
+    let mut bytes: Vec<u8> = Vec::with_capacity(input.len());
+
+    for c in &input[zeros..] {
+        let mut carry = BASE58_ALPHABET.iter().position(|a| a == c)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut decoded = Vec::with_capacity(zeros + bytes.len());
+    decoded.resize(zeros, 0u8);
+    decoded.extend(bytes.iter().rev());
+    Some(decoded)
+}
@@ -0,0 +1,116 @@
+//! EVM precompile giving Solidity contracts of Frontier-based chains read access to the DIDs,
+//! with the `frontier` feature.
+//!
+//! ```solidity
+//! interface Did {
+//!     function isOwner(address identity, address account) external view returns (bool);
+//!     function validDelegate(address identity, bytes32 delegateType, address delegate)
+//!         external view returns (bool);
+//!     function attribute(address identity, bytes32 name) external view returns (bytes memory);
+//! }
+//! ```
+//!
+//! Addresses are mapped to accounts by the `AddressMapping` of `pallet-evm`. Delegate types and
+//! attribute names are right padded with zeros to 32 bytes, and the trailing zeros are stripped
+//! on decoding: names of more than 32 bytes, or ending with a zero byte, can not be queried.
+//! `attribute` returns empty bytes if the attribute is unknown or expired.
+//!
+//! Queries are charged their weight, and `extension::WEIGHT_PER_BYTE` per byte of their output,
+//! converted to gas. Their input is paid for as the calldata of the EVM transaction.
+
+use crate::{
+    extension::{self, DidAnswer, DidQuery},
+    pallet::Config,
+};
+use fp_evm::{
+    ExitRevert, ExitSucceed, Precompile, PrecompileFailure, PrecompileHandle, PrecompileOutput, PrecompileResult,
+};
+use pallet_evm::{AddressMapping, GasWeightMapping};
+use sp_core::{H160, U256};
+use sp_io::hashing::keccak_256;
+use sp_std::{marker::PhantomData, vec::Vec};
+
+/// DID precompile of the runtime `T`.
+pub struct DidPrecompile<T>(PhantomData<T>);
+
+impl<T: pallet_evm::Config + Config> Precompile for DidPrecompile<T> {
+    fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+        let query = decode_call(handle.input(), T::AddressMapping::into_account_id)
+            .ok_or_else(|| revert(b"Invalid DID query"))?;
+        let weight = extension::weight::<T>(&query);
+        handle.record_cost(<T as pallet_evm::Config>::GasWeightMapping::weight_to_gas(weight))?;
+
+        let output = match extension::answer::<T>(&query) {
+            DidAnswer::Bool(answer) => encode_word(U256::from(answer as u8)),
+            DidAnswer::Value(value) => encode_bytes(&value.unwrap_or_default()),
+        };
+        let weight = extension::bytes_weight(output.len());
+        handle.record_cost(<T as pallet_evm::Config>::GasWeightMapping::weight_to_gas(weight))?;
+        Ok(PrecompileOutput { exit_status: ExitSucceed::Returned, output })
+    }
+}
+
+fn revert(reason: &[u8]) -> PrecompileFailure {
+    PrecompileFailure::Revert { exit_status: ExitRevert::Reverted, output: reason.to_vec() }
+}
+
+/// Selector of a Solidity function signature, e.g. `isOwner(address,address)`.
+pub(crate) fn selector(signature: &[u8]) -> [u8; 4] {
+    let hash = keccak_256(signature);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Decodes the `index`-th 32 bytes argument of a call.
+fn word(args: &[u8], index: usize) -> Option<&[u8]> {
+    args.get(index * 32..(index + 1) * 32)
+}
+
+/// Decodes the `index`-th `address` argument of a call.
+fn address(args: &[u8], index: usize) -> Option<H160> {
+    let word = word(args, index)?;
+    Some(H160::from_slice(&word[12..]))
+}
+
+/// Decodes the `index`-th `bytes32` argument of a call without its zero padding. The trailing
+/// zeros of the name are stripped with the padding, as both are indistinguishable.
+fn name(args: &[u8], index: usize) -> Option<Vec<u8>> {
+    let word = word(args, index)?;
+    let len = word.iter().rposition(|b| *b != 0).map_or(0, |last| last + 1);
+    Some(word[..len].to_vec())
+}
+
+/// Decodes the query of an ABI encoded call, mapping its addresses to accounts with `account`.
+/// `None` if the selector is unknown or the arguments too short.
+pub(crate) fn decode_call<AccountId>(
+    input: &[u8],
+    account: impl Fn(H160) -> AccountId,
+) -> Option<DidQuery<AccountId>> {
+    let (call, args) = (input.get(..4)?, input.get(4..)?);
+    let account_at = |index| address(args, index).map(&account);
+    if call == selector(b"isOwner(address,address)") {
+        Some(DidQuery::IsOwner(account_at(0)?, account_at(1)?))
+    } else if call == selector(b"validDelegate(address,bytes32,address)") {
+        Some(DidQuery::ValidDelegate(account_at(0)?, name(args, 1)?, account_at(2)?))
+    } else if call == selector(b"attribute(address,bytes32)") {
+        Some(DidQuery::AttributeValue(account_at(0)?, name(args, 1)?))
+    } else {
+        None
+    }
+}
+
+/// ABI encoding of a `bytes` return value: its offset, its length, then the value right
+/// padded with zeros to a multiple of 32 bytes.
+pub(crate) fn encode_bytes(value: &[u8]) -> Vec<u8> {
+    let mut output = encode_word(U256::from(32));
+    output.extend(encode_word(U256::from(value.len())));
+    output.extend_from_slice(value);
+    output.resize(64 + (value.len() + 31) / 32 * 32, 0);
+    output
+}
+
+/// ABI encoding of a 32 bytes word.
+pub(crate) fn encode_word(value: U256) -> Vec<u8> {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word.to_vec()
+}
@@ -0,0 +1,13 @@
+//! Account recovery authorized by the guardians of a DID.
+//!
+//! Guardians are `RecoveryGuardian` delegates of an identity. Once a threshold of them
+//! vouched for a rescuer, runtimes can let the rescuer recover the accounts of the identity
+//! in other pallets, e.g. through `pallet_recovery::set_recovered`.
+
+/// Authorizes the recovery of lost accounts by rescuers.
+pub trait RecoveryAuthority<AccountId> {
+    /// Returns `true` if `rescuer` is authorized to recover the account `lost`.
+    fn can_recover(lost: &AccountId, rescuer: &AccountId) -> bool;
+    /// Called once the recovery was performed, so the authorization can not be reused.
+    fn recovered(lost: &AccountId, rescuer: &AccountId);
+}
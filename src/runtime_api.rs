@@ -0,0 +1,65 @@
+//! Runtime API of the DID pallet, for resolvers and relayer services.
+
+use crate::{
+    document::DidDocument,
+    types::{
+        ActivityStatus, AttestationStatus, AttributeMetadata, AttributeTransaction, Delegate, Page, ReadQuery,
+    },
+};
+use codec::Codec;
+use frame_support::{dispatch::PostDispatchInfo, weights::Weight};
+use sp_runtime::DispatchError;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    pub trait DidApi<AccountId, BlockNumber, Call, Balance, Signature, Moment>
+    where
+        AccountId: Codec,
+        BlockNumber: Codec,
+        Moment: Codec,
+        Call: Codec,
+        Balance: Codec,
+        Signature: Codec,
+    {
+        /// All the `(name, nonce)` attribute nonces of an identity.
+        fn nonces_of(identity: AccountId) -> Vec<(Vec<u8>, u64)>;
+        /// Page of the `(name, value, validity)` current attributes of an identity, from the
+        /// `cursor` of the previous page.
+        fn attributes_of(identity: AccountId, cursor: Option<Vec<u8>>, max: u32) -> Page<(Vec<u8>, Vec<u8>, BlockNumber)>;
+        /// Page of the `(delegate_type, delegate, record)` delegates of an identity.
+        fn delegates_of(identity: AccountId, cursor: Option<Vec<u8>>, max: u32) -> Page<(Vec<u8>, AccountId, Delegate<BlockNumber>)>;
+        /// Page of the identities explicitly owned by an account.
+        fn identities_of_owner(owner: AccountId, cursor: Option<Vec<u8>>, max: u32) -> Page<AccountId>;
+        /// Nonce to sign in the next off-chain `AttributeTransaction` of an identity.
+        fn next_meta_nonce(identity: AccountId) -> u64;
+        /// Outcome of `execute` on an off-chain transaction, without writing state.
+        fn dry_run_execute(transaction: AttributeTransaction<Signature, AccountId>) -> Result<PostDispatchInfo, DispatchError>;
+        /// `(delegate_type, delegate, last_seen)` valid delegates of an identity
+        /// inactive for more than `inactive_for` blocks.
+        fn inactive_delegates(identity: AccountId, inactive_for: BlockNumber) -> Vec<(Vec<u8>, AccountId, BlockNumber)>;
+        /// DID Document of an identity, in its compact SCALE encoding.
+        fn did_document(identity: AccountId) -> DidDocument;
+        /// Blocks and times the current attribute of an identity named `name` was created and
+        /// last updated at.
+        fn attribute_metadata(identity: AccountId, name: Vec<u8>) -> Option<AttributeMetadata<BlockNumber, Moment>>;
+        /// Status of the current attribute of an identity named `name`, `None` if unknown.
+        fn attestation_status(identity: AccountId, name: Vec<u8>) -> Option<AttestationStatus>;
+        /// Whether each `(identity, name, value)` attribute claim is valid, in order.
+        fn verify_claims_batch(claims: Vec<(AccountId, Vec<u8>, Vec<u8>)>) -> Vec<bool>;
+        /// Rolling hash of the updates of an identity, changed by every mutation of its DID.
+        fn document_hash(identity: AccountId) -> [u8; 32];
+        /// Identity a readable handle refers to, if claimed.
+        fn identity_by_handle(handle: Vec<u8>) -> Option<AccountId>;
+        /// `(id, status)` of the credentials issued on chain to a subject.
+        fn credentials_of(subject: AccountId) -> Vec<([u8; 32], AttestationStatus)>;
+        /// Whether an identity is active, inactive or dormant under the dormancy policy.
+        fn activity_status(identity: AccountId) -> ActivityStatus<BlockNumber>;
+        /// Stable `(code, name)` of a dispatch error of the pallet, `None` for other errors.
+        fn error_code(error: DispatchError) -> Option<(u16, Vec<u8>)>;
+        /// `(weight, deposit)` of a call of the pallet signed by `who`, given the current state.
+        /// The weight is the one the call declares, not benchmarked.
+        fn operation_cost(who: AccountId, call: Call) -> (Weight, Balance);
+        /// Cost of a read query, as estimated by the `WeightInfo` of the runtime.
+        fn query_cost(query: ReadQuery) -> Weight;
+    }
+}
@@ -0,0 +1,112 @@
+//! Helpers to test pallets consuming the `DidProvider` trait against realistic DID state.
+//!
+//! Enabled by the `test-utils` feature, along with the `mock` runtime of the pallet tests.
+//! `DidBuilder` writes an identity through the pallet calls, so storage, events and indexes
+//! are the ones of a live chain, and `sign_attribute_tx` signs off-chain attribute
+//! transactions for the `execute` call.
+
+use crate::{
+    pallet::{Config, Pallet},
+    types::{AttributeTransaction, KeyType, Validity},
+};
+use frame_support::dispatch::DispatchResult;
+use frame_system::{pallet_prelude::OriginFor, RawOrigin};
+use sp_core::{sr25519, Pair};
+use sp_std::vec::Vec;
+
+type DelegateOf<T> = (
+    <T as frame_system::Config>::AccountId,
+    Vec<u8>,
+    KeyType,
+    Option<Validity<<T as frame_system::Config>::BlockNumber>>,
+);
+type AttributeOf<T> = (Vec<u8>, Vec<u8>, Option<Validity<<T as frame_system::Config>::BlockNumber>>);
+
+/// Creates an identity with its delegates, attributes and owner in one call.
+pub struct DidBuilder<T: Config> {
+    identity: T::AccountId,
+    owner: Option<T::AccountId>,
+    delegates: Vec<DelegateOf<T>>,
+    attributes: Vec<AttributeOf<T>>,
+}
+
+impl<T: Config> DidBuilder<T> {
+    pub fn new(identity: T::AccountId) -> Self {
+        DidBuilder { identity, owner: None, delegates: Vec::new(), attributes: Vec::new() }
+    }
+
+    /// Transfers the ownership of the identity once built.
+    pub fn owner(mut self, owner: T::AccountId) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    pub fn delegate(
+        mut self,
+        delegate: T::AccountId,
+        delegate_type: &[u8],
+        key_type: KeyType,
+        valid_for: Option<Validity<T::BlockNumber>>,
+    ) -> Self {
+        self.delegates.push((delegate, delegate_type.to_vec(), key_type, valid_for));
+        self
+    }
+
+    pub fn attribute(
+        mut self,
+        name: &[u8],
+        value: &[u8],
+        valid_for: Option<Validity<T::BlockNumber>>,
+    ) -> Self {
+        self.attributes.push((name.to_vec(), value.to_vec(), valid_for));
+        self
+    }
+
+    /// Dispatches the calls of the identity at the current block, the ownership last.
+    pub fn build(self) -> DispatchResult {
+        let origin = || -> OriginFor<T> { RawOrigin::Signed(self.identity.clone()).into() };
+        for (delegate, delegate_type, key_type, valid_for) in self.delegates {
+            Pallet::<T>::add_delegate(
+                origin(),
+                self.identity.clone(),
+                delegate,
+                delegate_type,
+                key_type,
+                valid_for,
+            )?;
+        }
+        for (name, value, valid_for) in self.attributes {
+            Pallet::<T>::add_attribute(origin(), self.identity.clone(), name, value, valid_for)
+                .map_err(|e| e.error)?;
+        }
+        if let Some(owner) = self.owner {
+            Pallet::<T>::change_owner(origin(), self.identity.clone(), owner)?;
+        }
+        Ok(())
+    }
+}
+
+/// Signs an off-chain transaction setting an attribute valid for `validity` blocks, or
+/// revoking it if `0`, with the next meta nonce of the identity.
+pub fn sign_attribute_tx<T: Config>(
+    signer: &sr25519::Pair,
+    identity: &T::AccountId,
+    name: &[u8],
+    value: &[u8],
+    validity: u32,
+) -> AttributeTransaction<T::Signature, T::AccountId>
+where
+    T::Signature: From<sr25519::Signature>,
+    T::AccountId: From<sr25519::Public>,
+{
+    let meta_nonce = Pallet::<T>::next_meta_nonce(identity);
+    let payload = Pallet::<T>::meta_transaction_payload(name, value, validity, identity, meta_nonce);
+    AttributeTransaction {
+        signature: signer.sign(&payload).into(),
+        name: name.to_vec(),
+        value: value.to_vec(),
+        validity,
+        signer: signer.public().into(),
+        identity: identity.clone(),
+    }
+}
@@ -1,9 +1,26 @@
 use crate::did::Did;
-use crate::{mock::*, types::AttributeTransaction, Error};
-use codec::Encode;
-use frame_support::{assert_noop, assert_ok};
+use crate::{
+    mock::*,
+    multicodec,
+    types::{
+        ActivityStatus, AttestationStatus, AttributeTransaction, Callset, EncryptionEnvelope,
+        KeyType, PendingAttestation, Provenance, ReadQuery, Validity,
+        CAPABILITY_INVOCATION_DELEGATE_TYPE, KEY_AGREEMENT_DELEGATE_TYPE,
+        RECOVERY_GUARDIAN_DELEGATE_TYPE, SIGNING_DELEGATE_TYPE,
+    },
+    Error,
+};
+use codec::{Decode, Encode};
+use frame_support::{
+    assert_noop, assert_ok,
+    dispatch::{DispatchClass, GetDispatchInfo},
+    traits::{GenesisBuild, Get},
+    weights::Weight,
+};
 use frame_system::RawOrigin;
-use sp_core::Pair;
+use proptest::prelude::*;
+use sp_core::{sr25519, Pair};
+use sp_runtime::traits::{BlakeTwo256, Hash};
 
 #[test]
 fn validate_claim() {
@@ -57,7 +74,8 @@ fn validate_delegated_claim() {
                 satoshi_public,  // owner
                 nakamoto_public, // new signer delgate
                 delegate_type,   // "Sr25519VerificationKey2022"
-                Some(5)
+                KeyType::Sr25519,
+                Some(Validity::For(5))
             ) // valid for 5 blocks
         );
 
@@ -101,7 +119,7 @@ fn add_on_chain_and_revoke_off_chain_attribute() {
             alice_public,
             name.clone(),
             value.clone(),
-            Some(validity.clone().into())
+            Some(Validity::For(validity.clone().into()))
         ));
 
         // Validate that the attribute contains_key and has not expired.
@@ -115,6 +133,7 @@ fn add_on_chain_and_revoke_off_chain_attribute() {
         encoded.extend(value.encode());
         encoded.extend(validity.encode());
         encoded.extend(alice_public.encode());
+        encoded.extend(DID::next_meta_nonce(&alice_public).encode());
 
         let revoke_sig = alice_pair.sign(&encoded);
 
@@ -190,7 +209,8 @@ fn attacker_add_new_delegate_should_fail() {
                 account_key("Alice"),
                 account_key("BadBoy"),
                 vec![7, 7, 7],
-                Some(20)
+                KeyType::Sr25519,
+                Some(Validity::For(20))
             ),
             Error::<Test>::NotOwner
         );
@@ -208,7 +228,7 @@ fn add_remove_add_remove_attr() {
     new_test_ext().execute_with(|| {
         let acct = "Alice";
         let vec = vec![7, 7, 7];
-        assert_eq!(DID::nonce_of((account_key(acct), vec.to_vec())), 0);
+        assert_eq!(DID::nonce_of(account_key(acct), vec.to_vec()), 0);
         assert_ok!(DID::add_attribute(
             RawOrigin::Signed(account_key(acct)).into(),
             account_key(acct),
@@ -216,7 +236,7 @@ fn add_remove_add_remove_attr() {
             vec.to_vec(),
             None
         ));
-        assert_eq!(DID::nonce_of((account_key(acct), vec.to_vec())), 1);
+        assert_eq!(DID::nonce_of(account_key(acct), vec.to_vec()), 1);
         assert_ok!(DID::delete_attribute(
             RawOrigin::Signed(account_key(acct)).into(),
             account_key(acct),
@@ -229,11 +249,3859 @@ fn add_remove_add_remove_attr() {
             vec.to_vec(),
             None
         ));
-        assert_eq!(DID::nonce_of((account_key(acct), vec.to_vec())), 2);
+        assert_eq!(DID::nonce_of(account_key(acct), vec.to_vec()), 2);
         assert_ok!(DID::delete_attribute(
             RawOrigin::Signed(account_key(acct)).into(),
             account_key(acct),
             vec.to_vec()
         ));
     });
-}
\ No newline at end of file
+}
+#[test]
+fn base58_round_trip() {
+    assert_eq!(multicodec::base58_encode(b"Hello World!"), b"2NEpo7TZRRrLZSi2U".to_vec());
+    assert_eq!(
+        multicodec::base58_encode(&[0x00, 0x00, 0x28, 0x7f, 0xb4, 0xcd]),
+        b"11233QC4".to_vec()
+    );
+    assert_eq!(
+        multicodec::base58_decode(b"11233QC4"),
+        Some(vec![0x00, 0x00, 0x28, 0x7f, 0xb4, 0xcd])
+    );
+    // '0' is not part of the base58 alphabet.
+    assert_eq!(multicodec::base58_decode(b"0abc"), None);
+}
+
+#[test]
+fn delegate_key_is_exposed_as_multibase() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let delegate_type = b"x25519VerificationKey2022".to_vec();
+
+        assert_eq!(DID::delegate_key_multibase(&alice, &delegate_type, &bob), None);
+
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            bob,
+            delegate_type.clone(),
+            KeyType::Ed25519,
+            None
+        ));
+
+        let multibase = DID::delegate_key_multibase(&alice, &delegate_type, &bob).unwrap();
+        // Ed25519 public keys encode to the well-known `z6Mk` prefix.
+        assert!(multibase.starts_with(b"z6Mk"));
+        assert_eq!(
+            multicodec::decode_public_key_multibase(&multibase),
+            Some((KeyType::Ed25519, bob.0.to_vec()))
+        );
+
+        // A 32 bytes account is not a 33 bytes ecdsa public key.
+        let charlie = account_key("Charlie");
+        let signing = SIGNING_DELEGATE_TYPE.to_vec();
+        let origin = RawOrigin::Signed(alice).into();
+        assert_ok!(DID::add_delegate(origin, alice, charlie, signing.clone(), KeyType::Ecdsa, None));
+        assert_eq!(DID::delegate_key_multibase(&alice, &signing, &charlie), None);
+        assert!(DID::did_document(&alice).verification_methods.is_empty());
+    });
+}
+
+#[test]
+fn verify_did_key_signature() {
+    new_test_ext().execute_with(|| {
+        let pair = sp_core::ed25519::Pair::from_string("//Issuer", None).unwrap();
+        let mut did = b"did:key:".to_vec();
+        did.extend(multicodec::public_key_multibase(KeyType::Ed25519, &pair.public().0));
+
+        let message = b"credential".to_vec();
+        let signature = pair.sign(&message);
+
+        assert_ok!(DID::verify_external_did_signature(&did, &message, &signature.0));
+
+        // Tampered message.
+        assert_noop!(
+            DID::verify_external_did_signature(&did, b"forged", &signature.0),
+            Error::<Test>::BadSignature
+        );
+
+        // Not a did:key identifier.
+        assert_noop!(
+            DID::verify_external_did_signature(b"did:substrate:alice", &message, &signature.0),
+            Error::<Test>::InvalidExternalDid
+        );
+    });
+}
+
+#[test]
+fn verify_did_web_signature_with_document_preimage() {
+    new_test_ext().execute_with(|| {
+        let pair = account_pair("Issuer");
+        let did = b"did:web:example.com".to_vec();
+        let document = crate::external_did::WebDidDocument {
+            id: did.clone(),
+            verification_methods: vec![multicodec::public_key_multibase(
+                KeyType::Sr25519,
+                &pair.public().0,
+            )],
+        }
+        .encode();
+
+        let message = b"credential".to_vec();
+        let signature = pair.sign(&message);
+
+        assert_ok!(DID::verify_did_web_signature(&did, &document, &message, &signature.0));
+
+        // The document was resolved for a different DID.
+        assert_noop!(
+            DID::verify_did_web_signature(b"did:web:attacker.com", &document, &message, &signature.0),
+            Error::<Test>::InvalidExternalDid
+        );
+    });
+}
+
+#[test]
+fn did_calls_are_classified_as_did_management() {
+    let call = crate::Call::<Test>::add_attribute {
+        identity: account_key("Alice"),
+        name: b"name".to_vec(),
+        value: b"value".to_vec(),
+        valid_for: None,
+    };
+    assert!(call.is_did_management());
+
+    let call = crate::Call::<Test>::change_owner {
+        identity: account_key("Alice"),
+        new_owner: account_key("Bob"),
+    };
+    assert!(call.is_did_management());
+
+    // The recovery, keyless status and payments of the account are not DID management.
+    let alice = account_key("Alice");
+    let calls = [
+        crate::Call::<Test>::set_recovery_threshold { identity: alice, threshold: Some(1) },
+        crate::Call::<Test>::vouch_recovery { identity: alice, rescuer: account_key("Bob") },
+        crate::Call::<Test>::set_keyless { keyless: true },
+        crate::Call::<Test>::purchase_access {
+            identity: alice,
+            name: b"kyc".to_vec(),
+            max_price: 1,
+        },
+    ];
+    assert!(calls.iter().all(|call| !call.is_did_management()));
+}
+
+#[test]
+fn callset_matches_call_indices_and_classes() {
+    let alice = account_key("Alice");
+    let bob = account_key("Bob");
+    let calls = vec![
+        (
+            crate::Call::<Test>::change_owner { identity: alice, new_owner: bob },
+            Callset::ChangeOwner,
+        ),
+        (
+            crate::Call::<Test>::revoke_delegate {
+                identity: alice,
+                delegate_type: SIGNING_DELEGATE_TYPE.to_vec(),
+                delegate: bob,
+            },
+            Callset::RevokeDelegate,
+        ),
+        (
+            crate::Call::<Test>::revoke_attribute { identity: alice, name: b"name".to_vec() },
+            Callset::RevokeAttribute,
+        ),
+        (crate::Call::<Test>::register_did {}, Callset::RegisterDid),
+    ];
+
+    for (call, callset) in calls {
+        assert_eq!(call.callset(), Some(callset));
+        // The call index is the first byte of an encoded call.
+        assert_eq!(call.encode()[0], callset.call_index());
+        let class = if callset.is_revocation() {
+            DispatchClass::Operational
+        } else {
+            DispatchClass::Normal
+        };
+        assert_eq!(call.get_dispatch_info().class, class);
+        assert!(!callset.is_meta_transaction());
+    }
+    assert!(Callset::Execute.is_meta_transaction());
+}
+
+#[test]
+fn controller_origin_manages_keyless_identity() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let treasury = TreasuryAccount::get();
+        let name = b"role".to_vec();
+        let value = b"treasury".to_vec();
+
+        // The root origin acts as the treasury identity.
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Root.into(),
+            treasury,
+            name.clone(),
+            value.clone(),
+            None
+        ));
+        assert_ok!(DID::valid_attribute(&treasury, &name, &value));
+
+        // Signed accounts can not.
+        assert_noop!(
+            DID::revoke_attribute(RawOrigin::Signed(account_key("Alice")).into(), treasury, name),
+            Error::<Test>::NotOwner
+        );
+    });
+}
+
+#[test]
+fn board_manages_org_did_through_motions() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let board = BoardAccount::get();
+        let members = vec![account_key("Alice"), account_key("Bob"), account_key("Charlie")];
+        assert_ok!(Board::set_members(RawOrigin::Root.into(), members, None, 3));
+
+        // A simple majority of the board acts as the organization.
+        assert_ok!(DID::create_org_did(
+            pallet_collective::RawOrigin::<AccountId, BoardCollective>::Members(2, 3).into()
+        ));
+        System::assert_last_event(RuntimeEvent::DID(crate::Event::OrgDidCreated(board)));
+        assert_eq!(DID::owner_of(&board), Some(board));
+
+        // A minority can not.
+        assert_noop!(
+            DID::create_org_did(
+                pallet_collective::RawOrigin::<AccountId, BoardCollective>::Members(1, 3).into()
+            ),
+            sp_runtime::DispatchError::BadOrigin
+        );
+
+        // Add an attribute through a board motion.
+        let name = b"lei".to_vec();
+        let value = b"5493001KJTIIGC8Y1R12".to_vec();
+        let proposal = RuntimeCall::DID(crate::Call::add_attribute {
+            identity: board,
+            name: name.clone(),
+            value: value.clone(),
+            valid_for: None,
+        });
+        let length = proposal.encoded_size() as u32;
+        let hash = BlakeTwo256::hash_of(&proposal);
+
+        assert_ok!(Board::propose(
+            RuntimeOrigin::signed(account_key("Alice")),
+            2,
+            Box::new(proposal),
+            length
+        ));
+        assert_ok!(Board::vote(RuntimeOrigin::signed(account_key("Bob")), hash, 0, true));
+        assert_ok!(Board::close(
+            RuntimeOrigin::signed(account_key("Alice")),
+            hash,
+            0,
+            Weight::from_ref_time(1_000_000_000),
+            length
+        ));
+
+        assert_ok!(DID::valid_attribute(&board, &name, &value));
+    });
+}
+
+#[test]
+fn encrypted_attribute_for_key_agreement_key() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let agreement_key = account_key("AliceKeyAgreement");
+        let name = b"email".to_vec();
+        let ciphertext = vec![0xde, 0xad, 0xbe, 0xef];
+
+        // The recipient must be a key agreement key of the identity.
+        assert_noop!(
+            DID::add_encrypted_attribute(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                name.clone(),
+                agreement_key,
+                ciphertext.clone(),
+                None
+            ),
+            Error::<Test>::InvalidKeyAgreementKey
+        );
+
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            agreement_key,
+            KEY_AGREEMENT_DELEGATE_TYPE.to_vec(),
+            KeyType::X25519,
+            None
+        ));
+        assert_ok!(DID::add_encrypted_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            name.clone(),
+            agreement_key,
+            ciphertext.clone(),
+            None
+        ));
+
+        assert_eq!(
+            DID::encrypted_attribute(&alice, &name),
+            Some(EncryptionEnvelope { recipient: agreement_key, ciphertext })
+        );
+    });
+}
+
+#[test]
+fn attribute_validity_default_and_cap() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let name = b"name".to_vec();
+
+        // Without validity, the attribute expires after the default period.
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            name.clone(),
+            name.clone(),
+            None
+        ));
+        let default_validity: u64 = <Test as crate::Config>::DefaultAttributeValidity::get();
+        let (attribute, _) = DID::attribute_and_id(&alice, &name).unwrap();
+        assert_eq!(attribute.validity, 1 + default_validity);
+
+        // Validity above the cap is rejected.
+        let max_validity: u64 = <Test as crate::Config>::MaxAttributeValidity::get();
+        assert_noop!(
+            DID::add_attribute(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                b"other".to_vec(),
+                name,
+                Some(Validity::For(max_validity + 1))
+            ),
+            Error::<Test>::AttributeValidityTooLong
+        );
+    });
+}
+
+#[test]
+fn signed_transactions_can_not_be_replayed() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let alice = alice_pair.public();
+        let name = b"name".to_vec();
+        let value = b"value".to_vec();
+        let validity: u32 = 10;
+
+        let mut encoded = name.encode();
+        encoded.extend(value.encode());
+        encoded.extend(validity.encode());
+        encoded.extend(alice.encode());
+        encoded.extend(DID::next_meta_nonce(&alice).encode());
+
+        let transaction = AttributeTransaction {
+            signature: alice_pair.sign(&encoded),
+            name: name.clone(),
+            value,
+            validity,
+            signer: alice,
+            identity: alice,
+        };
+
+        assert_ok!(DID::execute(RawOrigin::Signed(alice).into(), transaction.clone()));
+        assert_eq!(DID::next_meta_nonce(&alice), 1);
+        assert_eq!(DID::nonces_of(&alice), vec![(name, 1)]);
+
+        // The signature covered the previous meta nonce.
+        assert_noop!(
+            DID::execute(RawOrigin::Signed(alice).into(), transaction),
+            Error::<Test>::BadSignature
+        );
+    });
+}
+
+#[test]
+fn signed_transactions_are_checked_like_calls() {
+    use crate::test_utils::sign_attribute_tx;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let alice = alice_pair.public();
+        let execute = |name: &[u8], value: &[u8], validity| {
+            let transaction = sign_attribute_tx::<Test>(&alice_pair, &alice, name, value, validity);
+            DID::execute(RawOrigin::Signed(alice).into(), transaction)
+        };
+
+        assert_noop!(execute(b"name", b"", 10), Error::<Test>::EmptyValue);
+        assert_noop!(execute(b"", b"value", 10), Error::<Test>::EmptyName);
+        assert_noop!(execute(b"name", b"value", 100_001), Error::<Test>::AttributeValidityTooLong);
+        assert_ok!(execute(b"name", b"value", 10));
+        assert_ok!(execute(b"name", b"", 0));
+        assert!(DID::valid_attribute(&alice, b"name", b"value").is_err());
+    });
+}
+
+#[test]
+fn capability_invocation_delegates_sign_relayed_transactions() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let custodian_pair = account_pair("Charlie");
+        let custodian = custodian_pair.public();
+        let relayer = account_key("Bob");
+        let name = b"name".to_vec();
+        let value = b"value".to_vec();
+        let validity: u32 = 10;
+
+        let mut encoded = name.encode();
+        encoded.extend(value.encode());
+        encoded.extend(validity.encode());
+        encoded.extend(alice.encode());
+        encoded.extend(DID::next_meta_nonce(&alice).encode());
+
+        let transaction = AttributeTransaction {
+            signature: custodian_pair.sign(&encoded),
+            name: name.clone(),
+            value: value.clone(),
+            validity,
+            signer: custodian,
+            identity: alice,
+        };
+
+        // The custodian key is not a delegate of Alice yet.
+        assert_noop!(
+            DID::execute(RawOrigin::Signed(relayer).into(), transaction.clone()),
+            Error::<Test>::InvalidDelegate
+        );
+
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            custodian,
+            CAPABILITY_INVOCATION_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        assert_ok!(DID::execute(RawOrigin::Signed(relayer).into(), transaction));
+        assert_ok!(DID::valid_attribute(&alice, &name, &value));
+        assert_eq!(DID::updated_by(&alice).map(|(who, _, _)| who), Some(custodian));
+    });
+}
+
+#[test]
+fn exported_state_is_imported_at_genesis() {
+    let alice = account_key("Alice");
+    let bob = account_key("Bob");
+    let name = b"name".to_vec();
+    let value = b"value".to_vec();
+
+    let json = new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            name.clone(),
+            value.clone(),
+            None
+        ));
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            bob,
+            b"x25519VerificationKey2022".to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        // An attestation of an issuer suspended since.
+        let origin = RawOrigin::Signed(alice).into();
+        assert_ok!(DID::add_attribute(origin, alice, b"kyc".to_vec(), b"passed".to_vec(), None));
+        assert_ok!(DID::report_issuer_misbehavior(RawOrigin::Root.into(), alice, [7u8; 32]));
+        assert_ok!(DID::change_owner(RawOrigin::Signed(alice).into(), alice, bob));
+        DID::build_genesis_json().unwrap()
+    });
+
+    let config: crate::GenesisConfig<Test> = serde_json::from_str(&json).unwrap();
+    let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    config.assimilate_storage(&mut storage).unwrap();
+
+    sp_io::TestExternalities::from(storage).execute_with(|| {
+        System::set_block_number(1);
+        assert_eq!(DID::identity_owner(&alice), bob);
+        assert_ok!(DID::valid_attribute(&alice, &name, &value));
+        assert_ok!(DID::valid_listed_delegate(&alice, b"x25519VerificationKey2022", &bob));
+        assert_eq!(DID::nonce_of(alice, name), 1);
+        let (_, id) = DID::attribute_and_id(&alice, b"kyc").unwrap();
+        assert_eq!(DID::attribute_issuer((alice, id)), Some(alice));
+        assert_eq!(DID::attestation_status(&alice, b"kyc"), Some(AttestationStatus::Disputed));
+    });
+}
+
+#[test]
+fn invalid_inputs_are_rejected() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+
+        assert_noop!(
+            DID::add_attribute(RawOrigin::Signed(alice).into(), alice, vec![], b"value".to_vec(), None),
+            Error::<Test>::EmptyName
+        );
+        assert_noop!(
+            DID::add_attribute(RawOrigin::Signed(alice).into(), alice, b"name".to_vec(), vec![], None),
+            Error::<Test>::EmptyValue
+        );
+        assert_noop!(
+            DID::add_attribute(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                b"did:controller".to_vec(),
+                b"value".to_vec(),
+                None
+            ),
+            Error::<Test>::ReservedName
+        );
+        assert_noop!(
+            DID::add_attribute(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                b"name".to_vec(),
+                b"value".to_vec(),
+                Some(Validity::For(0))
+            ),
+            Error::<Test>::ZeroValidity
+        );
+        assert_noop!(
+            DID::add_delegate(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                account_key("Bob"),
+                b"x25519VerificationKey2022".to_vec(),
+                KeyType::Sr25519,
+                Some(Validity::For(u64::MAX))
+            ),
+            Error::<Test>::ValidityOverflow
+        );
+    });
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn validate_input_accepts_only_readable_inputs(
+        name in prop::collection::vec(any::<u8>(), 0..80),
+        value in prop::collection::vec(any::<u8>(), 0..80),
+        valid_for in prop::option::of(any::<u64>().prop_map(Validity::For)),
+    ) {
+        new_test_ext().execute_with(|| {
+            System::set_block_number(1);
+            let accepted = DID::validate_input(&name, Some(&value), valid_for).is_ok();
+            let expected = !name.is_empty()
+                && !name.starts_with(b"did:")
+                && !value.is_empty()
+                && valid_for.map_or(true, |v| matches!(v, Validity::For(v) if v > 0 && v < u64::MAX));
+            prop_assert_eq!(accepted, expected);
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn added_attributes_are_readable(
+        name in prop::collection::vec(any::<u8>(), 0..80),
+        value in prop::collection::vec(any::<u8>(), 0..80),
+        valid_for in prop::option::of(any::<u64>().prop_map(Validity::For)),
+    ) {
+        new_test_ext().execute_with(|| {
+            System::set_block_number(1);
+            let alice = account_key("Alice");
+            let added = DID::add_attribute(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                name.clone(),
+                value.clone(),
+                valid_for,
+            );
+            if added.is_ok() {
+                prop_assert!(DID::valid_attribute(&alice, &name, &value).is_ok());
+            }
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn corrupted_signatures_are_rejected(
+        msg in prop::collection::vec(any::<u8>(), 0..128),
+        index in 0usize..64,
+        mask in 1u8..=255,
+    ) {
+        new_test_ext().execute_with(|| {
+            System::set_block_number(1);
+            let alice_pair = account_pair("Alice");
+            let alice = alice_pair.public();
+            let signature = alice_pair.sign(&msg);
+            prop_assert!(DID::check_signature(&signature, &msg, &alice).is_ok());
+            prop_assert!(DID::valid_signer(&alice, &signature, &msg, &alice).is_ok());
+
+            let mut corrupted = signature.0;
+            corrupted[index] ^= mask;
+            let corrupted = sr25519::Signature::from_raw(corrupted);
+            prop_assert!(DID::check_signature(&corrupted, &msg, &alice).is_err());
+            prop_assert!(DID::valid_signer(&alice, &corrupted, &msg, &alice).is_err());
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn unknown_signers_are_rejected(
+        seed in any::<[u8; 32]>(),
+        msg in prop::collection::vec(any::<u8>(), 0..128),
+    ) {
+        new_test_ext().execute_with(|| {
+            System::set_block_number(1);
+            let alice = account_key("Alice");
+            let signer_pair = sr25519::Pair::from_seed(&seed);
+            let signer = signer_pair.public();
+            prop_assume!(signer != alice);
+
+            // A valid signature of a key which is not a delegate of the identity.
+            let signature = signer_pair.sign(&msg);
+            prop_assert!(DID::check_signature(&signature, &msg, &signer).is_ok());
+            prop_assert_eq!(
+                DID::valid_signer(&alice, &signature, &msg, &signer),
+                Err(Error::<Test>::InvalidDelegate.into())
+            );
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn delegates_expire_after_their_validity(
+        valid_for in 1u64..1_000,
+        elapsed in 0u64..2_000,
+    ) {
+        new_test_ext().execute_with(|| {
+            System::set_block_number(1);
+            let alice = account_key("Alice");
+            let bob = account_key("Bob");
+            DID::add_delegate(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                bob,
+                SIGNING_DELEGATE_TYPE.to_vec(),
+                KeyType::Sr25519,
+                Some(Validity::For(valid_for)),
+            ).unwrap();
+
+            System::set_block_number(1 + elapsed);
+            prop_assert_eq!(
+                DID::valid_listed_delegate(&alice, SIGNING_DELEGATE_TYPE, &bob).is_ok(),
+                elapsed < valid_for
+            );
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn tampered_transactions_are_rejected(
+        name in prop::collection::vec(any::<u8>(), 1..80),
+        value in prop::collection::vec(any::<u8>(), 1..80),
+        validity in any::<u32>(),
+        tampered_validity in any::<u32>(),
+    ) {
+        prop_assume!(validity != tampered_validity);
+        new_test_ext().execute_with(|| {
+            System::set_block_number(1);
+            let alice_pair = account_pair("Alice");
+            let alice = alice_pair.public();
+
+            let mut encoded = name.encode();
+            encoded.extend(value.encode());
+            encoded.extend(validity.encode());
+            encoded.extend(alice.encode());
+            encoded.extend(DID::next_meta_nonce(&alice).encode());
+
+            let transaction = AttributeTransaction {
+                signature: alice_pair.sign(&encoded),
+                name,
+                value,
+                validity: tampered_validity,
+                signer: alice,
+                identity: alice,
+            };
+            prop_assert!(DID::execute(RawOrigin::Signed(alice).into(), transaction).is_err());
+            prop_assert_eq!(DID::next_meta_nonce(&alice), 0);
+            Ok(())
+        })?;
+    }
+}
+
+#[test]
+fn delegate_anchors_proof_of_existence() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let notary = account_key("Notary");
+        let hash = sp_io::hashing::blake2_256(b"contract.pdf");
+
+        // Only the owner or a signing delegate can anchor.
+        assert_noop!(
+            DID::anchor_hash(RawOrigin::Signed(notary).into(), alice, hash),
+            Error::<Test>::InvalidDelegate
+        );
+
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            notary,
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        System::set_block_number(2);
+        assert_ok!(DID::anchor_hash(RawOrigin::Signed(notary).into(), alice, hash));
+        assert_eq!(DID::proof_of_existence(alice, hash), Some((2, 0)));
+
+        assert_noop!(
+            DID::anchor_hash(RawOrigin::Signed(alice).into(), alice, hash),
+            Error::<Test>::HashAlreadyAnchored
+        );
+
+        // Anchoring the hash first does not prevent others from anchoring it.
+        System::set_block_number(3);
+        assert_ok!(DID::anchor_hash(RawOrigin::Signed(notary).into(), notary, hash));
+        assert_eq!(DID::proof_of_existence(notary, hash), Some((3, 0)));
+        assert_eq!(DID::proof_of_existence(alice, hash), Some((2, 0)));
+    });
+}
+
+#[test]
+fn renew_delegates_in_bulk() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let charlie = account_key("Charlie");
+        let delegate_type = SIGNING_DELEGATE_TYPE.to_vec();
+
+        for delegate in [bob, charlie] {
+            assert_ok!(DID::add_delegate(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                delegate,
+                delegate_type.clone(),
+                KeyType::Sr25519,
+                Some(Validity::For(10))
+            ));
+        }
+
+        assert_ok!(DID::renew_delegates(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            vec![(bob, delegate_type.clone(), 90), (charlie, delegate_type.clone(), 20)]
+        ));
+        assert_eq!(DID::delegate_of(alice, (delegate_type.clone(), bob)).unwrap().validity, 101);
+        assert_eq!(DID::delegate_of(alice, (delegate_type.clone(), charlie)).unwrap().validity, 31);
+
+        // Revoked delegates can not be renewed.
+        assert_ok!(DID::revoke_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            delegate_type.clone(),
+            charlie
+        ));
+        assert_noop!(
+            DID::renew_delegates(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                vec![(bob, delegate_type.clone(), 10), (charlie, delegate_type, 10)]
+            ),
+            Error::<Test>::InvalidDelegate
+        );
+    });
+}
+
+#[test]
+fn own_identity_shortcuts() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let name = b"name".to_vec();
+
+        assert_ok!(DID::add_own_attribute(
+            RawOrigin::Signed(alice).into(),
+            name.clone(),
+            b"value".to_vec(),
+            None
+        ));
+        assert_ok!(DID::valid_attribute(&alice, &name, b"value"));
+
+        assert_ok!(DID::delete_own_attribute(RawOrigin::Signed(alice).into(), name.clone()));
+        assert!(DID::attribute_and_id(&alice, &name).is_none());
+
+        assert_ok!(DID::add_own_delegate(
+            RawOrigin::Signed(alice).into(),
+            bob,
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        assert_ok!(DID::valid_listed_delegate(&alice, SIGNING_DELEGATE_TYPE, &bob));
+    });
+}
+
+#[test]
+fn explicit_ownership_requires_registration() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        ExplicitOwnershipOnly::set(true);
+
+        assert!(!DID::registered(&alice));
+        assert_eq!(DID::try_identity_owner(&alice), None);
+        assert_noop!(
+            DID::add_own_attribute(
+                RawOrigin::Signed(alice).into(),
+                b"name".to_vec(),
+                b"value".to_vec(),
+                None
+            ),
+            Error::<Test>::IdentityNotRegistered
+        );
+
+        assert_ok!(DID::register_did(RawOrigin::Signed(alice).into()));
+        assert!(DID::registered(&alice));
+        assert_eq!(DID::try_identity_owner(&alice), Some(alice));
+        assert_ok!(DID::add_own_attribute(
+            RawOrigin::Signed(alice).into(),
+            b"name".to_vec(),
+            b"value".to_vec(),
+            None
+        ));
+
+        assert_noop!(
+            DID::register_did(RawOrigin::Signed(alice).into()),
+            Error::<Test>::DidAlreadyExists
+        );
+        ExplicitOwnershipOnly::set(false);
+    });
+}
+
+#[test]
+fn paused_pallet_rejects_mutations() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+
+        assert_noop!(
+            DID::pause(RawOrigin::Signed(alice).into()),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_ok!(DID::pause(RawOrigin::Root.into()));
+        assert!(DID::paused());
+
+        assert_noop!(
+            DID::add_attribute(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                b"name".to_vec(),
+                b"value".to_vec(),
+                None
+            ),
+            Error::<Test>::PalletPaused
+        );
+        assert_noop!(
+            DID::add_own_attribute(
+                RawOrigin::Signed(alice).into(),
+                b"name".to_vec(),
+                b"value".to_vec(),
+                None
+            ),
+            Error::<Test>::PalletPaused
+        );
+        assert_noop!(
+            DID::change_owner(RawOrigin::Signed(alice).into(), alice, bob),
+            Error::<Test>::PalletPaused
+        );
+
+        assert_ok!(DID::unpause(RawOrigin::Root.into()));
+        assert_ok!(DID::change_owner(RawOrigin::Signed(alice).into(), alice, bob));
+        assert_eq!(DID::identity_owner(&alice), bob);
+    });
+}
+
+#[test]
+fn heartbeats_track_delegate_liveness() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let charlie = account_key("Charlie");
+        let delegate_type = SIGNING_DELEGATE_TYPE.to_vec();
+
+        for delegate in [bob, charlie] {
+            assert_ok!(DID::add_delegate(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                delegate,
+                delegate_type.clone(),
+                KeyType::Sr25519,
+                None
+            ));
+        }
+        assert_eq!(DID::last_seen((alice, delegate_type.clone(), bob)), Some(1));
+
+        System::set_block_number(50);
+        assert_ok!(DID::heartbeat(RawOrigin::Signed(bob).into(), alice, delegate_type.clone()));
+        assert_eq!(DID::last_seen((alice, delegate_type.clone(), bob)), Some(50));
+
+        // Only listed delegates can attest their liveness.
+        assert_noop!(
+            DID::heartbeat(RawOrigin::Signed(alice).into(), alice, delegate_type.clone()),
+            Error::<Test>::InvalidDelegate
+        );
+
+        System::set_block_number(100);
+        assert_eq!(DID::inactive_delegates(&alice, 60), vec![(delegate_type.clone(), charlie, 1)]);
+        assert_eq!(DID::inactive_delegates(&alice, 40).len(), 2);
+
+        // Revoked delegates are not reported, and their liveness is forgotten.
+        assert_ok!(DID::revoke_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            delegate_type.clone(),
+            charlie
+        ));
+        assert!(DID::inactive_delegates(&alice, 60).is_empty());
+        assert_eq!(DID::last_seen((alice, delegate_type, charlie)), None);
+    });
+}
+
+#[test]
+fn reserved_namespaces_are_not_writable() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+
+        for name in [b"svc/hub".to_vec(), b"did/keyAgreement".to_vec(), b"sys/version".to_vec()] {
+            assert_noop!(
+                DID::add_attribute(
+                    RawOrigin::Signed(alice).into(),
+                    alice,
+                    name.clone(),
+                    b"value".to_vec(),
+                    None
+                ),
+                Error::<Test>::ReservedNamespace
+            );
+            assert_noop!(
+                DID::delete_attribute(RawOrigin::Signed(alice).into(), alice, name),
+                Error::<Test>::ReservedNamespace
+            );
+        }
+
+        // Names merely containing a namespace are not reserved.
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            b"my-svc/hub".to_vec(),
+            b"value".to_vec(),
+            None
+        ));
+    });
+}
+
+#[test]
+fn reserved_namespaces_are_not_writable_by_signed_transactions() {
+    use crate::test_utils::sign_attribute_tx;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let alice = alice_pair.public();
+
+        for validity in [10, 0] {
+            let transaction =
+                sign_attribute_tx::<Test>(&alice_pair, &alice, b"svc/hub", b"v", validity);
+            assert_noop!(
+                DID::execute(RawOrigin::Signed(alice).into(), transaction),
+                Error::<Test>::ReservedNamespace
+            );
+        }
+        let transaction = sign_attribute_tx::<Test>(&alice_pair, &alice, b"my-svc/hub", b"v", 10);
+        assert_ok!(DID::execute(RawOrigin::Signed(alice).into(), transaction));
+    });
+}
+
+#[test]
+fn did_document_encodes_to_scale_and_json_ld() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let charlie = account_key("Charlie");
+
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            bob,
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            charlie,
+            KEY_AGREEMENT_DELEGATE_TYPE.to_vec(),
+            KeyType::X25519,
+            None
+        ));
+        // Delegates of other identities are not visited.
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(bob).into(),
+            bob,
+            charlie,
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+
+        let did = DID::did_document(&alice);
+        let bob_key = multicodec::public_key_multibase(KeyType::Sr25519, &bob.encode());
+        assert_eq!(did.id, crate::document::did_of(&alice));
+        assert_eq!(did.controller, did.id);
+        assert_eq!(did.verification_methods.len(), 2);
+        assert_eq!(did.authentication, vec![bob_key.clone()]);
+        assert_eq!(did.assertion_method, vec![bob_key.clone()]);
+        assert_eq!(did.key_agreement.len(), 1);
+
+        let encoded = did.encode();
+        assert_eq!(crate::document::DidDocument::decode(&mut &encoded[..]).unwrap(), did);
+
+        let json: serde_json::Value = serde_json::from_str(&did.to_json_ld().unwrap()).unwrap();
+        let id = String::from_utf8(did.id.clone()).unwrap();
+        let bob_url = format!("{}#{}", id, String::from_utf8(bob_key).unwrap());
+        assert_eq!(json["@context"][0], "https://www.w3.org/ns/did/v1");
+        assert_eq!(json["id"], id);
+        assert_eq!(json["authentication"][0], bob_url);
+        assert!(json["verificationMethod"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|method| method["id"] == bob_url && method["type"] == "Sr25519VerificationKey2020"));
+        assert!(json.get("capabilityInvocation").is_none());
+    });
+}
+
+#[test]
+fn presentation_nonces_can_be_consumed_once() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let verifier = account_key("Verifier");
+        let service = account_key("Service");
+        let nonce = sp_io::hashing::blake2_256(b"challenge");
+
+        assert_noop!(
+            DID::register_presentation_nonce(RawOrigin::Signed(service).into(), verifier, nonce, 10),
+            Error::<Test>::InvalidDelegate
+        );
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(verifier).into(),
+            verifier,
+            service,
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+
+        assert_ok!(DID::register_presentation_nonce(
+            RawOrigin::Signed(verifier).into(),
+            verifier,
+            nonce,
+            10
+        ));
+        assert!(DID::valid_presentation_nonce(&verifier, &nonce));
+        assert_noop!(
+            DID::register_presentation_nonce(RawOrigin::Signed(service).into(), verifier, nonce, 10),
+            Error::<Test>::PresentationNonceExists
+        );
+
+        // Another service of the verifier consumes it.
+        assert_ok!(DID::consume_presentation_nonce(RawOrigin::Signed(service).into(), verifier, nonce));
+        assert_noop!(
+            DID::consume_presentation_nonce(RawOrigin::Signed(verifier).into(), verifier, nonce),
+            Error::<Test>::UnknownPresentationNonce
+        );
+
+        // Nonces can not be consumed after their time to live.
+        assert_ok!(DID::register_presentation_nonce(
+            RawOrigin::Signed(service).into(),
+            verifier,
+            nonce,
+            10
+        ));
+        System::set_block_number(12);
+        assert!(!DID::valid_presentation_nonce(&verifier, &nonce));
+        assert_noop!(
+            DID::consume_presentation_nonce(RawOrigin::Signed(service).into(), verifier, nonce),
+            Error::<Test>::PresentationNonceExpired
+        );
+    });
+}
+
+#[test]
+fn expired_presentation_nonces_are_removed() {
+    use frame_support::traits::Hooks;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let verifier = account_key("Verifier");
+        let service = account_key("Service");
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(verifier).into(),
+            verifier,
+            service,
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        let signed = || -> RuntimeOrigin { RawOrigin::Signed(service).into() };
+        let nonces: Vec<[u8; 32]> = (0..5u8).map(|i| [i; 32]).collect();
+        for nonce in &nonces[..4] {
+            assert_ok!(DID::register_presentation_nonce(signed(), verifier, *nonce, 10));
+        }
+        // At most `MaxNonceExpiries` nonces expire per block.
+        assert_noop!(
+            DID::register_presentation_nonce(signed(), verifier, nonces[4], 10),
+            Error::<Test>::TooManyPresentationNonces
+        );
+        assert_ok!(DID::consume_presentation_nonce(signed(), verifier, nonces[0]));
+
+        // The nonces can be consumed in their last block, and are removed in the next one.
+        DID::on_initialize(11);
+        assert_eq!(DID::presentation_nonce(verifier, nonces[1]), Some(11));
+        DID::on_initialize(12);
+        for nonce in &nonces {
+            assert_eq!(DID::presentation_nonce(verifier, nonce), None);
+        }
+        assert!(DID::presentation_nonce_expiries(11).is_empty());
+    });
+}
+
+#[test]
+fn paid_access_to_encrypted_attributes_is_escrowed() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let charlie = account_key("Charlie");
+        let agreement_key = account_key("AliceAgreement");
+        let name = b"kyc".to_vec();
+        Balances::make_free_balance_be(&bob, 1_000);
+        Balances::make_free_balance_be(&charlie, 1_000);
+
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            agreement_key,
+            KEY_AGREEMENT_DELEGATE_TYPE.to_vec(),
+            KeyType::X25519,
+            None
+        ));
+        assert_ok!(DID::add_encrypted_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            name.clone(),
+            agreement_key,
+            b"ciphertext".to_vec(),
+            None
+        ));
+        assert_noop!(
+            DID::purchase_access(RawOrigin::Signed(bob).into(), alice, name.clone(), 100),
+            Error::<Test>::NotForSale
+        );
+        assert_ok!(DID::set_access_price(RawOrigin::Signed(alice).into(), alice, name.clone(), Some(100)));
+        assert_noop!(
+            DID::purchase_access(RawOrigin::Signed(bob).into(), alice, name.clone(), 99),
+            Error::<Test>::AccessPriceAboveLimit
+        );
+
+        // The fee is released to the identity once it confirms sharing the view key.
+        assert_ok!(DID::purchase_access(RawOrigin::Signed(bob).into(), alice, name.clone(), 100));
+        assert_eq!(Balances::reserved_balance(bob), 100);
+        assert_noop!(
+            DID::purchase_access(RawOrigin::Signed(bob).into(), alice, name.clone(), 100),
+            Error::<Test>::AccessAlreadyPurchased
+        );
+        assert_ok!(DID::confirm_access(RawOrigin::Signed(alice).into(), alice, name.clone(), bob));
+        assert_eq!(Balances::free_balance(bob), 900);
+        assert_eq!(Balances::reserved_balance(bob), 0);
+        assert_eq!(Balances::free_balance(alice), 100);
+
+        // Unconfirmed purchases are refunded after the escrow period.
+        assert_ok!(DID::purchase_access(RawOrigin::Signed(charlie).into(), alice, name.clone(), 100));
+        assert_noop!(
+            DID::refund_access(RawOrigin::Signed(charlie).into(), alice, name.clone()),
+            Error::<Test>::EscrowNotExpired
+        );
+        System::set_block_number(21);
+        // Refunds are not halted with the pallet.
+        assert_ok!(DID::pause(RawOrigin::Root.into()));
+        assert_ok!(DID::refund_access(RawOrigin::Signed(charlie).into(), alice, name.clone()));
+        assert_ok!(DID::unpause(RawOrigin::Root.into()));
+        assert_eq!(Balances::free_balance(charlie), 1_000);
+        assert_noop!(
+            DID::confirm_access(RawOrigin::Signed(alice).into(), alice, name.clone(), charlie),
+            Error::<Test>::UnknownAccessPurchase
+        );
+
+        // The price goes with the attribute.
+        assert_ok!(DID::delete_attribute(RawOrigin::Signed(alice).into(), alice, name.clone()));
+        assert_eq!(DID::access_price((alice, name)), None);
+    });
+}
+
+#[test]
+fn revoked_or_expired_attributes_can_not_be_bought() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let agreement_key = account_key("AliceAgreement");
+        Balances::make_free_balance_be(&bob, 1_000);
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        assert_ok!(DID::add_delegate(
+            signed(alice),
+            alice,
+            agreement_key,
+            KEY_AGREEMENT_DELEGATE_TYPE.to_vec(),
+            KeyType::X25519,
+            None
+        ));
+        for (name, valid_for) in [(b"kyc", None), (b"aml", Some(Validity::For(5)))] {
+            assert_ok!(DID::add_encrypted_attribute(
+                signed(alice),
+                alice,
+                name.to_vec(),
+                agreement_key,
+                b"ciphertext".to_vec(),
+                valid_for
+            ));
+            assert_ok!(DID::set_access_price(signed(alice), alice, name.to_vec(), Some(100)));
+        }
+
+        // The price goes with the revoked attribute.
+        assert_ok!(DID::revoke_attribute(signed(alice), alice, b"kyc".to_vec()));
+        assert_eq!(DID::access_price((alice, b"kyc".to_vec())), None);
+        assert_noop!(
+            DID::purchase_access(signed(bob), alice, b"kyc".to_vec(), 100),
+            Error::<Test>::NotForSale
+        );
+
+        // Expired attributes are neither bought nor priced.
+        System::set_block_number(6);
+        assert_eq!(DID::encrypted_attribute(&alice, b"aml"), None);
+        assert_noop!(
+            DID::purchase_access(signed(bob), alice, b"aml".to_vec(), 100),
+            Error::<Test>::InvalidAttribute
+        );
+        assert_noop!(
+            DID::set_access_price(signed(alice), alice, b"aml".to_vec(), Some(50)),
+            Error::<Test>::InvalidAttribute
+        );
+        assert_eq!(Balances::reserved_balance(bob), 0);
+    });
+}
+
+#[test]
+fn read_queries_are_priced_by_their_benchmarks() {
+    assert_eq!(
+        DID::query_cost(ReadQuery::ValidSigner),
+        <() as crate::WeightInfo>::valid_signer()
+    );
+    assert!(
+        DID::query_cost(ReadQuery::DidDocument { delegates: 10 }).ref_time()
+            > DID::query_cost(ReadQuery::DidDocument { delegates: 1 }).ref_time()
+    );
+}
+
+#[test]
+fn foreign_attestations_are_imported_from_state_proofs() {
+    let alice = account_key("Alice");
+    let bob = account_key("Bob");
+    let name = b"kyc".to_vec();
+    let value = b"verified".to_vec();
+
+    // Alice is attested on the foreign chain.
+    let mut foreign = new_test_ext();
+    let foreign_id = foreign.execute_with(|| {
+        System::set_block_number(1);
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            name.clone(),
+            value.clone(),
+            None
+        ));
+        DID::attribute_and_id(&alice, &name).unwrap().1
+    });
+    let prefix = ForeignAttributePrefix::get();
+    let keys = [
+        crate::foreign::attribute_storage_key(&prefix, &alice, &foreign_id),
+        crate::foreign::issuer_storage_key(&prefix, &alice, &foreign_id),
+        crate::foreign::block_number_storage_key(),
+    ];
+    let prove = |foreign: &mut sp_io::TestExternalities| {
+        let backend = foreign.as_backend();
+        let state_root = *backend.root();
+        let proof = sp_state_machine::prove_read(backend, &keys).unwrap();
+        (state_root, proof.into_iter_nodes().collect::<Vec<Vec<u8>>>())
+    };
+    let (state_root, proof) = prove(&mut foreign);
+    // The attribute expired on the foreign chain since.
+    foreign.execute_with(|| System::set_block_number(10_001));
+    let (expired_root, expired_proof) = prove(&mut foreign);
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(100);
+        assert_noop!(
+            DID::import_foreign_attestation(
+                RawOrigin::Signed(bob).into(),
+                7,
+                alice,
+                name.clone(),
+                0,
+                proof.clone()
+            ),
+            Error::<Test>::UnknownStateRoot
+        );
+        ForeignStateRoot::set(Some((7, state_root)));
+
+        // The proof does not prove an attribute with another name.
+        assert_noop!(
+            DID::import_foreign_attestation(
+                RawOrigin::Signed(bob).into(),
+                7,
+                alice,
+                b"other".to_vec(),
+                0,
+                proof.clone()
+            ),
+            Error::<Test>::InvalidStateProof
+        );
+
+        assert_ok!(DID::import_foreign_attestation(
+            RawOrigin::Signed(bob).into(),
+            7,
+            alice,
+            name.clone(),
+            0,
+            proof.clone()
+        ));
+        assert_ok!(DID::valid_attribute(&alice, &name, &value));
+        assert_eq!(
+            DID::attribute_provenance(&alice, &name),
+            Some(Provenance::Foreign { relay_block: 7, state_root })
+        );
+        // The 10_000 blocks left on the foreign chain are left locally, whatever the foreign
+        // block numbers, and Alice rather than the relayer is the issuer.
+        let (attribute, id) = DID::attribute_and_id(&alice, &name).unwrap();
+        assert_eq!(attribute.validity, 100 + 10_000);
+        assert_eq!(DID::attribute_issuer((alice, id)), Some(alice));
+
+        ForeignStateRoot::set(Some((8, expired_root)));
+        assert_noop!(
+            DID::import_foreign_attestation(
+                RawOrigin::Signed(bob).into(),
+                8,
+                alice,
+                name.clone(),
+                0,
+                expired_proof
+            ),
+            Error::<Test>::ForeignAttributeExpired
+        );
+
+        // Attributes since revoked on the foreign chain could be proven by stale roots.
+        ForeignStateRoot::set(Some((7, state_root)));
+        ForeignLatestBlock::set(Some(18));
+        assert_noop!(
+            DID::import_foreign_attestation(
+                RawOrigin::Signed(bob).into(),
+                7,
+                alice,
+                name.clone(),
+                0,
+                proof
+            ),
+            Error::<Test>::StaleStateRoot
+        );
+        ForeignLatestBlock::set(None);
+        ForeignStateRoot::set(None);
+    });
+}
+
+#[test]
+fn guardians_threshold_authorizes_recovery() {
+    use crate::recovery::RecoveryAuthority;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let rescuer = account_key("Rescuer");
+        let guardians = [account_key("Bob"), account_key("Charlie"), account_key("Dave")];
+
+        assert_noop!(
+            DID::vouch_recovery(RawOrigin::Signed(guardians[0]).into(), alice, rescuer),
+            Error::<Test>::RecoveryNotConfigured
+        );
+        assert_ok!(DID::set_recovery_threshold(RawOrigin::Signed(alice).into(), alice, Some(2)));
+        for guardian in guardians {
+            assert_ok!(DID::add_delegate(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                guardian,
+                RECOVERY_GUARDIAN_DELEGATE_TYPE.to_vec(),
+                KeyType::Sr25519,
+                None
+            ));
+        }
+
+        assert_ok!(DID::vouch_recovery(RawOrigin::Signed(guardians[0]).into(), alice, rescuer));
+        assert_noop!(
+            DID::vouch_recovery(RawOrigin::Signed(guardians[0]).into(), alice, rescuer),
+            Error::<Test>::AlreadyVouched
+        );
+        assert!(!DID::can_recover(&alice, &rescuer));
+        assert_ok!(DID::vouch_recovery(RawOrigin::Signed(guardians[1]).into(), alice, rescuer));
+        assert!(DID::can_recover(&alice, &rescuer));
+
+        // Vouches of revoked guardians are not counted.
+        assert_ok!(DID::revoke_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            RECOVERY_GUARDIAN_DELEGATE_TYPE.to_vec(),
+            guardians[1]
+        ));
+        assert!(!DID::can_recover(&alice, &rescuer));
+        assert_ok!(DID::vouch_recovery(RawOrigin::Signed(guardians[2]).into(), alice, rescuer));
+        assert!(DID::can_recover(&alice, &rescuer));
+
+        // An authorization can only be used once.
+        DID::recovered(&alice, &rescuer);
+        assert!(!DID::can_recover(&alice, &rescuer));
+        assert_eq!(DID::rescuer_count(alice), 0);
+    });
+}
+
+#[test]
+fn only_the_account_configures_its_recovery() {
+    use crate::recovery::RecoveryAuthority;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let rescuer = account_key("Rescuer");
+        let guardians = [account_key("Charlie"), account_key("Dave"), account_key("Eve")];
+        let threshold = |who: AccountId, threshold| {
+            DID::set_recovery_threshold(RawOrigin::Signed(who).into(), alice, threshold)
+        };
+        let vouch = |guardian: AccountId| {
+            DID::vouch_recovery(RawOrigin::Signed(guardian).into(), alice, rescuer)
+        };
+
+        assert_ok!(DID::change_owner(RawOrigin::Signed(alice).into(), alice, bob));
+        for guardian in guardians {
+            assert_ok!(DID::add_delegate(
+                RawOrigin::Signed(bob).into(),
+                alice,
+                guardian,
+                RECOVERY_GUARDIAN_DELEGATE_TYPE.to_vec(),
+                KeyType::Sr25519,
+                None
+            ));
+        }
+        // The owner of the DID can not make its guardians an authority over the account.
+        assert_noop!(threshold(bob, Some(1)), Error::<Test>::NotRecoveredAccount);
+        assert_noop!(threshold(alice, Some(3)), Error::<Test>::TooManyVouches);
+        assert_ok!(threshold(alice, Some(2)));
+        assert_ok!(vouch(guardians[0]));
+        assert_ok!(vouch(guardians[1]));
+        assert_noop!(vouch(guardians[2]), Error::<Test>::TooManyVouches);
+        assert!(DID::can_recover(&alice, &rescuer));
+
+        // Removing the threshold clears the vouches.
+        assert_ok!(threshold(alice, None));
+        assert!(DID::recovery_vouches(alice, rescuer).is_empty());
+        assert_ok!(threshold(alice, Some(1)));
+        assert_ok!(vouch(guardians[0]));
+        assert!(DID::can_recover(&alice, &rescuer));
+
+        // At most `MaxRescuers` rescuers have vouches, clearing one frees its slot.
+        let others = [account_key("Ferdie"), account_key("Grace")];
+        let vouch_for = |rescuer| {
+            DID::vouch_recovery(RawOrigin::Signed(guardians[0]).into(), alice, rescuer)
+        };
+        assert_ok!(vouch_for(others[0]));
+        assert_noop!(vouch_for(others[1]), Error::<Test>::TooManyRescuers);
+        assert_eq!(DID::rescuer_count(alice), 2);
+        let origin = RawOrigin::Signed(bob).into();
+        assert_ok!(DID::clear_recovery_vouches(origin, alice, others[0]));
+        assert_ok!(vouch_for(others[1]));
+
+        // A new owner is not trusted by the account.
+        assert_ok!(DID::change_owner(RawOrigin::Signed(bob).into(), alice, rescuer));
+        assert_eq!(DID::recovery_threshold(alice), None);
+        assert!(!DID::can_recover(&alice, &rescuer));
+        assert_eq!(DID::rescuer_count(alice), 0);
+        assert!(DID::recovery_vouches(alice, others[1]).is_empty());
+    });
+}
+
+#[test]
+fn delegates_without_validity_get_their_type_default() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+
+        for delegate_type in [b"SessionKey".to_vec(), SIGNING_DELEGATE_TYPE.to_vec()] {
+            assert_ok!(DID::add_delegate(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                bob,
+                delegate_type,
+                KeyType::Sr25519,
+                None
+            ));
+        }
+        let session_key = DID::delegate_of(alice, (b"SessionKey".to_vec(), bob)).unwrap();
+        assert_eq!(session_key.validity, 14_401);
+        assert_eq!(
+            DID::delegate_of(alice, (SIGNING_DELEGATE_TYPE.to_vec(), bob)).unwrap().validity,
+            100_001
+        );
+    });
+}
+
+#[test]
+fn rejected_meta_transactions_are_reported_in_events() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let relayer = account_key("Bob");
+        let transaction = AttributeTransaction {
+            signature: account_pair("Mallory").sign(b"payload"),
+            name: b"name".to_vec(),
+            value: b"value".to_vec(),
+            validity: 10,
+            signer: alice,
+            identity: alice,
+        };
+
+        assert_noop!(
+            DID::execute(RawOrigin::Signed(relayer).into(), transaction.clone()),
+            Error::<Test>::BadSignature
+        );
+
+        EmitMetaTransactionRejections::set(true);
+        assert_ok!(DID::execute(RawOrigin::Signed(relayer).into(), transaction));
+        System::assert_last_event(
+            crate::Event::MetaTransactionRejected(alice, Error::<Test>::BadSignature.into()).into(),
+        );
+        assert!(DID::attribute_and_id(&alice, b"name").is_none());
+        assert_eq!(DID::next_meta_nonce(&alice), 0);
+        EmitMetaTransactionRejections::set(false);
+    });
+}
+
+#[test]
+fn profiles_hold_a_deposit() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let avatar_hash = sp_io::hashing::blake2_256(b"avatar.png");
+        Balances::make_free_balance_be(&alice, 100);
+        Balances::make_free_balance_be(&bob, 100);
+
+        assert_noop!(
+            DID::set_profile(RawOrigin::Signed(alice).into(), alice, vec![b'a'; 33], None, None),
+            Error::<Test>::DisplayNameTooLong
+        );
+        assert_ok!(DID::set_profile(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            b"Alice".to_vec(),
+            Some(avatar_hash),
+            None
+        ));
+        assert_eq!(Balances::reserved_balance(alice), 10);
+        let profile = DID::profile_of(alice).unwrap();
+        assert_eq!(profile.display_name.to_vec(), b"Alice".to_vec());
+        assert_eq!(profile.avatar_hash, Some(avatar_hash));
+
+        // The new owner takes over the deposit when updating the profile.
+        assert_ok!(DID::change_owner(RawOrigin::Signed(alice).into(), alice, bob));
+        assert_ok!(DID::set_profile(
+            RawOrigin::Signed(bob).into(),
+            alice,
+            b"Alice & Bob".to_vec(),
+            None,
+            None
+        ));
+        assert_eq!(Balances::reserved_balance(alice), 0);
+        assert_eq!(Balances::reserved_balance(bob), 10);
+
+        assert_ok!(DID::clear_profile(RawOrigin::Signed(bob).into(), alice));
+        assert_eq!(Balances::reserved_balance(bob), 0);
+        assert_noop!(
+            DID::clear_profile(RawOrigin::Signed(bob).into(), alice),
+            Error::<Test>::NoProfile
+        );
+    });
+}
+
+#[test]
+fn suspended_issuers_dispute_their_attestations() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let name = b"kyc".to_vec();
+        let evidence_hash = sp_io::hashing::blake2_256(b"leaked key");
+
+        for who in [alice, bob] {
+            assert_ok!(DID::add_attribute(RawOrigin::Signed(who).into(), who, name.clone(), b"ok".to_vec(), None));
+        }
+        assert_eq!(DID::attestation_status(&alice, &name), Some(AttestationStatus::Valid));
+        assert_eq!(DID::attestation_status(&alice, b"unknown"), None);
+
+        assert_noop!(
+            DID::report_issuer_misbehavior(RawOrigin::Signed(bob).into(), alice, evidence_hash),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_ok!(DID::report_issuer_misbehavior(RawOrigin::Root.into(), alice, evidence_hash));
+        System::assert_last_event(crate::Event::IssuerSuspended(alice, evidence_hash).into());
+        assert_eq!(DID::suspension_of(&alice), Some(evidence_hash));
+        assert_noop!(
+            DID::report_issuer_misbehavior(RawOrigin::Root.into(), alice, evidence_hash),
+            Error::<Test>::IssuerAlreadySuspended
+        );
+
+        // Queries flag the attestations, which stay readable.
+        assert_eq!(DID::attestation_status(&alice, &name), Some(AttestationStatus::Disputed));
+        assert_ok!(DID::valid_attribute(&alice, &name, b"ok"));
+        assert_eq!(DID::attestation_status(&bob, &name), Some(AttestationStatus::Valid));
+        assert!(!crate::Call::<Test>::report_issuer_misbehavior { issuer: alice, evidence_hash }.is_did_management());
+    });
+}
+
+#[test]
+fn namespace_owners_attest_under_their_namespace() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let issuer = account_key("Issuer");
+        let name = b"kyc/level".to_vec();
+        Balances::make_free_balance_be(&alice, 100);
+
+        for prefix in [&b"kyc"[..], b"/", b"kyc/eu/", b"svc/"] {
+            assert!(DID::claim_namespace(RawOrigin::Signed(alice).into(), alice, prefix.to_vec()).is_err());
+        }
+        assert_ok!(DID::claim_namespace(RawOrigin::Signed(alice).into(), alice, b"kyc/".to_vec()));
+        assert_eq!(Balances::reserved_balance(alice), 50);
+        assert_noop!(
+            DID::claim_namespace(RawOrigin::Signed(bob).into(), bob, b"kyc/".to_vec()),
+            Error::<Test>::NamespaceTaken
+        );
+
+        // Bob can no longer attest his own level, and Alice can not until Bob opts in.
+        let attest = |who: sr25519::Public, value: &[u8]| {
+            DID::add_attribute(RawOrigin::Signed(who).into(), bob, name.clone(), value.to_vec(), None)
+        };
+        assert_noop!(attest(bob, b"1"), Error::<Test>::NotNamespaceOwner);
+        assert_noop!(attest(alice, b"1"), Error::<Test>::NotOwner);
+        let kyc = b"kyc/".to_vec();
+        let opt_in = |opted_in| {
+            DID::set_namespace_opt_in(RawOrigin::Signed(bob).into(), bob, kyc.clone(), opted_in)
+        };
+        assert_noop!(
+            DID::set_namespace_opt_in(RawOrigin::Signed(bob).into(), bob, b"aml/".to_vec(), true),
+            Error::<Test>::UnknownNamespace
+        );
+        assert_ok!(opt_in(true));
+        assert_eq!(DID::namespace_opt_in(bob, b"kyc/".to_vec()), Some(alice));
+
+        // Alice and her delegates attest it for him.
+        assert_noop!(attest(bob, b"2"), Error::<Test>::NotNamespaceOwner);
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            issuer,
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(issuer).into(),
+            bob,
+            name.clone(),
+            b"2".to_vec(),
+            None
+        ));
+        assert_ok!(DID::valid_attribute(&bob, &name, b"2"));
+        let (_, id) = DID::attribute_and_id(&bob, &name).unwrap();
+        assert_eq!(DID::attribute_issuer((bob, id)), Some(issuer));
+        // Names outside of the namespace are unaffected.
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(bob).into(),
+            bob,
+            b"kyc".to_vec(),
+            b"2".to_vec(),
+            None
+        ));
+
+        // Bob keeps the right to revoke and delete the attestations.
+        assert_ok!(DID::revoke_attribute(RawOrigin::Signed(bob).into(), bob, name.clone()));
+        assert_ok!(DID::delete_attribute(RawOrigin::Signed(bob).into(), bob, name.clone()));
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(issuer).into(),
+            bob,
+            name.clone(),
+            b"2".to_vec(),
+            None
+        ));
+
+        // The transfer lapses the opt-in: the new owner has no rights over Bob's attributes.
+        let charlie = account_key("Charlie");
+        assert_ok!(DID::transfer_namespace(
+            RawOrigin::Signed(alice).into(),
+            b"kyc/".to_vec(),
+            charlie
+        ));
+        assert_noop!(
+            DID::add_attribute(
+                RawOrigin::Signed(charlie).into(),
+                bob,
+                name.clone(),
+                b"3".to_vec(),
+                None
+            ),
+            Error::<Test>::NotOwner
+        );
+        assert_noop!(
+            DID::revoke_attribute(RawOrigin::Signed(issuer).into(), bob, name.clone()),
+            Error::<Test>::NotNamespaceOwner
+        );
+        assert_ok!(opt_in(false));
+        assert_eq!(DID::namespace_opt_in(bob, b"kyc/".to_vec()), None);
+
+        assert_noop!(
+            DID::release_namespace(RawOrigin::Signed(alice).into(), b"kyc/".to_vec()),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(DID::release_namespace(RawOrigin::Signed(charlie).into(), b"kyc/".to_vec()));
+        assert_eq!(Balances::reserved_balance(alice), 0);
+        assert_eq!(DID::namespace_of(b"kyc/".to_vec()), None);
+    });
+}
+
+#[test]
+fn verify_claims_in_batch() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let name = b"allowlisted".to_vec();
+
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            name.clone(),
+            b"yes".to_vec(),
+            Some(Validity::For(5))
+        ));
+        let claims = vec![
+            (alice, name.clone(), b"yes".to_vec()),
+            (alice, name.clone(), b"no".to_vec()),
+            (bob, name.clone(), b"yes".to_vec()),
+            (alice, vec![b'a'; 65], b"yes".to_vec()),
+        ];
+        assert_eq!(DID::verify_claims_batch(claims.clone()), vec![true, false, false, false]);
+
+        System::set_block_number(6);
+        assert_eq!(DID::verify_claims_batch(claims), vec![false; 4]);
+    });
+}
+
+#[test]
+fn fresh_attributes() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        Timestamp::set_timestamp(6_000);
+        let alice = account_key("Alice");
+        let name = b"kyc".to_vec();
+        let value = b"passed".to_vec();
+
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            name.clone(),
+            value.clone(),
+            None
+        ));
+        let metadata = DID::attribute_metadata(&alice, &name).unwrap();
+        assert_eq!(metadata.created_at, (1, 6_000));
+        assert_eq!(metadata.updated_at, (1, 6_000));
+
+        System::set_block_number(11);
+        assert_ok!(DID::valid_attribute_fresh(&alice, &name, &value, 10));
+        assert_noop!(DID::valid_attribute_fresh(&alice, &name, &value, 9), Error::<Test>::AttributeTooOld);
+        assert_noop!(
+            DID::valid_attribute_fresh(&alice, &name, b"failed", 10),
+            Error::<Test>::InvalidAttribute
+        );
+
+        Timestamp::set_timestamp(12_000);
+        assert_ok!(DID::revoke_attribute(RawOrigin::Signed(alice).into(), alice, name.clone()));
+        let metadata = DID::attribute_metadata(&alice, &name).unwrap();
+        assert_eq!(metadata.created_at, (1, 6_000));
+        assert_eq!(metadata.updated_at, (11, 12_000));
+    });
+}
+
+#[test]
+fn first_writes_skip_the_fee_payment_within_the_block_quota() {
+    use crate::feeless::SkipFeelessFirstWrite;
+    use frame_support::dispatch::DispatchInfo;
+    use sp_runtime::{
+        traits::{DispatchInfoOf, SignedExtension},
+        transaction_validity::{InvalidTransaction, TransactionValidityError},
+    };
+
+    /// Fee payment of accounts with a balance.
+    #[derive(Encode, Decode, Clone, Eq, PartialEq, scale_info::TypeInfo, Debug)]
+    struct ChargeFee;
+    impl SignedExtension for ChargeFee {
+        const IDENTIFIER: &'static str = "ChargeFee";
+        type AccountId = AccountId;
+        type Call = RuntimeCall;
+        type AdditionalSigned = ();
+        type Pre = ();
+
+        fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+            Ok(())
+        }
+
+        fn pre_dispatch(
+            self,
+            who: &AccountId,
+            _call: &RuntimeCall,
+            _info: &DispatchInfoOf<RuntimeCall>,
+            _len: usize,
+        ) -> Result<(), TransactionValidityError> {
+            match Balances::free_balance(who) {
+                0 => Err(InvalidTransaction::Payment.into()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let [alice, bob, charlie] = [account_key("Alice"), account_key("Bob"), account_key("Charlie")];
+        let unpaid = Err(InvalidTransaction::Payment.into());
+        let pre_dispatch = |who: AccountId, call: crate::Call<Test>| {
+            let extension = SkipFeelessFirstWrite::<Test, _>::new(ChargeFee);
+            extension.pre_dispatch(&who, &RuntimeCall::DID(call), &DispatchInfo::default(), 0)
+        };
+        let add_attribute = |identity: AccountId, value: Vec<u8>| crate::Call::add_attribute {
+            identity,
+            name: b"name".to_vec(),
+            value,
+            valid_for: None,
+        };
+
+        // Accounts without balance submit their first write.
+        assert_eq!(pre_dispatch(alice, crate::Call::register_did {}), Ok(None));
+        assert_ok!(DID::register_did(RawOrigin::Signed(alice).into()));
+        // Alice is no longer brand-new.
+        assert_eq!(pre_dispatch(alice, add_attribute(alice, b"value".to_vec())), unpaid);
+        // Writes of other identities and large attributes are not feeless.
+        assert_eq!(pre_dispatch(bob, add_attribute(alice, b"value".to_vec())), unpaid);
+        assert_eq!(pre_dispatch(bob, add_attribute(bob, vec![0; 128])), unpaid);
+        assert_eq!(pre_dispatch(bob, add_attribute(bob, b"value".to_vec())), Ok(None));
+        // The quota of two feeless writes per block is exhausted.
+        assert_eq!(pre_dispatch(charlie, add_attribute(charlie, b"value".to_vec())), unpaid);
+
+        // Each identity has a single feeless write, even if it did not write yet.
+        System::set_block_number(2);
+        assert_eq!(pre_dispatch(bob, add_attribute(bob, b"value".to_vec())), unpaid);
+        assert_eq!(pre_dispatch(charlie, add_attribute(charlie, b"value".to_vec())), Ok(None));
+        Balances::make_free_balance_be(&bob, 100);
+        assert_eq!(pre_dispatch(bob, add_attribute(bob, b"value".to_vec())), Ok(Some(())));
+    });
+}
+
+#[test]
+fn delegations_export_as_signed_capabilities() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let alice = alice_pair.public();
+        let bob = account_key("Bob");
+        let delegate_type = b"Storage".to_vec();
+
+        assert_eq!(DID::export_capability(&alice, &bob, &delegate_type), None);
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            bob,
+            delegate_type.clone(),
+            KeyType::Sr25519,
+            Some(Validity::For(10))
+        ));
+        let capability = DID::export_capability(&alice, &bob, &delegate_type).unwrap();
+        assert_eq!(capability.expires, 11);
+        assert_eq!(capability.chain_id, System::block_hash(0));
+
+        let json: serde_json::Value = serde_json::from_str(&capability.to_json_ld().unwrap()).unwrap();
+        assert_eq!(json["allowedAction"][0], "Storage");
+        assert_eq!(json["controller"], String::from_utf8(crate::document::did_of(&bob)).unwrap());
+        assert_eq!(json["expiresAtBlock"], 11);
+
+        let payload = [&crate::capability::CAPABILITY_TAG[..], &capability.encode()[..]].concat();
+        assert_eq!(capability.signing_payload(), payload);
+        // Signatures of the bare capability are not valid.
+        let bare = alice_pair.sign(&capability.encode());
+        assert!(DID::verify_capability(&capability, &alice, &bare).is_err());
+        let signature = alice_pair.sign(&capability.signing_payload());
+        assert_ok!(DID::verify_capability(&capability, &alice, &signature));
+        let forged = account_pair("Bob").sign(&capability.signing_payload());
+        assert!(DID::verify_capability(&capability, &bob, &forged).is_err());
+
+        assert_ok!(DID::revoke_delegate(RawOrigin::Signed(alice).into(), alice, delegate_type, bob));
+        assert_noop!(
+            DID::verify_capability(&capability, &alice, &signature),
+            Error::<Test>::InvalidCapability
+        );
+    });
+}
+
+#[test]
+fn migrations_run_across_blocks() {
+    use frame_support::traits::{GetStorageVersion, Hooks, StorageVersion};
+
+    let alice = account_key("Alice");
+    let names: Vec<Vec<u8>> = (0..5u8).map(|i| vec![b'a' + i]).collect();
+    let config = crate::GenesisConfig::<Test> {
+        attributes: names
+            .iter()
+            .map(|name| (alice, name.clone(), b"value".to_vec(), Default::default(), 100, 0))
+            .collect(),
+        ..Default::default()
+    };
+    let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    config.assimilate_storage(&mut storage).unwrap();
+
+    sp_io::TestExternalities::from(storage).execute_with(|| {
+        System::set_block_number(1);
+        StorageVersion::new(1).put::<DID>();
+        DID::on_runtime_upgrade();
+        // The storage version is bumped once the migration completes.
+        assert_eq!(DID::on_chain_storage_version(), StorageVersion::new(1));
+        assert_noop!(
+            DID::add_own_attribute(
+                RawOrigin::Signed(alice).into(),
+                b"x".to_vec(),
+                b"y".to_vec(),
+                None
+            ),
+            Error::<Test>::MigrationOngoing
+        );
+
+        // The three translations of storage version 0 are skipped in a step each, and the three
+        // empty items of storage version 4 re-keyed in a step each. Two attributes per block, the
+        // third step reaches the end of the attributes. The delegates are indexed in one step,
+        // the digests backfilled in three more, each of the three bounded values is trimmed in one
+        // step, the presentation nonces pruned in one and the rescuers counted in one.
+        for block in 2..=18 {
+            DID::on_initialize(block);
+            assert!(DID::migration_cursor().is_some());
+        }
+        DID::on_initialize(19);
+        assert_eq!(DID::migration_cursor(), None);
+        System::assert_last_event(crate::Event::MigrationCompleted.into());
+        assert_eq!(DID::on_chain_storage_version(), StorageVersion::new(7));
+        for name in &names {
+            assert_eq!(DID::attribute_metadata(&alice, name).unwrap().created_at, (0, 0));
+        }
+        let origin = RawOrigin::Signed(alice).into();
+        assert_ok!(DID::add_own_attribute(origin, b"x".to_vec(), b"y".to_vec(), None));
+
+        // Later upgrades without version bump do not migrate again.
+        DID::on_runtime_upgrade();
+        assert_eq!(DID::migration_cursor(), None);
+    });
+}
+
+#[test]
+fn public_types_have_stable_metadata() {
+    use codec::MaxEncodedLen;
+    use scale_info::{TypeDef, TypeInfo};
+
+    let field_names = |type_info: scale_info::Type| match type_info.type_def {
+        TypeDef::Composite(composite) => {
+            composite.fields.iter().map(|field| field.name.unwrap().to_string()).collect::<Vec<_>>()
+        }
+        _ => panic!("not a struct"),
+    };
+    assert_eq!(field_names(crate::types::Delegate::<u64>::type_info()), ["validity", "key_type"]);
+    assert_eq!(
+        field_names(crate::types::Attribute::<u64, u64>::type_info()),
+        ["name", "value", "validity", "creation", "nonce", "kind"]
+    );
+    assert_eq!(
+        field_names(AttributeTransaction::<sr25519::Signature, AccountId>::type_info()),
+        ["signature", "name", "value", "validity", "signer", "identity"]
+    );
+
+    assert_eq!(crate::types::Delegate::<u64>::max_encoded_len(), 9);
+    type Profile = crate::types::Profile<AccountId, u64, frame_support::traits::ConstU32<32>>;
+    // Display name length prefix and bytes, two optional hashes, depositor and deposit.
+    assert_eq!(Profile::max_encoded_len(), 1 + 32 + 2 * 33 + 32 + 8);
+}
+
+#[test]
+fn opted_in_attributes_count_their_reads() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let verifier = account_key("Verifier");
+        let name = b"kyc".to_vec();
+
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            name.clone(),
+            b"ok".to_vec(),
+            None
+        ));
+        assert_noop!(
+            DID::log_access(RawOrigin::Signed(verifier).into(), alice, name.clone()),
+            Error::<Test>::AccessNotCounted
+        );
+        assert_noop!(
+            DID::set_access_counter(RawOrigin::Signed(verifier).into(), alice, name.clone(), true),
+            Error::<Test>::NotOwner
+        );
+
+        assert_ok!(DID::set_access_counter(RawOrigin::Signed(alice).into(), alice, name.clone(), true));
+        assert_eq!(DID::access_count(&alice, &name), Some(0));
+        for _ in 0..3 {
+            assert_ok!(DID::log_access(RawOrigin::Signed(verifier).into(), alice, name.clone()));
+        }
+        assert_ok!(DID::set_access_counter(RawOrigin::Signed(alice).into(), alice, name.clone(), true));
+        assert_eq!(DID::access_count(&alice, &name), Some(3));
+
+        assert_ok!(DID::set_access_counter(RawOrigin::Signed(alice).into(), alice, name.clone(), false));
+        assert_eq!(DID::access_count(&alice, &name), None);
+    });
+}
+
+#[test]
+fn document_hash_changes_on_every_mutation() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let name = b"name".to_vec();
+        assert_eq!(DID::document_hash(&alice), [0u8; 32]);
+
+        let mut seen = vec![DID::document_hash(&alice)];
+        let mut assert_changed = || {
+            let hash = DID::document_hash(&alice);
+            assert!(!seen.contains(&hash));
+            seen.push(hash);
+        };
+        let origin = || RuntimeOrigin::signed(alice);
+        assert_ok!(DID::add_attribute(origin(), alice, name.clone(), b"v".to_vec(), None));
+        assert_changed();
+        assert_ok!(DID::revoke_attribute(RawOrigin::Signed(alice).into(), alice, name.clone()));
+        assert_changed();
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            bob,
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        assert_changed();
+        assert_ok!(DID::change_owner(RawOrigin::Signed(alice).into(), alice, bob));
+        assert_changed();
+
+        // Reads and failed calls leave it unchanged.
+        let hash = DID::document_hash(&alice);
+        let _ = DID::did_document(&alice);
+        assert!(DID::add_attribute(origin(), alice, name, b"v".to_vec(), None).is_err());
+        assert_eq!(DID::document_hash(&alice), hash);
+        assert_eq!(DID::document_hash(&bob), [0u8; 32]);
+    });
+}
+
+#[test]
+fn validity_boundaries() {
+    new_test_ext().execute_with(|| {
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let charlie = account_key("Charlie");
+        let delegate_type = SIGNING_DELEGATE_TYPE.to_vec();
+        let max_validity: u64 = <Test as crate::Config>::MaxAttributeValidity::get();
+        let add_delegate = |delegate, valid_for| {
+            DID::add_delegate(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                delegate,
+                delegate_type.clone(),
+                KeyType::Sr25519,
+                Some(valid_for),
+            )
+        };
+        let validity_of =
+            |delegate| DID::delegate_of(alice, (delegate_type.clone(), delegate)).unwrap().validity;
+
+        // Expiries up to the maximum block number are accepted, one more block overflows.
+        System::set_block_number(u64::MAX - max_validity);
+        assert_noop!(
+            add_delegate(bob, Validity::For(max_validity + 1)),
+            Error::<Test>::ValidityOverflow
+        );
+        assert_noop!(
+            DID::validate_input(b"name", Some(b"value"), Some(Validity::For(u64::MAX))),
+            Error::<Test>::ValidityOverflow
+        );
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            b"name".to_vec(),
+            b"value".to_vec(),
+            Some(Validity::For(max_validity))
+        ));
+        assert_eq!(DID::attribute_and_id(&alice, b"name").unwrap().0.validity, u64::MAX);
+        assert_ok!(add_delegate(bob, Validity::For(max_validity)));
+        assert_eq!(validity_of(bob), u64::MAX);
+
+        // Attributes can not outlive `MaxAttributeValidity`.
+        System::set_block_number(1);
+        assert_ok!(DID::validate_input(b"name", Some(b"value"), Some(Validity::Never)));
+        assert_noop!(
+            DID::add_attribute(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                b"other".to_vec(),
+                b"value".to_vec(),
+                Some(Validity::Never)
+            ),
+            Error::<Test>::AttributeValidityTooLong
+        );
+
+        // Delegates that never expire are still valid at the last block, even once renewed.
+        assert_ok!(add_delegate(charlie, Validity::Never));
+        assert_eq!(validity_of(charlie), u64::MAX);
+        assert_ok!(DID::renew_delegates(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            vec![(charlie, delegate_type.clone(), 10)]
+        ));
+        assert_eq!(validity_of(charlie), u64::MAX);
+        System::set_block_number(u64::MAX - 1);
+        assert_ok!(DID::valid_delegate(&alice, &delegate_type, &charlie));
+    });
+}
+
+#[test]
+fn handles_are_unique_and_hold_a_deposit() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        Balances::make_free_balance_be(&alice, 100);
+        Balances::make_free_balance_be(&bob, 100);
+        let claim = |who, handle: &[u8]| {
+            DID::claim_handle(RawOrigin::Signed(who).into(), who, handle.to_vec())
+        };
+
+        assert_noop!(claim(alice, b""), Error::<Test>::InvalidHandle);
+        assert_noop!(claim(alice, b"Alice"), Error::<Test>::InvalidHandle);
+        assert_noop!(claim(alice, b"al ice"), Error::<Test>::InvalidHandle);
+        assert_noop!(claim(alice, &[b'a'; 17]), Error::<Test>::HandleTooLong);
+        assert_ok!(claim(alice, b"alice_01"));
+        System::assert_last_event(crate::Event::HandleClaimed(alice, b"alice_01".to_vec()).into());
+        assert_eq!(Balances::reserved_balance(alice), 20);
+        assert_eq!(DID::identity_by_handle(b"alice_01"), Some(alice));
+        assert_eq!(DID::identity_by_handle(b"alice"), None);
+
+        assert_noop!(claim(bob, b"alice_01"), Error::<Test>::HandleTaken);
+        assert_noop!(claim(alice, b"alice-02"), Error::<Test>::HandleAlreadySet);
+        assert_noop!(
+            DID::claim_handle(RawOrigin::Signed(bob).into(), alice, b"bob".to_vec()),
+            Error::<Test>::NotOwner
+        );
+
+        assert_ok!(DID::unclaim_handle(RawOrigin::Signed(alice).into(), alice));
+        assert_eq!(Balances::reserved_balance(alice), 0);
+        assert_eq!(DID::identity_by_handle(b"alice_01"), None);
+        assert_noop!(
+            DID::unclaim_handle(RawOrigin::Signed(alice).into(), alice),
+            Error::<Test>::NoHandle
+        );
+
+        // Released handles can be claimed by another identity.
+        assert_ok!(claim(bob, b"alice_01"));
+        assert_eq!(DID::identity_by_handle(b"alice_01"), Some(bob));
+        assert_eq!(DID::handle_of(bob).unwrap().to_vec(), b"alice_01".to_vec());
+    });
+}
+
+#[test]
+fn registered_issuers_issue_credentials_on_chain() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let issuer = account_key("Alice");
+        let subject = account_key("Bob");
+        let schema_id = sp_io::hashing::blake2_256(b"KycCredential");
+        let claim_hash = sp_io::hashing::blake2_256(b"{\"kyc\":true}");
+        Balances::make_free_balance_be(&issuer, 100);
+        let issue = |expiry| {
+            let origin = RawOrigin::Signed(issuer).into();
+            DID::issue_credential(origin, subject, schema_id, claim_hash, expiry)
+        };
+
+        assert_noop!(issue(Validity::For(10)), Error::<Test>::IdentityNotRegistered);
+        assert_ok!(DID::register_did(RawOrigin::Signed(issuer).into()));
+        assert_noop!(issue(Validity::For(0)), Error::<Test>::ZeroValidity);
+        assert_ok!(issue(Validity::For(10)));
+        assert_noop!(issue(Validity::Never), Error::<Test>::CredentialExists);
+
+        let credentials = DID::credentials_of(&subject);
+        assert_eq!(credentials.len(), 1);
+        let (id, credential) = credentials[0].clone();
+        System::assert_last_event(crate::Event::CredentialIssued(issuer, subject, id).into());
+        assert_eq!(credential.issuer, issuer);
+        assert_eq!(credential.schema_id, schema_id);
+        assert_eq!(credential.claim_hash, claim_hash);
+        assert_eq!(credential.expiry, 11);
+        assert_eq!(DID::credential_status(&subject, &id), Some(AttestationStatus::Valid));
+        assert_eq!(DID::credential_status(&issuer, &id), None);
+
+        assert_eq!(Balances::reserved_balance(issuer), 10);
+
+        System::set_block_number(11);
+        assert_eq!(DID::credential_status(&subject, &id), Some(AttestationStatus::Expired));
+
+        // Credentials of a subject are bounded, and revoked by their issuer or subject.
+        let other = sp_io::hashing::blake2_256(b"AmlCredential");
+        let issue_schema = |schema_id| {
+            let origin = RawOrigin::Signed(issuer).into();
+            DID::issue_credential(origin, subject, schema_id, claim_hash, Validity::Never)
+        };
+        assert_ok!(issue_schema(other));
+        assert_noop!(issue_schema([3u8; 32]), Error::<Test>::TooManyCredentials);
+        let mallory = account_key("Mallory");
+        assert_noop!(
+            DID::revoke_credential(RawOrigin::Signed(mallory).into(), subject, id),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(DID::revoke_credential(RawOrigin::Signed(issuer).into(), subject, id));
+        System::assert_last_event(crate::Event::CredentialRevoked(issuer, subject, id).into());
+        assert_eq!(DID::credential_status(&subject, &id), None);
+        assert_noop!(
+            DID::revoke_credential(RawOrigin::Signed(issuer).into(), subject, id),
+            Error::<Test>::UnknownCredential
+        );
+        let (other_id, _) = DID::credentials_of(&subject)[0].clone();
+        assert_ok!(DID::revoke_credential(RawOrigin::Signed(subject).into(), subject, other_id));
+        assert_eq!(DID::credential_count(subject), 0);
+        assert_eq!(Balances::reserved_balance(issuer), 0);
+        assert_ok!(issue(Validity::For(10)));
+        let (id, _) = DID::credentials_of(&subject)[0].clone();
+
+        // Suspended issuers can not issue, and their credentials are disputed.
+        assert_ok!(DID::report_issuer_misbehavior(RawOrigin::Root.into(), issuer, [7u8; 32]));
+        assert_eq!(DID::credential_status(&subject, &id), Some(AttestationStatus::Disputed));
+        assert_noop!(
+            DID::issue_credential(
+                RawOrigin::Signed(issuer).into(),
+                issuer,
+                schema_id,
+                claim_hash,
+                Validity::Never
+            ),
+            Error::<Test>::IssuerSuspended
+        );
+    });
+}
+
+#[test]
+fn third_party_claims_await_subject_consent() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        RequireAttestationConsent::set(true);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let name = b"kyc/level".to_vec();
+        Balances::make_free_balance_be(&alice, 100);
+        assert_ok!(DID::claim_namespace(RawOrigin::Signed(alice).into(), alice, b"kyc/".to_vec()));
+        assert_ok!(DID::register_did(RawOrigin::Signed(alice).into()));
+        let kyc = b"kyc/".to_vec();
+        assert_ok!(DID::set_namespace_opt_in(RawOrigin::Signed(bob).into(), bob, kyc, true));
+
+        // Claims of the owner about its own identity apply at once.
+        let attest = |identity, value: &[u8]| {
+            let origin = RawOrigin::Signed(alice).into();
+            DID::add_attribute(origin, identity, name.clone(), value.to_vec(), None)
+        };
+        assert_ok!(attest(alice, b"3"));
+        assert_ok!(DID::valid_attribute(&alice, &name, b"3"));
+
+        assert_ok!(attest(bob, b"1"));
+        assert_ok!(attest(bob, b"2"));
+        assert_noop!(attest(bob, b"2"), Error::<Test>::AttestationAlreadyPending);
+        assert_noop!(attest(bob, b"4"), Error::<Test>::TooManyPendingAttestations);
+        assert!(DID::valid_attribute(&bob, &name, b"2").is_err());
+        // The namespace deposit and one `AttestationDeposit` per pending claim.
+        assert_eq!(Balances::reserved_balance(alice), 60);
+        assert_eq!(DID::pending_attestation_count(bob), 2);
+        let pending = DID::pending_attestations(&bob);
+        assert_eq!(pending.len(), 2);
+        let id_of = |value: &[u8]| {
+            pending
+                .iter()
+                .find(|(_, attestation)| {
+                    matches!(attestation, PendingAttestation::Attribute { value: v, .. } if v == value)
+                })
+                .map(|(id, _)| *id)
+                .unwrap()
+        };
+        let (first, second) = (id_of(b"1"), id_of(b"2"));
+        System::assert_last_event(crate::Event::AttestationPending(bob, alice, second).into());
+
+        assert_noop!(
+            DID::accept_attestation(RawOrigin::Signed(alice).into(), bob, second),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(DID::reject_attestation(RawOrigin::Signed(bob).into(), bob, first));
+        assert_ok!(DID::accept_attestation(RawOrigin::Signed(bob).into(), bob, second));
+        System::assert_last_event(crate::Event::AttestationAccepted(bob, second).into());
+        assert_ok!(DID::valid_attribute(&bob, &name, b"2"));
+        assert!(DID::pending_attestations(&bob).is_empty());
+        assert_eq!(DID::pending_attestation_count(bob), 0);
+        assert_eq!(Balances::reserved_balance(alice), 50);
+        assert_noop!(
+            DID::accept_attestation(RawOrigin::Signed(bob).into(), bob, first),
+            Error::<Test>::UnknownAttestation
+        );
+
+        // Credentials are stored once accepted, under the id they were issued with.
+        let (schema_id, claim_hash) = ([1u8; 32], [2u8; 32]);
+        assert_ok!(DID::issue_credential(
+            RawOrigin::Signed(alice).into(),
+            bob,
+            schema_id,
+            claim_hash,
+            Validity::Never
+        ));
+        assert!(DID::credentials_of(&bob).is_empty());
+        assert_eq!(Balances::reserved_balance(alice), 55);
+        let (id, _) = DID::pending_attestations(&bob)[0].clone();
+        assert_ok!(DID::accept_attestation(RawOrigin::Signed(bob).into(), bob, id));
+        // The attestation deposit is returned and the credential deposit reserved.
+        assert_eq!(Balances::reserved_balance(alice), 60);
+        assert_eq!(DID::credential_status(&bob, &id), Some(AttestationStatus::Valid));
+        System::assert_has_event(crate::Event::CredentialIssued(alice, bob, id).into());
+        RequireAttestationConsent::set(false);
+    });
+}
+
+#[test]
+fn revoke_all_delegates_of_a_type() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let session_key = b"SessionKey".to_vec();
+        let keys: Vec<AccountId> = ["Bob", "Charlie", "Dave"].map(account_key).to_vec();
+        for key in &keys {
+            assert_ok!(DID::add_delegate(
+                RawOrigin::Signed(alice).into(),
+                alice,
+                *key,
+                session_key.clone(),
+                KeyType::Sr25519,
+                None
+            ));
+        }
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            keys[0],
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        let eve = account_key("Eve");
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            eve,
+            session_key.clone(),
+            KeyType::Sr25519,
+            Some(Validity::For(1))
+        ));
+        let origin = RawOrigin::Signed(alice).into();
+        assert_ok!(DID::revoke_delegate(origin, alice, session_key.clone(), keys[2]));
+        let indexed = || {
+            crate::DelegatesOfType::<Test>::iter_key_prefix((alice, session_key.clone())).count()
+        };
+        assert_eq!(indexed(), 3);
+
+        let revoke_all = |delegate_count| {
+            let origin = RawOrigin::Signed(alice).into();
+            DID::revoke_delegates_of_type(origin, alice, session_key.clone(), delegate_count)
+        };
+        // Delegates revoked one by one leave the bound, expired ones count in it.
+        System::set_block_number(2);
+        assert_noop!(revoke_all(2), Error::<Test>::DelegateCountTooLow);
+        let call = crate::Call::<Test>::revoke_delegates_of_type {
+            identity: alice,
+            delegate_type: session_key.clone(),
+            delegate_count: 3,
+        };
+        assert_eq!(call.get_dispatch_info().class, DispatchClass::Operational);
+
+        assert_ok!(revoke_all(3));
+        let event = crate::Event::DelegatesOfTypeRevoked(alice, session_key.clone(), 2);
+        System::assert_last_event(event.into());
+        for key in &keys {
+            assert!(DID::valid_delegate(&alice, &session_key, key).is_err());
+        }
+        // The expired delegate is pruned along with the revoked ones.
+        assert_eq!(indexed(), 0);
+        // Other delegate types are unaffected.
+        assert_ok!(DID::valid_delegate(&alice, SIGNING_DELEGATE_TYPE, &keys[0]));
+    });
+}
+
+#[test]
+fn original_layout_is_translated_on_upgrade() {
+    use frame_support::{
+        storage::{migration::put_storage_value, unhashed},
+        traits::{Hooks, StorageVersion},
+        Blake2_128Concat, StorageHasher, Twox64Concat,
+    };
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let charlie = account_key("Charlie");
+        let delegate_key = Blake2_128Concat::hash(&(alice, b"SessionKey".to_vec(), bob).encode());
+        put_storage_value(b"DID", b"DelegateOf", &delegate_key, Some(100u64));
+        crate::OwnerOf::<Test>::insert(charlie, alice);
+        let nonce_key = Twox64Concat::hash(&(alice, b"name".to_vec()).encode());
+        put_storage_value(b"DID", b"AttributeNonce", &nonce_key, 3u64);
+        let attribute = crate::migrations::v1::OldAttribute::<u64, u64> {
+            name: b"name".to_vec(),
+            value: b"value".to_vec(),
+            validity: 100,
+            creation: 0,
+            nonce: 2,
+        };
+        let id = DID::attribute_id(&alice, b"name", 2);
+        unhashed::put(&crate::AttributeOf::<Test>::hashed_key_for((alice, id)), &attribute);
+        let hash = sp_io::hashing::blake2_256(b"contract.pdf");
+        let anchor_key = Blake2_128Concat::hash(&hash.encode());
+        put_storage_value(b"DID", b"Anchors", &anchor_key, (alice, 1u64, 0u64));
+
+        StorageVersion::new(0).put::<DID>();
+        DID::on_runtime_upgrade();
+        // The values of the original layout are migrated in later blocks.
+        assert!(DID::valid_delegate(&alice, b"SessionKey", &bob).is_err());
+        let mut block = 2;
+        while DID::migration_cursor().is_some() && block < 40 {
+            DID::on_initialize(block);
+            block += 1;
+        }
+        assert_eq!(DID::migration_cursor(), None);
+        let delegate = DID::delegate_of(alice, (b"SessionKey".to_vec(), bob)).unwrap();
+        assert_eq!((delegate.validity, delegate.key_type), (100, KeyType::Sr25519));
+        assert_ok!(DID::valid_delegate(&alice, b"SessionKey", &bob));
+        assert_eq!(DID::delegates_of(&alice, None, 10).items.len(), 1);
+        assert_eq!(DID::identities_of_owner(&alice, None, 10).items, vec![charlie]);
+        assert_eq!(DID::nonces_of(&alice), vec![(b"name".to_vec(), 3)]);
+        let (attribute, _) = DID::attribute_and_id(&alice, b"name").unwrap();
+        assert_eq!(attribute.value, b"value".to_vec());
+        assert_eq!(attribute.kind, crate::types::AttributeKind::Plain);
+        assert_eq!(DID::proof_of_existence(alice, hash), Some((1, 0)));
+    });
+}
+
+#[test]
+fn original_release_upgrades_through_every_migration() {
+    use crate::types::WatchedItem;
+    use frame_support::{
+        storage::{migration::put_storage_value, unhashed},
+        traits::{GetStorageVersion, Hooks, ReservableCurrency, StorageVersion},
+        Blake2_128Concat, StorageHasher, Twox64Concat,
+    };
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let [alice, bob, charlie, dave, eve, ferdie] =
+            ["Alice", "Bob", "Charlie", "Dave", "Eve", "Ferdie"].map(account_key);
+        for (delegate_type, delegate) in [(b"SessionKey", bob), (b"OtherType!", charlie)] {
+            let key = Blake2_128Concat::hash(&(alice, delegate_type.to_vec(), delegate).encode());
+            put_storage_value(b"DID", b"DelegateOf", &key, Some(100u64));
+        }
+        let nonce_key = Twox64Concat::hash(&(alice, b"name".to_vec()).encode());
+        put_storage_value(b"DID", b"AttributeNonce", &nonce_key, 1u64);
+        let attribute = crate::migrations::v1::OldAttribute::<u64, u64> {
+            name: b"name".to_vec(),
+            value: b"value".to_vec(),
+            validity: 100,
+            creation: 0,
+            nonce: 0,
+        };
+        let id = DID::attribute_id(&alice, b"name", 0);
+        unhashed::put(&crate::AttributeOf::<Test>::hashed_key_for((alice, id)), &attribute);
+        // More watchers and alerts than their bounds, stored as unbounded vectors.
+        for watcher in [dave, eve, ferdie] {
+            Balances::make_free_balance_be(&watcher, 100);
+            assert_ok!(<Balances as ReservableCurrency<_>>::reserve(&watcher, 5));
+            crate::WatcherDeposits::<Test>::insert(alice, watcher, 5);
+        }
+        let watchers_key = crate::Watchers::<Test>::hashed_key_for(alice);
+        unhashed::put(&watchers_key, &vec![dave, eve, ferdie]);
+        let item = WatchedItem::Attribute { name: b"name".to_vec() };
+        let alerts = vec![(alice, item.clone(), 60u64); 17];
+        unhashed::put(&crate::ExpiryAlerts::<Test>::hashed_key_for(50), &alerts);
+        // A presentation nonce expired before the upgrade, and one still valid.
+        crate::PresentationNonces::<Test>::insert(alice, [0u8; 32], 0);
+        crate::PresentationNonces::<Test>::insert(alice, [1u8; 32], 30);
+        // Vouches for two rescuers, before they were counted.
+        for rescuer in [dave, eve] {
+            let vouches: frame_support::BoundedVec<AccountId, _> = vec![bob].try_into().unwrap();
+            crate::RecoveryVouches::<Test>::insert(alice, rescuer, vouches);
+        }
+
+        StorageVersion::new(0).put::<DID>();
+        DID::on_runtime_upgrade();
+        assert!(DID::migration_cursor().is_some());
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        let session_keys = b"SessionKey".to_vec();
+        assert_noop!(
+            DID::add_own_attribute(signed(alice), b"x".to_vec(), b"y".to_vec(), None),
+            Error::<Test>::MigrationOngoing
+        );
+
+        // Revocations are accepted during the migration, once the delegates are re-keyed and
+        // before they are indexed by type.
+        let mut block = 2;
+        while DID::delegate_of(alice, (session_keys.clone(), bob)).is_none() && block < 40 {
+            DID::on_initialize(block);
+            block += 1;
+        }
+        assert!(DID::migration_cursor().is_some());
+        assert_ok!(DID::revoke_delegates_of_type(signed(alice), alice, session_keys.clone(), 2));
+        assert!(DID::valid_delegate(&alice, &session_keys, &bob).is_err());
+
+        while DID::migration_cursor().is_some() && block < 40 {
+            DID::on_initialize(block);
+            block += 1;
+        }
+        assert_eq!(DID::migration_cursor(), None);
+        System::assert_last_event(crate::Event::MigrationCompleted.into());
+        assert_eq!(DID::on_chain_storage_version(), StorageVersion::new(7));
+
+        assert_ok!(DID::valid_attribute(&alice, b"name", b"value"));
+        assert!(DID::attribute_metadata(&alice, b"name").is_some());
+        assert_ok!(DID::valid_delegate(&alice, b"OtherType!", &charlie));
+        let other_type = (alice, b"OtherType!".to_vec());
+        let indexed: Vec<_> = crate::DelegatesOfType::<Test>::iter_key_prefix(other_type).collect();
+        assert_eq!(indexed, vec![charlie]);
+        assert_eq!(DID::watchers_of(alice), vec![dave, eve]);
+        assert_eq!(Balances::reserved_balance(ferdie), 0);
+        System::assert_has_event(crate::Event::WatcherRemoved(alice, ferdie).into());
+        assert_eq!(DID::expiry_alerts(50).len(), 16);
+        System::assert_has_event(crate::Event::ExpiryAlertDropped(alice, item, 60).into());
+        assert_eq!(DID::presentation_nonce(alice, [0u8; 32]), None);
+        assert_eq!(DID::presentation_nonce_expiries(30).into_inner(), vec![(alice, [1u8; 32])]);
+        assert_eq!(DID::rescuer_count(alice), 2);
+        assert!(DID::valid_delegate(&alice, &session_keys, &bob).is_err());
+        assert_ok!(DID::do_try_state());
+        assert_ok!(DID::add_own_attribute(signed(alice), b"x".to_vec(), b"y".to_vec(), None));
+    });
+}
+
+#[test]
+fn delegates_are_indexed_by_type_in_sequence_after_metadata() {
+    use crate::migrations::{BackfillAttributeMetadata, IndexDelegatesByType, SteppedMigration};
+
+    let alice = account_key("Alice");
+    let delegates: Vec<AccountId> = ["Bob", "Charlie", "Dave"].map(account_key).to_vec();
+    let config = crate::GenesisConfig::<Test> {
+        delegates: delegates
+            .iter()
+            .map(|delegate| (alice, b"SessionKey".to_vec(), *delegate, KeyType::Sr25519, 100))
+            .collect(),
+        attributes: vec![(alice, b"name".to_vec(), b"value".to_vec(), Default::default(), 100, 0)],
+        ..Default::default()
+    };
+    let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    config.assimilate_storage(&mut storage).unwrap();
+
+    sp_io::TestExternalities::from(storage).execute_with(|| {
+        // Drop the index, as on chains created before it.
+        let _ = crate::DelegatesOfType::<Test>::clear(u32::MAX, None);
+        type Migration = (BackfillAttributeMetadata<Test>, IndexDelegatesByType<Test>);
+
+        let mut cursor = None;
+        let mut steps = 0;
+        loop {
+            let (next, _) = Migration::step(cursor, 2);
+            steps += 1;
+            match next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        // One step for the attribute, then two for three delegates.
+        assert_eq!(steps, 3);
+        assert!(DID::attribute_metadata(&alice, b"name").is_some());
+        let indexed: Vec<AccountId> =
+            crate::DelegatesOfType::<Test>::iter_key_prefix((alice, b"SessionKey".to_vec()))
+                .collect();
+        assert_eq!(indexed.len(), 3);
+        assert!(delegates.iter().all(|delegate| indexed.contains(delegate)));
+    });
+}
+
+#[test]
+fn banned_attribute_names_can_not_be_written() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let add = |name: &[u8]| {
+            let origin = RawOrigin::Signed(alice).into();
+            DID::add_attribute(origin, alice, name.to_vec(), b"value".to_vec(), None)
+        };
+        assert_ok!(add(b"leak/1"));
+
+        assert_noop!(
+            DID::ban_attribute_name(RawOrigin::Signed(alice).into(), b"leak/".to_vec()),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_ok!(DID::ban_attribute_name(RawOrigin::Root.into(), b"leak/".to_vec()));
+        System::assert_last_event(crate::Event::AttributeNameBanned(b"leak/".to_vec()).into());
+        assert_noop!(
+            DID::ban_attribute_name(RawOrigin::Root.into(), b"leak/".to_vec()),
+            Error::<Test>::AlreadyBanned
+        );
+        assert_eq!(DID::banned_names(), vec![b"leak/".to_vec()]);
+
+        assert_noop!(add(b"leak/2"), Error::<Test>::BannedName);
+        assert_ok!(add(b"leaky"));
+        // Existing attributes can still be taken down.
+        assert_ok!(DID::valid_attribute(&alice, b"leak/1", b"value"));
+        assert_ok!(DID::delete_attribute(RawOrigin::Signed(alice).into(), alice, b"leak/1".to_vec()));
+
+        assert_ok!(DID::unban_attribute_name(RawOrigin::Root.into(), b"leak/".to_vec()));
+        assert_noop!(
+            DID::unban_attribute_name(RawOrigin::Root.into(), b"leak/".to_vec()),
+            Error::<Test>::NotBanned
+        );
+        assert_ok!(add(b"leak/2"));
+        assert!(!crate::Call::<Test>::ban_attribute_name { name_or_prefix: vec![] }.is_did_management());
+    });
+}
+
+#[test]
+fn attribute_digests_follow_the_current_attribute() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let name = b"name".to_vec();
+        let name_hash = sp_io::hashing::blake2_256(&name);
+        let origin = || RawOrigin::Signed(alice).into();
+
+        assert_ok!(DID::add_attribute(origin(), alice, name.clone(), b"v1".to_vec(), None));
+        let (value_hash, validity) = DID::attribute_digest(alice, name_hash).unwrap();
+        assert_eq!(value_hash, sp_io::hashing::blake2_256(b"v1"));
+        assert_eq!(validity, DID::attribute_and_id(&alice, &name).unwrap().0.validity);
+        assert_ok!(DID::valid_attribute(&alice, &name, b"v1"));
+        assert_noop!(DID::valid_attribute(&alice, &name, b"v2"), Error::<Test>::InvalidAttribute);
+
+        assert_ok!(DID::revoke_attribute(origin(), alice, name.clone()));
+        assert_eq!(DID::attribute_digest(alice, name_hash).unwrap().1, 1);
+        assert_noop!(DID::valid_attribute(&alice, &name, b"v1"), Error::<Test>::InvalidAttribute);
+
+        assert_ok!(DID::delete_attribute(origin(), alice, name.clone()));
+        assert_eq!(DID::attribute_digest(alice, name_hash), None);
+        assert_ok!(DID::add_attribute(origin(), alice, name.clone(), b"v2".to_vec(), None));
+        assert_ok!(DID::valid_attribute(&alice, &name, b"v2"));
+        assert_ok!(DID::do_try_state());
+
+        // Denormalized digests disagreeing with their attribute are caught.
+        crate::AttributeDigests::<Test>::insert(alice, name_hash, ([0u8; 32], 1));
+        assert!(DID::do_try_state().is_err());
+        crate::AttributeDigests::<Test>::remove(alice, name_hash);
+        assert!(DID::do_try_state().is_err());
+    });
+}
+
+#[test]
+fn attribute_digests_are_backfilled() {
+    use crate::migrations::{BackfillAttributeDigests, SteppedMigration};
+
+    let alice = account_key("Alice");
+    let config = crate::GenesisConfig::<Test> {
+        attributes: vec![
+            (alice, b"a".to_vec(), b"old".to_vec(), Default::default(), 100, 0),
+            (alice, b"a".to_vec(), b"new".to_vec(), Default::default(), 100, 1),
+            (alice, b"b".to_vec(), b"value".to_vec(), Default::default(), 100, 0),
+        ],
+        nonces: vec![(alice, b"a".to_vec(), 2)],
+        ..Default::default()
+    };
+    let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    config.assimilate_storage(&mut storage).unwrap();
+
+    sp_io::TestExternalities::from(storage).execute_with(|| {
+        System::set_block_number(1);
+        // Drop the digests, as on chains created before them.
+        let _ = crate::AttributeDigests::<Test>::clear(u32::MAX, None);
+        assert!(DID::valid_attribute(&alice, b"b", b"value").is_err());
+
+        let mut cursor = None;
+        while let (Some(next), _) = BackfillAttributeDigests::<Test>::step(cursor, 1) {
+            cursor = Some(next);
+        }
+        assert_ok!(DID::valid_attribute(&alice, b"a", b"new"));
+        assert_ok!(DID::valid_attribute(&alice, b"b", b"value"));
+        assert!(DID::valid_attribute(&alice, b"a", b"old").is_err());
+        assert_ok!(DID::do_try_state());
+    });
+}
+
+#[test]
+fn attribute_keys_use_the_key_hasher() {
+    use sp_runtime::traits::{BlakeTwo256, Hash};
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let name = b"name".to_vec();
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            name.clone(),
+            b"value".to_vec(),
+            None
+        ));
+
+        // Verifiers recompute the keys by hashing the SCALE encoded `(identity, name, nonce)`.
+        let (_, id) = DID::attribute_and_id(&alice, &name).unwrap();
+        assert_eq!(id, DID::attribute_id(&alice, &name, 0));
+        assert_eq!(id, BlakeTwo256::hash_of(&(alice, name.clone(), 0u64)).0);
+        assert_eq!(DID::key_hash(&name), BlakeTwo256::hash(&name).0);
+        assert!(DID::attribute_digest(alice, DID::key_hash(&name)).is_some());
+    });
+}
+
+#[test]
+fn test_utils_build_realistic_did_state() {
+    use crate::did::DidProvider;
+    use crate::test_utils::{sign_attribute_tx, DidBuilder};
+
+    // A consuming pallet only sees the `DidProvider` trait.
+    fn is_signer<P: DidProvider<AccountId>>(identity: &AccountId, who: &AccountId) -> bool {
+        P::valid_delegate(identity, SIGNING_DELEGATE_TYPE, who).is_ok()
+    }
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob_pair = account_pair("Bob");
+        let bob = bob_pair.public();
+        let charlie = account_key("Charlie");
+
+        assert_ok!(DidBuilder::<Test>::new(alice)
+            .delegate(bob, SIGNING_DELEGATE_TYPE, KeyType::Sr25519, None)
+            .attribute(b"name", b"Alice", None)
+            .owner(charlie)
+            .build());
+        assert!(is_signer::<DID>(&alice, &bob));
+        assert_eq!(<DID as DidProvider<AccountId>>::identity_owner(&alice), charlie);
+        assert_ok!(<DID as DidProvider<AccountId>>::valid_attribute(&alice, b"name", b"Alice"));
+
+        let transaction = sign_attribute_tx::<Test>(&bob_pair, &alice, b"email", b"a@b.c", 100);
+        assert_ok!(DID::execute(RawOrigin::Signed(bob).into(), transaction));
+        assert_ok!(<DID as DidProvider<AccountId>>::valid_attribute(&alice, b"email", b"a@b.c"));
+    });
+}
+
+#[test]
+fn dormant_identities_are_reaped_for_a_bounty() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        Balances::make_free_balance_be(&alice, 100);
+        Balances::make_free_balance_be(&bob, 100);
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        let add = |name: &[u8], value: &[u8]| {
+            DID::add_attribute(signed(alice), alice, name.to_vec(), value.to_vec(), None)
+        };
+        assert_ok!(add(b"name", b"Alice"));
+        assert_ok!(DID::set_profile(signed(alice), alice, b"Alice".to_vec(), None, None));
+        assert_ok!(DID::claim_handle(signed(alice), alice, b"alice".to_vec()));
+        let (charlie, dave) = (account_key("Charlie"), account_key("Dave"));
+        assert_ok!(DID::add_delegate(
+            signed(alice),
+            alice,
+            charlie,
+            b"SessionKey".to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        Balances::make_free_balance_be(&charlie, 100);
+        assert_ok!(DID::register_did(signed(charlie)));
+        assert_ok!(DID::issue_credential(signed(charlie), alice, [1; 32], [2; 32], Validity::Never));
+        Balances::make_free_balance_be(&dave, 100);
+        assert_ok!(DID::watch_identity(signed(dave), alice));
+        assert_eq!(DID::last_activity(&alice), Some(1));
+
+        assert_noop!(DID::flag_dormant(signed(bob), alice), Error::<Test>::DormancyDisabled);
+        DormancyPeriod::set(Some(100));
+        System::set_block_number(100);
+        assert_noop!(DID::flag_dormant(signed(bob), alice), Error::<Test>::IdentityActive);
+
+        // Any activity clears the flag.
+        System::set_block_number(101);
+        assert_eq!(DID::activity_status(&alice), ActivityStatus::Inactive);
+        assert_ok!(DID::flag_dormant(signed(bob), alice));
+        System::assert_last_event(crate::Event::IdentityDormant(alice, 101).into());
+        assert_noop!(DID::flag_dormant(signed(bob), alice), Error::<Test>::AlreadyDormant);
+        assert_ok!(add(b"email", b"a@b.c"));
+        assert_eq!(DID::activity_status(&alice), ActivityStatus::Active);
+        assert_noop!(DID::reap_identity(signed(bob), alice, 2, 1), Error::<Test>::NotDormant);
+
+        System::set_block_number(201);
+        assert_ok!(DID::flag_dormant(signed(bob), alice));
+        assert_eq!(DID::activity_status(&alice), ActivityStatus::Dormant(201));
+        System::set_block_number(210);
+        assert_noop!(DID::reap_identity(signed(bob), alice, 2, 1), Error::<Test>::GracePeriodOngoing);
+        System::set_block_number(211);
+        assert_noop!(
+            DID::reap_identity(signed(bob), alice, 1, 1),
+            Error::<Test>::AttributeCountTooLow
+        );
+        assert_noop!(
+            DID::reap_identity(signed(bob), alice, 2, 0),
+            Error::<Test>::DelegateCountTooLow
+        );
+
+        // The bounty comes out of the profile deposit, then the handle deposit.
+        assert_ok!(DID::reap_identity(signed(bob), alice, 2, 1));
+        System::assert_last_event(crate::Event::IdentityReaped(alice, bob, 15).into());
+        assert_eq!(Balances::free_balance(bob), 115);
+        assert_eq!(Balances::reserved_balance(alice), 0);
+        assert_eq!(Balances::free_balance(alice), 85);
+        assert!(DID::attribute_and_id(&alice, b"name").is_none());
+        assert_noop!(
+            DID::valid_attribute(&alice, b"email", b"a@b.c"),
+            Error::<Test>::InvalidAttribute
+        );
+        assert_eq!(DID::profile_of(alice), None);
+        assert_eq!(DID::identity_by_handle(b"alice"), None);
+        assert_eq!(DID::activity_status(&alice), ActivityStatus::Active);
+        assert_eq!(DID::nonce_of(alice, b"name".to_vec()), 1);
+        // The delegates, credentials and watchers are removed, their deposits unreserved.
+        assert!(DID::valid_delegate(&alice, b"SessionKey", &charlie).is_err());
+        let session_keys = (alice, b"SessionKey".to_vec());
+        assert_eq!(crate::DelegatesOfType::<Test>::iter_prefix(session_keys).count(), 0);
+        assert_eq!(DID::credentials_of(&alice), vec![]);
+        assert_eq!(DID::credential_count(alice), 0);
+        assert_eq!(DID::watchers_of(alice), vec![]);
+        assert_eq!(Balances::reserved_balance(charlie), 0);
+        assert_eq!(Balances::reserved_balance(dave), 0);
+        assert_eq!(DID::storage_usage(alice), 0);
+        // Reaped identities are not brand-new again.
+        assert!(!DID::is_feeless_first_write(&alice, &crate::Call::register_did {}));
+        DormancyPeriod::set(None);
+    });
+}
+
+#[test]
+fn third_party_updates_do_not_count_as_activity() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        let name = b"kyc/level".to_vec();
+        let add_own = |name: &[u8], value: &[u8]| {
+            DID::add_attribute(signed(alice), alice, name.to_vec(), value.to_vec(), None)
+        };
+        Balances::make_free_balance_be(&bob, 100);
+        assert_ok!(add_own(b"name", b"Alice"));
+        assert_ok!(DID::claim_namespace(signed(bob), bob, b"kyc/".to_vec()));
+        assert_ok!(DID::set_namespace_opt_in(signed(alice), alice, b"kyc/".to_vec(), true));
+        DormancyPeriod::set(Some(100));
+
+        // Bob attests Alice, which is still inactive since her own update.
+        System::set_block_number(50);
+        assert_ok!(DID::add_attribute(signed(bob), alice, name.clone(), b"1".to_vec(), None));
+        assert_eq!(DID::updated_by(&alice).map(|(who, _, _)| who), Some(bob));
+        assert_eq!(DID::last_activity(&alice), Some(1));
+        System::set_block_number(101);
+        assert_eq!(DID::activity_status(&alice), ActivityStatus::Inactive);
+        assert_ok!(DID::flag_dormant(signed(bob), alice));
+        assert_ok!(DID::add_attribute(signed(bob), alice, name, b"2".to_vec(), None));
+        assert_eq!(DID::activity_status(&alice), ActivityStatus::Dormant(101));
+
+        assert_ok!(add_own(b"email", b"a@b.c"));
+        assert_eq!(DID::activity_status(&alice), ActivityStatus::Active);
+        assert_eq!(DID::last_activity(&alice), Some(101));
+        DormancyPeriod::set(None);
+    });
+}
+
+#[test]
+fn genesis_identities_become_dormant() {
+    let alice = account_key("Alice");
+    let config = crate::GenesisConfig::<Test> {
+        attributes: vec![(alice, b"name".to_vec(), b"Alice".to_vec(), Default::default(), 100, 0)],
+        ..Default::default()
+    };
+    let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    config.assimilate_storage(&mut storage).unwrap();
+
+    sp_io::TestExternalities::from(storage).execute_with(|| {
+        assert_eq!(DID::last_activity(&alice), Some(0));
+        DormancyPeriod::set(Some(100));
+        System::set_block_number(100);
+        assert_eq!(DID::activity_status(&alice), ActivityStatus::Inactive);
+        assert_ok!(DID::flag_dormant(RawOrigin::Signed(account_key("Bob")).into(), alice));
+        DormancyPeriod::set(None);
+    });
+}
+
+#[test]
+fn contract_delegates_authorize_callers() {
+    use crate::types::CONTRACT_DELEGATE_TYPE;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let contract = account_key("Contract");
+        let bob = account_key("Bob");
+        ContractPolicy::set(vec![(bob, b"pay".to_vec())]);
+
+        assert_noop!(
+            DID::valid_contract_delegate(&alice, &contract, &bob, b"pay"),
+            Error::<Test>::InvalidDelegate
+        );
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            contract,
+            CONTRACT_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        assert_ok!(DID::valid_contract_delegate(&alice, &contract, &bob, b"pay"));
+        assert_noop!(
+            DID::valid_contract_delegate(&alice, &contract, &bob, b"sign"),
+            Error::<Test>::ContractNotAuthorized
+        );
+        assert_noop!(
+            DID::valid_contract_delegate(&alice, &contract, &alice, b"pay"),
+            Error::<Test>::ContractNotAuthorized
+        );
+
+        // Contract accounts are not verification methods, even as signing delegates.
+        assert_ok!(DID::add_delegate(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            contract,
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        let document = DID::did_document(&alice);
+        assert!(document.verification_methods.is_empty());
+        assert!(document.authentication.is_empty());
+        ContractPolicy::set(vec![]);
+    });
+}
+
+#[test]
+fn extension_queries_decode_and_execute() {
+    use crate::extension::{self, DidQuery, ATTRIBUTE_VALUE, IS_OWNER, VALID_ATTRIBUTE};
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        assert_ok!(DID::add_attribute(
+            RawOrigin::Signed(alice).into(),
+            alice,
+            b"name".to_vec(),
+            b"Alice".to_vec(),
+            None
+        ));
+
+        let query = |func_id, input: Vec<u8>| {
+            let query = DidQuery::<AccountId>::decode(func_id, &mut &input[..]).unwrap();
+            extension::execute::<Test>(&query)
+        };
+        assert_eq!(query(IS_OWNER, (alice, alice).encode()), true.encode());
+        assert_eq!(query(IS_OWNER, (alice, bob).encode()), false.encode());
+        let claim = (alice, b"name".to_vec(), b"Alice".to_vec()).encode();
+        assert_eq!(query(VALID_ATTRIBUTE, claim), true.encode());
+        let name = (alice, b"name".to_vec()).encode();
+        assert_eq!(query(ATTRIBUTE_VALUE, name.clone()), Some(b"Alice".to_vec()).encode());
+
+        System::set_block_number(10_001);
+        assert_eq!(query(ATTRIBUTE_VALUE, name), None::<Vec<u8>>.encode());
+        assert_eq!(DidQuery::<AccountId>::decode(0, &mut &[][..]), None);
+        assert_eq!(DidQuery::<AccountId>::decode(IS_OWNER, &mut &alice.encode()[..]), None);
+
+        // Inputs and outputs are charged per byte.
+        assert_eq!(extension::bytes_weight(0), Weight::zero());
+        assert_eq!(extension::bytes_weight(100), extension::bytes_weight(1).saturating_mul(100));
+    });
+}
+
+#[cfg(feature = "frontier")]
+#[test]
+fn precompile_calls_decode_and_outputs_are_abi_encoded() {
+    use crate::extension::{self, DidQuery, WEIGHT_PER_BYTE};
+    use crate::precompile::{decode_call, encode_bytes, encode_word, selector};
+    use sp_core::{H160, U256};
+
+    let (identity, account) = (H160::repeat_byte(1), H160::repeat_byte(2));
+    let address = |address: H160| {
+        let mut word = [0u8; 32];
+        word[12..].copy_from_slice(address.as_bytes());
+        word
+    };
+    let mut name = [0u8; 32];
+    name[..4].copy_from_slice(b"name");
+    let call = |signature: &[u8], args: &[[u8; 32]]| {
+        let mut input = selector(signature).to_vec();
+        input.extend(args.concat());
+        input
+    };
+    let decode = |input: &[u8]| decode_call(input, |address| address);
+
+    assert_eq!(selector(b"isOwner(address,address)"), [0x7d, 0xdc, 0x02, 0xd4]);
+    let is_owner = call(b"isOwner(address,address)", &[address(identity), address(account)]);
+    assert_eq!(decode(&is_owner), Some(DidQuery::IsOwner(identity, account)));
+    let signature = b"validDelegate(address,bytes32,address)";
+    let valid_delegate = call(signature, &[address(identity), name, address(account)]);
+    assert_eq!(
+        decode(&valid_delegate),
+        Some(DidQuery::ValidDelegate(identity, b"name".to_vec(), account))
+    );
+    // The zero padding of the names is stripped.
+    let attribute = call(b"attribute(address,bytes32)", &[address(identity), name]);
+    assert_eq!(decode(&attribute), Some(DidQuery::AttributeValue(identity, b"name".to_vec())));
+    assert_eq!(decode(&attribute[..attribute.len() - 1]), None);
+    assert_eq!(decode(&call(b"attribute(address,bytes)", &[address(identity)])), None);
+    assert_eq!(decode(&[0x7d, 0xdc][..]), None);
+
+    // Offset, length, then the value padded to a multiple of 32 bytes.
+    let encoded = encode_bytes(b"value");
+    assert_eq!(encoded[..32], encode_word(U256::from(32))[..]);
+    assert_eq!(encoded[32..64], encode_word(U256::from(5))[..]);
+    assert_eq!(&encoded[64..69], b"value");
+    assert!(encoded[69..].iter().all(|byte| *byte == 0));
+    assert_eq!(encoded.len(), 96);
+    assert_eq!(encode_bytes(&[]).len(), 64);
+    assert_eq!(encode_bytes(&[7; 32]).len(), 96);
+    assert_eq!(encode_bytes(&[7; 33]).len(), 128);
+
+    // The gas of the output grows with its encoded length.
+    let output = encode_bytes(&[7; 40]);
+    let weight = Weight::from_ref_time(128 * WEIGHT_PER_BYTE);
+    assert_eq!(extension::bytes_weight(output.len()), weight);
+}
+
+#[test]
+fn signature_policies_restrict_signer_schemes() {
+    use crate::test_utils::sign_attribute_tx;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let alice = alice_pair.public();
+        let policy = |schemes| {
+            DID::set_signature_policy(RawOrigin::Signed(alice).into(), alice, schemes)
+        };
+        let signed = |name: &[u8]| {
+            sign_attribute_tx::<Test>(&alice_pair, &alice, name, b"value", 100)
+        };
+
+        assert_noop!(
+            DID::set_signature_policy(RawOrigin::Signed(account_key("Bob")).into(), alice, vec![]),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(policy(vec![KeyType::Ed25519, KeyType::Ecdsa, KeyType::Ed25519]));
+        System::assert_last_event(
+            crate::Event::SignaturePolicySet(alice, vec![KeyType::Ed25519, KeyType::Ecdsa]).into(),
+        );
+        assert_noop!(
+            DID::execute(RawOrigin::Signed(alice).into(), signed(b"name")),
+            Error::<Test>::SchemeNotAllowed
+        );
+
+        assert_ok!(policy(vec![KeyType::Sr25519]));
+        assert_ok!(DID::execute(RawOrigin::Signed(alice).into(), signed(b"name")));
+        assert_ok!(policy(vec![]));
+        assert_eq!(DID::signature_policy(alice), None);
+        assert_ok!(DID::execute(RawOrigin::Signed(alice).into(), signed(b"email")));
+    });
+}
+
+#[test]
+fn watchers_are_alerted_before_expiries() {
+    use crate::types::WatchedItem;
+    use frame_support::traits::Hooks;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let charlie = account_key("Charlie");
+        let watcher = account_key("Watcher");
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        for who in [bob, charlie, watcher] {
+            Balances::make_free_balance_be(&who, 100);
+        }
+
+        assert_ok!(DID::watch_identity(signed(watcher), alice));
+        assert_eq!(Balances::reserved_balance(watcher), 5);
+        assert_noop!(DID::watch_identity(signed(watcher), alice), Error::<Test>::AlreadyWatching);
+        assert_ok!(DID::watch_identity(signed(bob), alice));
+        assert_noop!(
+            DID::watch_identity(signed(charlie), alice),
+            Error::<Test>::TooManyWatchers
+        );
+        assert_ok!(DID::unwatch_identity(signed(bob), alice));
+        assert_eq!(Balances::reserved_balance(bob), 0);
+        assert_noop!(DID::unwatch_identity(signed(bob), alice), Error::<Test>::NotWatching);
+
+        // The owner decides who watches the identity.
+        assert_ok!(DID::watch_identity(signed(charlie), alice));
+        assert_noop!(
+            DID::remove_watcher(signed(bob), alice, charlie),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(DID::remove_watcher(signed(alice), alice, charlie));
+        System::assert_last_event(crate::Event::WatcherRemoved(alice, charlie).into());
+        assert_eq!(Balances::reserved_balance(charlie), 0);
+        assert_eq!(DID::watchers_of(alice), vec![watcher]);
+
+        let for_50 = Some(Validity::For(50));
+        assert_ok!(DID::add_delegate(
+            signed(alice),
+            alice,
+            bob,
+            b"SessionKey".to_vec(),
+            KeyType::Sr25519,
+            for_50
+        ));
+        let (name, value) = (b"name".to_vec(), b"Alice".to_vec());
+        assert_ok!(DID::add_attribute(signed(alice), alice, name, value, for_50));
+        assert_ok!(DID::revoke_attribute(signed(alice), alice, b"name".to_vec()));
+
+        // Alerted `ExpiryNotice` blocks before the expiry, unless revoked since.
+        System::set_block_number(41);
+        System::reset_events();
+        DID::on_initialize(41);
+        let item = WatchedItem::Delegate { delegate_type: b"SessionKey".to_vec(), delegate: bob };
+        assert_eq!(
+            System::events().into_iter().map(|r| r.event).collect::<Vec<_>>(),
+            vec![crate::Event::WatchedIdentityAlert(watcher, alice, item, 51).into()]
+        );
+        assert!(DID::expiry_alerts(41).is_empty());
+
+        // Alerts beyond `MaxExpiryAlerts` for a block are surfaced rather than lost silently.
+        for i in 0..16 {
+            let name = format!("name{}", i).into_bytes();
+            assert_ok!(DID::add_attribute(signed(alice), alice, name, b"v".to_vec(), for_50));
+        }
+        let last = b"last".to_vec();
+        assert_ok!(DID::add_attribute(signed(alice), alice, last.clone(), b"v".to_vec(), for_50));
+        let item = WatchedItem::Attribute { name: last };
+        System::assert_last_event(crate::Event::ExpiryAlertDropped(alice, item, 91).into());
+        assert_eq!(DID::expiry_alerts(81).len(), 16);
+    });
+}
+
+#[test]
+fn error_codes_are_stable_and_unique() {
+    use crate::error_codes::{error_code_of, ERROR_CODES};
+    use sp_runtime::DispatchError;
+
+    assert_eq!(Error::<Test>::NotOwner.error_code(), 1);
+    assert_eq!(Error::<Test>::TooManyWatchers.error_code(), 71);
+
+    // Every error has a code, and no code is used twice.
+    let mut codes: Vec<u16> = (0u8..)
+        .map_while(|index| Error::<Test>::decode(&mut &[index][..]).ok())
+        .map(|error| error.error_code())
+        .collect();
+    assert_eq!(codes.len(), ERROR_CODES.len());
+    assert!(!codes.contains(&0));
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), ERROR_CODES.len());
+
+    let dispatch_error: DispatchError = Error::<Test>::BadSignature.into();
+    assert_eq!(error_code_of::<Test>(&dispatch_error), Some((3, "BadSignature")));
+    assert_eq!(error_code_of::<Test>(&DispatchError::BadOrigin), None);
+}
+
+#[test]
+fn operation_cost_previews_deposits() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        Balances::make_free_balance_be(&alice, 100);
+        let set_profile = crate::Call::<Test>::set_profile {
+            identity: alice,
+            display_name: b"Alice".to_vec(),
+            avatar_hash: None,
+            bio_hash: None,
+        };
+        let (weight, deposit) = DID::operation_cost(&alice, &set_profile);
+        assert_eq!(weight, set_profile.get_dispatch_info().weight);
+        assert_eq!(deposit, 10);
+
+        // The deposit of an existing profile stays reserved from its depositor.
+        let origin = RawOrigin::Signed(alice).into();
+        assert_ok!(DID::set_profile(origin, alice, b"A".to_vec(), None, None));
+        assert_eq!(DID::operation_cost(&alice, &set_profile).1, 0);
+        assert_eq!(DID::operation_cost(&bob, &set_profile).1, 10);
+
+        let claim_handle =
+            crate::Call::<Test>::claim_handle { identity: alice, handle: b"a".to_vec() };
+        assert_eq!(DID::operation_cost(&alice, &claim_handle).1, 20);
+        let delegate_type = b"SessionKey".to_vec();
+        let heartbeat = crate::Call::<Test>::heartbeat { identity: alice, delegate_type };
+        assert_eq!(DID::operation_cost(&alice, &heartbeat).1, 0);
+    });
+}
+
+#[test]
+fn operation_cost_previews_the_watcher_deposit() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let watcher = account_key("Watcher");
+        Balances::make_free_balance_be(&watcher, 100);
+        let watch = crate::Call::<Test>::watch_identity { identity: alice };
+
+        let (_, deposit) = DID::operation_cost(&watcher, &watch);
+        assert_ok!(DID::watch_identity(RawOrigin::Signed(watcher).into(), alice));
+        assert_eq!(Balances::reserved_balance(watcher), deposit);
+        assert_eq!(deposit, 5);
+        // Watching again fails, reserving nothing.
+        assert_eq!(DID::operation_cost(&watcher, &watch).1, 0);
+    });
+}
+
+#[test]
+fn operation_cost_previews_the_attestation_deposit() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        RequireAttestationConsent::set(true);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        Balances::make_free_balance_be(&alice, 100);
+        assert_ok!(DID::claim_namespace(signed(alice), alice, b"kyc/".to_vec()));
+        assert_ok!(DID::set_namespace_opt_in(signed(bob), bob, b"kyc/".to_vec(), true));
+        let attest = |identity| crate::Call::<Test>::add_attribute {
+            identity,
+            name: b"kyc/level".to_vec(),
+            value: b"1".to_vec(),
+            valid_for: None,
+        };
+
+        // Claims about its own identity and unauthorized claims apply at once or fail.
+        assert_eq!(DID::operation_cost(&alice, &attest(alice)).1, 0);
+        assert_eq!(DID::operation_cost(&bob, &attest(alice)).1, 0);
+        let reserved = Balances::reserved_balance(alice);
+        let (_, deposit) = DID::operation_cost(&alice, &attest(bob));
+        assert_ok!(DID::add_attribute(signed(alice), bob, b"kyc/level".to_vec(), b"1".to_vec(), None));
+        assert_eq!(DID::pending_attestation_count(bob), 1);
+        assert_eq!(Balances::reserved_balance(alice) - reserved, deposit);
+        assert_eq!(deposit, 5);
+        RequireAttestationConsent::set(false);
+    });
+}
+
+#[test]
+fn import_identity_from_another_registry() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let charlie = account_key("Charlie");
+        let import = |origin: RuntimeOrigin, identity| {
+            DID::import_identity(
+                origin,
+                identity,
+                bob,
+                vec![(b"name".to_vec(), b"Alice".to_vec(), None)],
+                vec![(charlie, SIGNING_DELEGATE_TYPE.to_vec(), KeyType::Sr25519, None)],
+            )
+        };
+
+        assert_noop!(
+            import(RawOrigin::Signed(alice).into(), alice),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_ok!(import(RawOrigin::Root.into(), alice));
+        System::assert_last_event(crate::Event::IdentityImported(alice, bob, 1, 1).into());
+        assert_eq!(DID::owner_of(alice), Some(bob));
+        assert_ok!(DID::valid_attribute(&alice, b"name", b"Alice"));
+        assert_eq!(DID::attribute_provenance(&alice, b"name"), Some(Provenance::Imported));
+        assert_ok!(DID::valid_delegate(&alice, SIGNING_DELEGATE_TYPE, &charlie));
+
+        assert_noop!(import(RawOrigin::Root.into(), alice), Error::<Test>::DidAlreadyExists);
+    });
+}
+
+#[test]
+fn dependent_attributes_are_invalidated_with_their_parent() {
+    use frame_support::traits::Hooks;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        let employer = (alice, b"employer_attestation".to_vec());
+        let license = (alice, b"license".to_vec());
+        let value = b"engineer".to_vec();
+
+        let (name, acme) = (employer.1.clone(), b"ACME".to_vec());
+        assert_ok!(DID::add_attribute(signed(alice), alice, name, acme, None));
+        let (name, for_5) = (license.1.clone(), Some(Validity::For(5)));
+        assert_ok!(DID::add_attribute(signed(alice), alice, name, b"1".to_vec(), for_5));
+        for name in [&b"employment_role"[..], b"title", b"team"] {
+            assert_ok!(DID::add_attribute(signed(bob), bob, name.to_vec(), value.clone(), None));
+        }
+        let depend = |name: &[u8], parent: Option<(AccountId, Vec<u8>)>| {
+            DID::set_attribute_dependency(signed(bob), bob, name.to_vec(), parent)
+        };
+
+        // Dependents of the attributes of Alice need the consent of Alice.
+        assert_noop!(
+            depend(b"employment_role", Some(employer.clone())),
+            Error::<Test>::DependentNotAllowed
+        );
+        assert_noop!(
+            DID::allow_dependent(signed(bob), alice, employer.1.clone(), bob, true),
+            Error::<Test>::NotOwner
+        );
+        for parent in [&employer, &license] {
+            assert_ok!(DID::allow_dependent(signed(alice), alice, parent.1.clone(), bob, true));
+        }
+        assert_ok!(depend(b"employment_role", Some(employer.clone())));
+        assert_eq!(
+            System::events().last().unwrap().event,
+            crate::Event::AttributeDependencySet(
+                bob,
+                b"employment_role".to_vec(),
+                Some(employer.clone())
+            )
+            .into()
+        );
+        assert_ok!(depend(b"title", Some(employer.clone())));
+        assert_noop!(depend(b"team", Some(employer.clone())), Error::<Test>::TooManyDependents);
+        assert_noop!(
+            depend(b"team", Some((alice, b"missing".to_vec()))),
+            Error::<Test>::InvalidDependency
+        );
+        assert_noop!(
+            depend(b"team", Some((bob, b"team".to_vec()))),
+            Error::<Test>::InvalidDependency
+        );
+        // Dependencies are a single level deep.
+        assert_noop!(
+            depend(b"team", Some((bob, b"employment_role".to_vec()))),
+            Error::<Test>::InvalidDependency
+        );
+        assert_noop!(
+            DID::set_attribute_dependency(signed(bob), alice, license.1.clone(), None),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(depend(b"team", Some(license.clone())));
+        assert_ok!(DID::valid_attribute(&bob, b"employment_role", &value));
+
+        // Revoking the parent orphans its dependents.
+        System::reset_events();
+        assert_ok!(DID::revoke_attribute(signed(alice), alice, employer.1.clone()));
+        let invalidated = System::events().into_iter().find_map(|record| match record.event {
+            RuntimeEvent::DID(crate::Event::DependentAttributesInvalidated(i, name, mut d)) => {
+                d.sort();
+                Some((i, name, d))
+            }
+            _ => None,
+        });
+        let mut dependents = vec![(bob, b"employment_role".to_vec()), (bob, b"title".to_vec())];
+        dependents.sort();
+        assert_eq!(invalidated, Some((alice, employer.1.clone(), dependents)));
+        assert_noop!(
+            DID::valid_attribute(&bob, b"employment_role", &value),
+            Error::<Test>::InvalidAttribute
+        );
+        assert_eq!(
+            DID::verify_claims_batch(vec![(bob, b"title".to_vec(), value.clone())]),
+            vec![false]
+        );
+        assert_eq!(DID::attestation_status(&bob, b"title"), Some(AttestationStatus::Orphaned));
+
+        assert_ok!(depend(b"title", None));
+        assert_eq!(DID::attribute_dependency(bob, b"title".to_vec()), None);
+        assert_eq!(DID::attestation_status(&bob, b"title"), Some(AttestationStatus::Valid));
+
+        // Dependents are notified at the block their parent expires at.
+        System::set_block_number(6);
+        System::reset_events();
+        DID::on_initialize(6);
+        assert_eq!(
+            System::events().into_iter().map(|r| r.event).collect::<Vec<_>>(),
+            vec![crate::Event::DependentAttributesInvalidated(
+                alice,
+                license.1.clone(),
+                vec![(bob, b"team".to_vec())]
+            )
+            .into()]
+        );
+        assert_noop!(DID::valid_attribute(&bob, b"team", &value), Error::<Test>::InvalidAttribute);
+    });
+}
+
+#[test]
+fn cosigned_attributes_require_the_cosigner_signature() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let officer_pair = account_pair("Officer");
+        let officer = officer_pair.public();
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        let (name, value) = (b"kyc_level".to_vec(), b"2".to_vec());
+
+        assert_noop!(
+            DID::set_attribute_cosigner(signed(officer), alice, name.clone(), Some(officer)),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(DID::set_attribute_cosigner(signed(alice), alice, name.clone(), Some(officer)));
+        System::assert_last_event(
+            crate::Event::AttributeCosignerSet(alice, name.clone(), Some(officer)).into(),
+        );
+        assert_noop!(
+            DID::add_attribute(signed(alice), alice, name.clone(), value.clone(), None),
+            Error::<Test>::CosignatureRequired
+        );
+        assert_noop!(
+            DID::add_cosigned_attribute(
+                signed(alice),
+                alice,
+                b"other".to_vec(),
+                value.clone(),
+                None,
+                officer,
+                officer_pair.sign(b"other"),
+            ),
+            Error::<Test>::NotCosigned
+        );
+
+        let payload = DID::cosignature_payload(&alice, &name, &value, None);
+        let cosign = |signature| {
+            DID::add_cosigned_attribute(
+                signed(alice),
+                alice,
+                name.clone(),
+                value.clone(),
+                None,
+                officer,
+                signature,
+            )
+        };
+        assert_noop!(cosign(account_pair("Alice").sign(&payload)), Error::<Test>::BadSignature);
+        assert_ok!(cosign(officer_pair.sign(&payload)));
+        assert_ok!(DID::valid_attribute(&alice, &name, &value));
+
+        // Approvals can not be replayed once the attribute is deleted.
+        assert_ok!(DID::delete_attribute(signed(alice), alice, name.clone()));
+        assert_noop!(cosign(officer_pair.sign(&payload)), Error::<Test>::BadSignature);
+
+        // Only the owner of the co-signer lifts the requirement.
+        assert_noop!(
+            DID::set_attribute_cosigner(signed(alice), alice, name.clone(), None),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(DID::set_attribute_cosigner(signed(officer), alice, name.clone(), None));
+        assert_ok!(DID::add_attribute(signed(alice), alice, name, value, None));
+    });
+}
+
+#[test]
+fn enumeration_apis_page_with_cursors() {
+    use crate::types::Page;
+
+    // Collects all the items from the first page on, checking that pages respect `max`.
+    fn collect<Item>(mut page: impl FnMut(Option<Vec<u8>>) -> Page<Item>) -> Vec<Item> {
+        let (mut items, mut cursor) = (Vec::new(), None);
+        loop {
+            let Page { items: page_items, next } = page(cursor);
+            assert!(page_items.len() <= 2);
+            items.extend(page_items);
+            match next {
+                Some(next) => cursor = Some(next),
+                None => return items,
+            }
+        }
+    }
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+
+        let names: Vec<Vec<u8>> = (0..5u8).map(|i| vec![b'a' + i]).collect();
+        for name in &names {
+            assert_ok!(DID::add_attribute(signed(alice), alice, name.clone(), b"v".to_vec(), None));
+        }
+        assert_ok!(DID::delete_attribute(signed(alice), alice, names[0].clone()));
+        let mut attributes = collect(|cursor| DID::attributes_of(&alice, cursor, 2));
+        attributes.sort();
+        let expected: Vec<_> =
+            names[1..].iter().map(|n| (n.clone(), b"v".to_vec(), 10_001)).collect();
+        assert_eq!(attributes, expected);
+
+        let delegates: Vec<AccountId> =
+            ["Charlie", "Dave", "Eve"].into_iter().map(account_key).collect();
+        for delegate in &delegates {
+            let delegate_type = b"SessionKey".to_vec();
+            assert_ok!(DID::add_delegate(
+                signed(alice),
+                alice,
+                *delegate,
+                delegate_type,
+                KeyType::Sr25519,
+                None
+            ));
+        }
+        let (charlie, dave) = (delegates[0], delegates[1]);
+        let session_key = b"SessionKey".to_vec();
+        let (for_bob, key) = (signed(bob), KeyType::Sr25519);
+        assert_ok!(DID::add_delegate(for_bob, bob, charlie, session_key, key, None));
+        let mut found: Vec<_> = collect(|cursor| DID::delegates_of(&alice, cursor, 2))
+            .into_iter()
+            .map(|(_, delegate, record)| (delegate, record.key_type))
+            .collect();
+        found.sort();
+        let mut expected: Vec<_> = delegates.iter().map(|d| (*d, KeyType::Sr25519)).collect();
+        expected.sort();
+        assert_eq!(found, expected);
+        // Only the delegates of the identity are visited, so pages are full.
+        assert_eq!(DID::delegates_of(&alice, None, 2).items.len(), 2);
+
+        for identity in [alice, bob, charlie] {
+            assert_ok!(DID::change_owner(signed(identity), identity, dave));
+        }
+        let mut owned = collect(|cursor| DID::identities_of_owner(&dave, cursor, 2));
+        owned.sort();
+        let mut expected = vec![alice, bob, charlie];
+        expected.sort();
+        assert_eq!(owned, expected);
+        assert_eq!(DID::identities_of_owner(&alice, None, 10).items, vec![]);
+        let eve = delegates[2];
+        assert_ok!(DID::change_owner(signed(dave), alice, eve));
+        assert_eq!(DID::identities_of_owner(&eve, None, 10).items, vec![alice]);
+        assert_eq!(DID::identities_of_owner(&dave, None, 10).items.len(), 2);
+    });
+}
+
+#[test]
+fn dry_run_execute_checks_without_writing_state() {
+    use crate::test_utils::sign_attribute_tx;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let alice = alice_pair.public();
+        let transaction = sign_attribute_tx::<Test>(&alice_pair, &alice, b"email", b"a@b.c", 100);
+
+        let info = DID::dry_run_execute(transaction.clone()).unwrap();
+        let call = crate::Call::<Test>::execute { transaction: transaction.clone() };
+        assert_eq!(info.actual_weight, Some(call.get_dispatch_info().weight));
+        assert_eq!(DID::next_meta_nonce(&alice), 0);
+        assert!(DID::attribute_and_id(&alice, b"email").is_none());
+        assert!(System::events().is_empty());
+
+        let mut forged = transaction.clone();
+        forged.value = b"x@y.z".to_vec();
+        assert_eq!(DID::dry_run_execute(forged), Err(Error::<Test>::BadSignature.into()));
+
+        // Replays are rejected once the transaction is executed.
+        let relayer = RawOrigin::Signed(account_key("Relayer")).into();
+        assert_ok!(DID::execute(relayer, transaction.clone()));
+        assert_eq!(DID::dry_run_execute(transaction), Err(Error::<Test>::BadSignature.into()));
+    });
+}
+
+#[test]
+fn random_challenges_authenticate_identities() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let verifier = account_key("Verifier");
+        let alice_pair = account_pair("Alice");
+        let alice = alice_pair.public();
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        let challenge_of = || match System::events().last().unwrap().event {
+            RuntimeEvent::DID(crate::Event::ChallengeIssued(_, challenge, expiry)) => {
+                (challenge, expiry)
+            }
+            _ => panic!("no challenge issued"),
+        };
+
+        assert_noop!(
+            DID::request_challenge(signed(alice), verifier),
+            Error::<Test>::InvalidDelegate
+        );
+        assert_ok!(DID::request_challenge(signed(verifier), verifier));
+        let (challenge, expiry) = challenge_of();
+        assert_eq!(expiry, 11);
+        assert!(DID::valid_presentation_nonce(&verifier, &challenge));
+        // Every challenge is unique.
+        assert_ok!(DID::request_challenge(signed(verifier), verifier));
+        assert_ne!(challenge_of().0, challenge);
+
+        // The bare challenge is not signed, the domain tagged payload is.
+        let verify = DID::verify_challenge_response;
+        let bare = alice_pair.sign(&challenge);
+        assert!(verify(&verifier, &challenge, &alice, &alice, &bare).is_err());
+        let payload = [&b"did/challenge-response"[..], verifier.as_ref(), &challenge].concat();
+        assert_eq!(DID::challenge_response_payload(&verifier, &challenge), payload);
+        let signature = alice_pair.sign(&payload);
+        assert_ok!(verify(&verifier, &challenge, &alice, &alice, &signature));
+        let bob = account_key("Bob");
+        assert_noop!(
+            DID::verify_challenge_response(&verifier, &challenge, &bob, &alice, &signature),
+            Error::<Test>::InvalidDelegate
+        );
+
+        assert_ok!(DID::consume_presentation_nonce(signed(verifier), verifier, challenge));
+        assert_noop!(
+            DID::verify_challenge_response(&verifier, &challenge, &alice, &alice, &signature),
+            Error::<Test>::UnknownPresentationNonce
+        );
+    });
+}
+
+#[test]
+fn attribute_writes_are_timestamped() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        Timestamp::set_timestamp(6_000);
+        let alice = account_key("Alice");
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        let (name, value) = (b"name".to_vec(), b"Alice".to_vec());
+        let timestamped = |block, moment| -> RuntimeEvent {
+            crate::Event::AttributeTimestamped(alice, name.clone(), block, moment).into()
+        };
+
+        assert_ok!(DID::add_attribute(signed(alice), alice, name.clone(), value.clone(), None));
+        assert!(System::events().into_iter().any(|r| r.event == timestamped(1, 6_000)));
+
+        System::set_block_number(3);
+        Timestamp::set_timestamp(18_000);
+        assert_ok!(DID::valid_attribute_fresh_by_time(&alice, &name, &value, 12_000));
+        assert_noop!(
+            DID::valid_attribute_fresh_by_time(&alice, &name, &value, 11_999),
+            Error::<Test>::AttributeTooOld
+        );
+
+        assert_ok!(DID::revoke_attribute(signed(alice), alice, name.clone()));
+        assert!(System::events().into_iter().any(|r| r.event == timestamped(3, 18_000)));
+        let metadata = DID::attribute_metadata(&alice, &name).unwrap();
+        assert_eq!((metadata.created_at, metadata.updated_at), ((1, 6_000), (3, 18_000)));
+    });
+}
+
+#[test]
+fn signers_of_keyless_owners_sign_for_their_identities() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let treasury = TreasuryAccount::get();
+        let org = account_key("Org");
+        let alice_pair = account_pair("Alice");
+        let alice = alice_pair.public();
+        let msg = b"pay invoice 42".to_vec();
+        let signature = alice_pair.sign(&msg);
+
+        // The root origin acts as the keyless treasury account.
+        assert_ok!(DID::change_owner(RawOrigin::Signed(org).into(), org, treasury));
+        assert_ok!(DID::add_own_delegate(
+            RawOrigin::Root.into(),
+            alice,
+            SIGNING_DELEGATE_TYPE.to_vec(),
+            KeyType::Sr25519,
+            None
+        ));
+        assert_noop!(
+            DID::valid_signer(&org, &signature, &msg, &alice),
+            Error::<Test>::InvalidDelegate
+        );
+
+        assert_ok!(DID::set_keyless(RawOrigin::Root.into(), true));
+        System::assert_last_event(crate::Event::KeylessAccountSet(treasury, true).into());
+        assert_ok!(DID::valid_signer(&org, &signature, &msg, &alice));
+        assert_noop!(
+            DID::valid_signer(&org, &account_pair("Bob").sign(&msg), &msg, &alice),
+            Error::<Test>::BadSignature
+        );
+
+        assert_ok!(DID::set_keyless(RawOrigin::Root.into(), false));
+        assert_noop!(
+            DID::valid_signer(&org, &signature, &msg, &alice),
+            Error::<Test>::InvalidDelegate
+        );
+    });
+}
+
+#[test]
+fn storage_usage_is_capped_per_identity() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        MaxBytesPerDid::set(80);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        let value = vec![7u8; 28];
+
+        assert_ok!(DID::add_attribute(signed(alice), alice, b"name".to_vec(), value.clone(), None));
+        assert_eq!(DID::storage_usage(alice), 32);
+        let session_key = b"SessionKey".to_vec();
+        let key = KeyType::Sr25519;
+        assert_ok!(DID::add_delegate(signed(alice), alice, bob, session_key.clone(), key, None));
+        assert_eq!(DID::storage_usage(alice), 74);
+
+        assert_noop!(
+            DID::add_attribute(signed(alice), alice, b"mail".to_vec(), value.clone(), None),
+            Error::<Test>::StorageQuotaExceeded
+        );
+        // Revoked delegates are overwritten, without counting them twice.
+        assert_ok!(DID::revoke_delegate(signed(alice), alice, session_key.clone(), bob));
+        assert_ok!(DID::add_delegate(signed(alice), alice, bob, session_key, key, None));
+        assert_eq!(DID::storage_usage(alice), 74);
+
+        assert_ok!(DID::delete_attribute(signed(alice), alice, b"name".to_vec()));
+        assert_eq!(DID::storage_usage(alice), 42);
+        assert_ok!(DID::add_attribute(signed(alice), alice, b"mail".to_vec(), value, None));
+        assert_eq!(DID::storage_usage(alice), 74);
+    });
+}
+
+#[test]
+fn writes_signed_for_an_identity_fill_its_storage_quota() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let relayer = account_key("Dave");
+        let (signer_pair, custodian_pair) = (account_pair("Bob"), account_pair("Charlie"));
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        for (pair, delegate_type) in [
+            (&signer_pair, SIGNING_DELEGATE_TYPE),
+            (&custodian_pair, CAPABILITY_INVOCATION_DELEGATE_TYPE),
+        ] {
+            let (delegate, delegate_type) = (pair.public(), delegate_type.to_vec());
+            let key = KeyType::Sr25519;
+            assert_ok!(DID::add_delegate(signed(alice), alice, delegate, delegate_type, key, None));
+        }
+        let usage = DID::storage_usage(alice);
+        MaxBytesPerDid::set(usage + 20);
+        let execute = |pair: &sr25519::Pair, name: &[u8]| {
+            let transaction = sign_attribute_tx::<Test>(pair, &alice, name, b"value!", 10);
+            DID::execute(signed(relayer), transaction)
+        };
+
+        // Both delegates write for Alice, every write counted whoever relays it.
+        assert_ok!(execute(&signer_pair, b"name"));
+        assert_ok!(execute(&custodian_pair, b"mail"));
+        assert_eq!(DID::storage_usage(alice), usage + 20);
+        assert_noop!(execute(&signer_pair, b"city"), Error::<Test>::StorageQuotaExceeded);
+        assert_noop!(execute(&custodian_pair, b"city"), Error::<Test>::StorageQuotaExceeded);
+        assert_eq!(DID::storage_usage(relayer), 0);
+    });
+}
+
+#[test]
+fn third_party_writes_are_not_counted_in_the_storage_usage() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let alice = account_key("Alice");
+        let bob = account_key("Bob");
+        let charlie = account_key("Charlie");
+        let signed = |who| -> RuntimeOrigin { RawOrigin::Signed(who).into() };
+        Balances::make_free_balance_be(&alice, 100);
+        assert_ok!(DID::claim_namespace(signed(alice), alice, b"kyc/".to_vec()));
+        assert_ok!(DID::set_namespace_opt_in(signed(bob), bob, b"kyc/".to_vec(), true));
+
+        let name = b"kyc/level".to_vec();
+        assert_ok!(DID::add_attribute(signed(alice), bob, name.clone(), b"2".to_vec(), None));
+        assert_eq!(DID::storage_usage(bob), 0);
+        assert_eq!(DID::storage_usage(alice), 0);
+        assert_ok!(DID::add_attribute(signed(bob), bob, b"name".to_vec(), b"Bob".to_vec(), None));
+        assert_eq!(DID::storage_usage(bob), 7);
+        // Deleting an uncounted attribute leaves the usage of the identity as is.
+        assert_ok!(DID::delete_attribute(signed(bob), bob, name));
+        assert_eq!(DID::storage_usage(bob), 7);
+
+        assert_ok!(DID::import_identity(
+            RawOrigin::Root.into(),
+            charlie,
+            charlie,
+            vec![(b"name".to_vec(), b"Charlie".to_vec(), None)],
+            vec![(bob, SIGNING_DELEGATE_TYPE.to_vec(), KeyType::Sr25519, None)],
+        ));
+        assert_eq!(DID::storage_usage(charlie), 0);
+    });
+}
@@ -1,27 +1,384 @@
-use codec::{Decode, Encode};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{traits::Get, BoundedVec, CloneNoBound, EqNoBound, PartialEqNoBound, RuntimeDebugNoBound};
 use scale_info::TypeInfo;
-use sp_core::RuntimeDebug;
+use sp_core::{ecdsa, ed25519, sr25519, RuntimeDebug, H256};
+use sp_runtime::MultiSignature;
 use sp_std::vec::Vec;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+/// Prefix of names reserved to the DID method internals.
+pub const RESERVED_NAME_PREFIX: &[u8] = b"did:";
+
+/// Maximum length in bytes of attribute names and delegate types.
+pub const MAX_NAME_LENGTH: usize = 64;
+
+/// Delegate type of the keys allowed to sign on behalf of an identity.
+pub const SIGNING_DELEGATE_TYPE: &[u8] = b"x25519VerificationKey2022";
+
+/// Delegate type of the key agreement keys an attribute value can be encrypted for.
+pub const KEY_AGREEMENT_DELEGATE_TYPE: &[u8] = b"X25519KeyAgreementKey2019";
+
+/// Delegate type of the management keys allowed to sign off-chain attribute transactions.
+pub const CAPABILITY_INVOCATION_DELEGATE_TYPE: &[u8] = b"CapabilityInvocation";
+
+/// Delegate type of the guardians vouching for the recovery of an identity accounts.
+pub const RECOVERY_GUARDIAN_DELEGATE_TYPE: &[u8] = b"RecoveryGuardian";
+
+/// Delegate type of the contracts authorizing callers through their `is_authorized` query.
+pub const CONTRACT_DELEGATE_TYPE: &[u8] = b"ContractDelegate";
+
+/// Domain tag of the payload signed to answer a challenge, so the signature of a challenge can
+/// not be replayed as another message, nor to another verifier.
+pub const CHALLENGE_RESPONSE_TAG: &[u8; 22] = b"did/challenge-response";
+
+/// Kind of value stored in an attribute.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Encode, Decode, Default, TypeInfo, MaxEncodedLen, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum AttributeKind {
+    /// Opaque bytes.
+    #[default]
+    Plain,
+    /// A SCALE encoded `EncryptionEnvelope`.
+    Encrypted,
+}
+
+/// Attribute value encrypted for a key agreement key of the identity.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Default, TypeInfo, RuntimeDebug)]
+pub struct EncryptionEnvelope<AccountId> {
+    /// The key agreement delegate holding the decryption key.
+    pub recipient: AccountId,
+    pub ciphertext: Vec<u8>,
+}
 
 /// Attributes or properties that make an identity.
 #[derive(PartialEq, Eq, PartialOrd, Ord, TypeInfo, Clone, Encode, Decode, Default, RuntimeDebug)]
 pub struct Attribute<BlockNumber, Moment> {
     pub name: Vec<u8>,
+    /// Raw value, or a SCALE encoded `EncryptionEnvelope` for `Encrypted` attributes.
     pub value: Vec<u8>,
+    /// First block the attribute is no longer valid at.
     pub validity: BlockNumber,
+    /// Time of creation.
     pub creation: Moment,
+    /// Nonce of the attribute name when created, part of its `id`.
     pub nonce: u64,
+    pub kind: AttributeKind,
+}
+
+/// Blocks and times an attribute was created and last updated at.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, Default, TypeInfo, MaxEncodedLen, RuntimeDebug)]
+pub struct AttributeMetadata<BlockNumber, Moment> {
+    pub created_at: (BlockNumber, Moment),
+    /// Creation or revocation of the attribute.
+    pub updated_at: (BlockNumber, Moment),
 }
 
+/// Validity period of a delegate or an attribute, from the block it is added at.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+pub enum Validity<BlockNumber> {
+    /// Valid for the given number of blocks.
+    For(BlockNumber),
+    /// Never expires. Stored as a `validity` of the maximum block number.
+    Never,
+}
+
+/// Credential issued on chain to a subject, the claim itself is shared off chain.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, TypeInfo, MaxEncodedLen, RuntimeDebug)]
+pub struct Credential<AccountId, BlockNumber, Moment> {
+    pub issuer: AccountId,
+    /// Hash identifying the schema of the claim.
+    pub schema_id: [u8; 32],
+    /// Hash of the claim, to verify the claim presented by the subject against.
+    pub claim_hash: [u8; 32],
+    /// Block and time of the issuance.
+    pub issued_at: (BlockNumber, Moment),
+    /// First block the credential is no longer valid at.
+    pub expiry: BlockNumber,
+}
+
+/// Third-party claim awaiting the consent of its subject.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub enum PendingAttestation<AccountId, BlockNumber, Moment> {
+    /// Attribute attested under a claimed namespace, valid from its acceptance.
+    Attribute {
+        issuer: AccountId,
+        name: Vec<u8>,
+        value: Vec<u8>,
+        kind: AttributeKind,
+        valid_for: Option<Validity<BlockNumber>>,
+    },
+    /// Credential, expiring as issued.
+    Credential(Credential<AccountId, BlockNumber, Moment>),
+}
+
+/// Cryptographic scheme of a delegate public key.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Encode, Decode, Default, TypeInfo, MaxEncodedLen, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum KeyType {
+    #[default]
+    Sr25519,
+    Ed25519,
+    Ecdsa,
+    X25519,
+}
+
+/// Scheme of a signature, enforced by the signature policies of the identities.
+pub trait SignatureScheme {
+    fn key_type(&self) -> KeyType;
+}
+
+impl SignatureScheme for sr25519::Signature {
+    fn key_type(&self) -> KeyType {
+        KeyType::Sr25519
+    }
+}
+
+impl SignatureScheme for ed25519::Signature {
+    fn key_type(&self) -> KeyType {
+        KeyType::Ed25519
+    }
+}
+
+impl SignatureScheme for ecdsa::Signature {
+    fn key_type(&self) -> KeyType {
+        KeyType::Ecdsa
+    }
+}
+
+impl SignatureScheme for MultiSignature {
+    fn key_type(&self) -> KeyType {
+        match self {
+            MultiSignature::Sr25519(_) => KeyType::Sr25519,
+            MultiSignature::Ed25519(_) => KeyType::Ed25519,
+            MultiSignature::Ecdsa(_) => KeyType::Ecdsa,
+        }
+    }
+}
+
+/// Delegate of an identity, valid until the `validity` block.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Default, TypeInfo, MaxEncodedLen, RuntimeDebug)]
+pub struct Delegate<BlockNumber> {
+    /// First block the delegate is no longer valid at.
+    pub validity: BlockNumber,
+    /// Scheme of the delegate public key, i.e. of its account.
+    pub key_type: KeyType,
+}
+
+/// An attribute with its `id`.
 pub type AttributedId<BlockNumber, Moment> = (Attribute<BlockNumber, Moment>, [u8; 32]);
 
 /// Off-chain signed transaction.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Default, TypeInfo, RuntimeDebug)]
 pub struct AttributeTransaction<Signature, AccountId> {
+    /// Signature of the `meta_transaction_payload` by the `signer`.
     pub signature: Signature,
     pub name: Vec<u8>,
+    /// Ignored when revoking.
     pub value: Vec<u8>,
+    /// Validity in blocks of the new attribute, `0` to revoke the attribute.
     pub validity: u32,
+    /// Owner, signing delegate or capability invocation delegate of the identity.
     pub signer: AccountId,
     pub identity: AccountId,
 }
+
+/// Stable identifiers of the pallet calls, equal to their call index.
+///
+/// Runtime call filters can match on these instead of the call variants,
+/// e.g. to disable meta-transactions during an incident.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+#[repr(u8)]
+pub enum Callset {
+    ChangeOwner = 0,
+    AddDelegate = 1,
+    RevokeDelegate = 2,
+    AddAttribute = 3,
+    RevokeAttribute = 4,
+    DeleteAttribute = 5,
+    Execute = 6,
+    CreateOrgDid = 7,
+    AddEncryptedAttribute = 8,
+    AnchorHash = 9,
+    RenewDelegates = 10,
+    AddOwnAttribute = 11,
+    DeleteOwnAttribute = 12,
+    AddOwnDelegate = 13,
+    RegisterDid = 14,
+    Pause = 15,
+    Unpause = 16,
+    Heartbeat = 17,
+    RegisterPresentationNonce = 18,
+    ConsumePresentationNonce = 19,
+    SetAccessPrice = 20,
+    PurchaseAccess = 21,
+    ConfirmAccess = 22,
+    RefundAccess = 23,
+    ImportForeignAttestation = 24,
+    SetRecoveryThreshold = 25,
+    VouchRecovery = 26,
+    ClearRecoveryVouches = 27,
+    SetProfile = 28,
+    ClearProfile = 29,
+    ReportIssuerMisbehavior = 30,
+    ClaimNamespace = 31,
+    TransferNamespace = 32,
+    ReleaseNamespace = 33,
+    SetAccessCounter = 34,
+    LogAccess = 35,
+    ClaimHandle = 36,
+    UnclaimHandle = 37,
+    IssueCredential = 38,
+    AcceptAttestation = 39,
+    RejectAttestation = 40,
+    RevokeDelegatesOfType = 41,
+    BanAttributeName = 42,
+    UnbanAttributeName = 43,
+    FlagDormant = 44,
+    ReapIdentity = 45,
+    SetSignaturePolicy = 46,
+    WatchIdentity = 47,
+    UnwatchIdentity = 48,
+    ImportIdentity = 49,
+    SetAttributeDependency = 50,
+    SetAttributeCosigner = 51,
+    AddCosignedAttribute = 52,
+    RequestChallenge = 53,
+    SetKeyless = 54,
+    SetNamespaceOptIn = 55,
+    RevokeCredential = 56,
+    RemoveWatcher = 57,
+    AllowDependent = 58,
+}
+
+impl Callset {
+    /// The call index of the call.
+    pub fn call_index(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns `true` for the calls relaying off-chain signed transactions.
+    pub fn is_meta_transaction(self) -> bool {
+        matches!(self, Callset::Execute)
+    }
+
+    /// Returns `true` for the calls of the `AdminOrigin`.
+    pub fn is_admin(self) -> bool {
+        matches!(
+            self,
+            Callset::Pause
+                | Callset::Unpause
+                | Callset::ReportIssuerMisbehavior
+                | Callset::BanAttributeName
+                | Callset::UnbanAttributeName
+                | Callset::ImportIdentity
+        )
+    }
+
+    /// Returns `true` for the calls acting on the account itself rather than on its DID: its
+    /// recovery, its keyless status and the payments from it.
+    pub fn is_account_control(self) -> bool {
+        matches!(
+            self,
+            Callset::SetRecoveryThreshold
+                | Callset::VouchRecovery
+                | Callset::ClearRecoveryVouches
+                | Callset::SetKeyless
+                | Callset::PurchaseAccess
+        )
+    }
+
+    /// Returns `true` for the revocations, dispatched as `Operational` so they
+    /// are included even when blocks are full, and accepted during migrations.
+    pub fn is_revocation(self) -> bool {
+        matches!(
+            self,
+            Callset::RevokeDelegate
+                | Callset::RevokeAttribute
+                | Callset::RevokeDelegatesOfType
+                | Callset::RevokeCredential
+        )
+    }
+}
+
+/// Read paths with a benchmark, priced by the `query_cost` runtime API.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+pub enum ReadQuery {
+    ValidSigner,
+    ValidAttribute,
+    /// Document assembly, iterating over `delegates` stored delegates.
+    DidDocument { delegates: u32 },
+}
+
+/// Origin of an attribute.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, Default, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+pub enum Provenance {
+    /// Written on this chain.
+    #[default]
+    Local,
+    /// Imported from a storage proof of a foreign consensus.
+    Foreign { relay_block: u32, state_root: H256 },
+    /// Imported by the `AdminOrigin` from another DID registry.
+    Imported,
+}
+
+/// Status of an attestation, i.e. an attribute, for its consumers.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+pub enum AttestationStatus {
+    Valid,
+    Expired,
+    /// Written by an issuer suspended for misbehavior, e.g. a compromised key.
+    Disputed,
+    /// Depends on an attribute revoked, deleted or expired.
+    Orphaned,
+}
+
+/// Delegate or attribute of a watched identity its watchers are alerted about.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub enum WatchedItem<AccountId> {
+    Delegate { delegate_type: Vec<u8>, delegate: AccountId },
+    Attribute { name: Vec<u8> },
+}
+
+/// Activity of an identity under the dormancy policy of the chain.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+pub enum ActivityStatus<BlockNumber> {
+    Active,
+    /// Without activity for the `DormancyPeriod`, anyone can flag it dormant.
+    Inactive,
+    /// Flagged dormant at the block, reapable after the `ReapGracePeriod`.
+    Dormant(BlockNumber),
+}
+
+/// Minimal human-readable profile of an identity, for wallets and explorers.
+#[derive(
+    CloneNoBound, PartialEqNoBound, EqNoBound, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebugNoBound,
+)]
+#[scale_info(skip_type_params(MaxDisplayNameLength))]
+#[codec(mel_bound(AccountId: MaxEncodedLen, Balance: MaxEncodedLen))]
+pub struct Profile<AccountId, Balance, MaxDisplayNameLength>
+where
+    AccountId: Clone + PartialEq + Eq + core::fmt::Debug,
+    Balance: Clone + PartialEq + Eq + core::fmt::Debug,
+    MaxDisplayNameLength: Get<u32>,
+{
+    pub display_name: BoundedVec<u8, MaxDisplayNameLength>,
+    /// Hash of the avatar image, stored off chain.
+    pub avatar_hash: Option<[u8; 32]>,
+    /// Hash of the biography, stored off chain.
+    pub bio_hash: Option<[u8; 32]>,
+    /// Account the deposit is reserved from.
+    pub depositor: AccountId,
+    pub deposit: Balance,
+}
+
+/// Maximum number of storage entries a page of an enumeration API visits.
+pub const MAX_PAGE_SIZE: u32 = 1_000;
+
+/// Page of an enumeration API, with the cursor of the next page unless it is the last one.
+/// The cursor is opaque to clients, it is the raw storage key of the last visited entry.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub struct Page<Item> {
+    pub items: Vec<Item>,
+    pub next: Option<Vec<u8>>,
+}
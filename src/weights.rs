@@ -0,0 +1,61 @@
+//! Weights of the read paths of the pallet, used to price runtime API and RPC queries.
+//!
+//! The values of `SubstrateWeight` and `()` are estimates, not benchmark results: runtimes must
+//! regenerate `SubstrateWeight` with `cargo run --release --features runtime-benchmarks -- benchmark
+//! pallet --pallet pallet_did --extrinsic '*'` on their reference hardware before relying on them.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{constants::RocksDbWeight, Weight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_did.
+pub trait WeightInfo {
+    fn valid_signer() -> Weight;
+    fn valid_attribute() -> Weight;
+    fn did_document(d: u32) -> Weight;
+}
+
+/// Estimated weights for pallet_did, to regenerate with the benchmarks of the pallet.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+    // Storage: DID OwnerOf (r:1 w:0)
+    // Storage: DID DelegateOf (r:2 w:0)
+    fn valid_signer() -> Weight {
+        Weight::from_ref_time(52_000_000)
+            .saturating_add(T::DbWeight::get().reads(3))
+    }
+    // Storage: DID AttributeNonce (r:1 w:0)
+    // Storage: DID AttributeOf (r:1 w:0)
+    fn valid_attribute() -> Weight {
+        Weight::from_ref_time(14_000_000)
+            .saturating_add(T::DbWeight::get().reads(2))
+    }
+    // Storage: DID OwnerOf (r:1 w:0)
+    // Storage: DID DelegateOf (r:1 w:0)
+    fn did_document(d: u32) -> Weight {
+        Weight::from_ref_time(18_000_000)
+            .saturating_add(Weight::from_ref_time(9_000_000).saturating_mul(d.into()))
+            .saturating_add(T::DbWeight::get().reads(1))
+            .saturating_add(T::DbWeight::get().reads(d.into()))
+    }
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+    fn valid_signer() -> Weight {
+        Weight::from_ref_time(52_000_000)
+            .saturating_add(RocksDbWeight::get().reads(3))
+    }
+    fn valid_attribute() -> Weight {
+        Weight::from_ref_time(14_000_000)
+            .saturating_add(RocksDbWeight::get().reads(2))
+    }
+    fn did_document(d: u32) -> Weight {
+        Weight::from_ref_time(18_000_000)
+            .saturating_add(Weight::from_ref_time(9_000_000).saturating_mul(d.into()))
+            .saturating_add(RocksDbWeight::get().reads(1))
+            .saturating_add(RocksDbWeight::get().reads(d.into()))
+    }
+}